@@ -17,6 +17,7 @@ REQ_URL="http://localhost:3012/api/v1/chnot-query"
 HEADERS=(
 "Content-Type: application/json"
 "K-namespace: public"
+"Authorization: Bearer ${CHNOTS_TOKEN}"
 )
 
 # body
@@ -1,18 +1,106 @@
-use chin_tools::wrapper::anyhow::AResult;
-use std::{ops::Deref, sync::Arc};
+use anyhow::Context;
+use arc_swap::ArcSwap;
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{FixedOffset, Local, TimeDelta};
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Deref,
+    str::FromStr,
+    sync::Arc,
+};
+use tracing::{error, info};
 
 use crate::{
     config::Config,
-    mapper::{ChnotMapper, MapperType},
-    model::dto::{
-        chnot::{Chnot, ChnotQueryReq, ChnotQueryRsp},
-        KReq,
+    error::KError,
+    mapper::{
+        query::ChnotQuery, AclMapper, ApiTokenMapper, AuditLogMapper, ChnotLinkMapper, ChnotMapper,
+        ChnotTagMapper, ChnotTrashMapper, KVMapper, LLMChatMapper, MapperType, NamespaceMapper,
+        ResourceMapper, ShareLinkMapper, UserMapper,
+    },
+    model::{
+        db::{
+            acl::{AclSubjectKind, NamespaceAcl, NamespaceRole, UserGroup, UserGroupMember},
+            audit::{AuditAction, AuditEntityType, AuditLog, GLOBAL_NAMESPACE, SYSTEM_ACTOR},
+            chnot::{ChnotKind, ChnotMetadata, ChnotRecord},
+            llmchat::LLMChatSession,
+            namespace::NamespaceRecord,
+            resource::Resource,
+            share::{ShareLink, ShareTargetKind},
+            user::{ApiToken, User, UserSession},
+        },
+        dto::{
+            audit::{AuditLogQueryReq, AuditLogQueryRsp},
+            chnot::{
+                Chnot, ChnotActivityReq, ChnotActivityRsp, ChnotArchiveFilter, ChnotBacklinksReq,
+                ChnotBacklinksRsp, ChnotDeletionReq, ChnotDeletionRsp, ChnotDiffReq, ChnotDiffRsp,
+                ChnotGraphEdge, ChnotGraphNode, ChnotGraphReq, ChnotGraphRsp, ChnotHighlight,
+                ChnotHistoryReq, ChnotMoveItem, ChnotMoveReq, ChnotMoveResult, ChnotMoveRsp,
+                ChnotMoveStatus, ChnotOverwriteReq, ChnotOverwriteRsp, ChnotQueryReq,
+                ChnotQueryRsp, ChnotRestoreReq, ChnotRestoreRsp, ChnotSearchMode, ChnotSearchReq,
+                ChnotSort, ChnotTagRenameReq, ChnotTagRenameRsp, ChnotTimeRange,
+                ChnotTrashRestoreReq, ChnotUpdateReq,
+            },
+            kv::{KVDeleteReq, KVDeleteRsp, KVOverwriteReq, KVOverwriteRsp},
+            llmchat::{
+                LLMChatDeleteBotReq, LLMChatDeleteBotRsp, LLMChatDeleteSessionReq,
+                LLMChatDeleteSessionRsp, LLMChatDeleteTemplateReq, LLMChatDeleteTemplateRsp,
+                LLMChatInsertRecordReq, LLMChatInsertRecordRsp, LLMChatInsertSessionReq,
+                LLMChatInsertSessionRsp, LLMChatListSessionReq, LLMChatListSessionRsp,
+                LLMChatOverwriteBotReq, LLMChatOverwriteBotRsp, LLMChatOverwriteTemplateReq,
+                LLMChatOverwriteTemplateRsp, LLMChatSessionDetialReq, LLMChatTruncateSessionReq,
+                LLMChatTruncateSessionRsp, LLMChatUpdateSessionReq, LLMChatUpdateSessionRsp,
+            },
+            namespace::{
+                NamespaceAclDeleteReq, NamespaceAclDeleteRsp, NamespaceAclListReq,
+                NamespaceAclListRsp, NamespaceAclOverwriteReq, NamespaceCreateReq,
+                NamespaceDeleteReq, NamespaceDeleteRsp, NamespaceMoveReq, NamespaceRenameReq,
+                NamespaceTreeRsp,
+            },
+            share::{
+                ShareContent, ShareLinkCreateReq, ShareLinkCreateRsp, ShareLinkListRsp,
+                ShareLinkRevokeReq, ShareLinkRevokeRsp, ShareRecord,
+            },
+            user::{
+                ApiTokenCreateReq, ApiTokenCreateRsp, ApiTokenListRsp, ApiTokenRevokeReq,
+                ApiTokenRevokeRsp, UserGroupCreateReq, UserGroupListRsp, UserGroupMemberReq,
+                UserGroupMemberRsp, UserListRsp, UserLoginReq, UserLoginRsp,
+            },
+            KReq, ResourceListReq, ResourceListRsp,
+        },
+    },
+    search::{SearchIndex, MAX_SEARCH_HITS},
+    util::{
+        acl::NamespaceRoles,
+        auth::{
+            hash_password, hash_token, new_token, verify_password, API_TOKEN_PREFIX,
+            DUMMY_PASSWORD_HASH,
+        },
+        diff::diff_lines,
+        link::{chnot_title, extract_links, extract_resource_ids},
+        namespace::NamespaceTree,
+        ratelimit::RateLimiter,
+        share::{sign_share_token, verify_share_token},
+        tag::{extract_tags, is_valid_tag, replace_tag},
     },
 };
 
+/// The widest range `chnot_activity` counts at once.
+const MAX_ACTIVITY_DAYS: i64 = 3 * 366;
+
 pub struct AppState {
     pub mapper: MapperType,
     pub config: Config,
+    pub search: Option<SearchIndex>,
+    /// Read by every query with `include_descendants`, refreshed whenever a
+    /// namespace is changed.
+    pub namespaces: ArcSwap<NamespaceTree>,
+    /// Built from `limit.rate` of the config.
+    pub rate_limiter: Option<RateLimiter>,
+    /// Held while a resource file is shared or removed, an upload must not
+    /// reuse a file the deletion of its last resource is removing.
+    pub resource_files: tokio::sync::Mutex<()>,
 }
 
 #[derive(Clone)]
@@ -35,8 +123,1930 @@ impl Into<ShareAppState> for AppState {
 impl AppState {
     pub async fn chnot_query(
         &self,
-        req: KReq<ChnotQueryReq>,
+        mut req: KReq<ChnotQueryReq>,
     ) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
+        if req.search_mode == ChnotSearchMode::FullText && req.cursor.is_some() {
+            return Err(KError::Validation(
+                "the full text search is ordered by rank, page it with start_index instead of cursor"
+                    .to_owned(),
+            )
+            .into());
+        }
+
+        if req.include_descendants {
+            // `ns:` in the query replaces the namespace of the header, the
+            // full text search uses the query as it is.
+            let namespace = match (req.search_mode, req.query.as_deref()) {
+                (ChnotSearchMode::Substring, Some(query)) => ChnotQuery::from_str(query)?.namespace,
+                _ => None,
+            }
+            .unwrap_or(req.namespace.clone());
+            req.body.descendants = self.namespace_tree().descendants(&namespace);
+        }
+
         self.mapper.chnot_query(req).await
     }
+
+    /// The record id and not the content is audited, the content is kept by
+    /// the history.
+    pub async fn chnot_overwrite(
+        &self,
+        user: &User,
+        req: KReq<ChnotOverwriteReq>,
+    ) -> AResult<ChnotOverwriteRsp> {
+        let namespace = req.namespace.clone();
+        let checkpoint = req.checkpoint;
+        let rsp = self.save_chnot(req).await?;
+        self.audit(
+            user,
+            &namespace,
+            AuditEntityType::Chnot,
+            &rsp.chnot.meta.id,
+            AuditAction::Overwrite,
+            &serde_json::json!({
+                "record_id": rsp.chnot.record.id,
+                "kind": rsp.chnot.meta.kind,
+                "checkpoint": checkpoint,
+                "new_version": rsp.new_version,
+            }),
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    /// Save the record with its tags and links, and index it.
+    async fn save_chnot(&self, req: KReq<ChnotOverwriteReq>) -> AResult<ChnotOverwriteRsp> {
+        let policy = self.config.versioning.policy_of(&req.namespace);
+        let rsp = self.mapper.chnot_overwrite(req, policy).await?;
+        self.mapper
+            .chnot_tag_overwrite(&rsp.chnot.meta.id, &extract_tags(&rsp.chnot.record.content))
+            .await?;
+        self.mapper
+            .chnot_link_overwrite(
+                &rsp.chnot.meta.id,
+                &extract_links(&rsp.chnot.record.content),
+            )
+            .await?;
+
+        // The chnot is saved already, a stale index is fixed by a rebuild.
+        if let Some(search) = self.search.as_ref() {
+            if let Err(err) = search.upsert(&rsp.chnot).await {
+                error!("unable to index chnot {}: {:?}", rsp.chnot.meta.id, err);
+            }
+        }
+
+        Ok(rsp)
+    }
+
+    pub async fn chnot_delete(
+        &self,
+        user: &User,
+        req: KReq<ChnotDeletionReq>,
+    ) -> AResult<ChnotDeletionRsp> {
+        let meta_id = req.chnot_id.clone();
+        let rsp = self.mapper.chnot_delete(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::Chnot,
+            &meta_id,
+            AuditAction::Delete,
+            &req.body,
+        )
+        .await;
+
+        if let Some(search) = self.search.as_ref() {
+            if let Err(err) = search.delete(&meta_id).await {
+                error!("unable to remove chnot {} from index: {:?}", meta_id, err);
+            }
+        }
+
+        Ok(rsp)
+    }
+
+    pub async fn chnot_trash_restore(
+        &self,
+        user: &User,
+        req: KReq<ChnotTrashRestoreReq>,
+    ) -> AResult<ChnotMetadata> {
+        let meta = self.mapper.chnot_trash_restore(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::Chnot,
+            &meta.id,
+            AuditAction::Restore,
+            &req.body,
+        )
+        .await;
+
+        if let Some(search) = self.search.as_ref() {
+            if let Some(chnot) = self.chnot_by_meta_id(&meta.namespace, &meta.id).await? {
+                if let Err(err) = search.upsert(&chnot).await {
+                    error!("unable to index chnot {}: {:?}", meta.id, err);
+                }
+            }
+        }
+
+        Ok(meta)
+    }
+
+    /// Physically delete the chnots in the trash longer than the retention
+    /// period, returns the count of them.
+    pub async fn purge_trash(&self, retention_days: u32) -> AResult<usize> {
+        let time = Local::now().fixed_offset() - TimeDelta::days(retention_days as i64);
+        let metas = self.mapper.chnot_trash_expired(time).await?;
+        let meta_ids: Vec<String> = metas.iter().map(|e| e.id.clone()).collect();
+        self.mapper.chnot_purge(&meta_ids).await?;
+
+        for meta in metas.iter() {
+            self.audit_as(
+                (SYSTEM_ACTOR, SYSTEM_ACTOR),
+                &meta.namespace,
+                AuditEntityType::Chnot,
+                &meta.id,
+                AuditAction::Purge,
+                meta,
+            )
+            .await;
+        }
+
+        Ok(metas.len())
+    }
+
+    pub async fn chnot_update(
+        &self,
+        user: &User,
+        req: KReq<ChnotUpdateReq>,
+    ) -> AResult<ChnotMetadata> {
+        let meta = self.mapper.chnot_update(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::Chnot,
+            &req.meta_id,
+            AuditAction::Update,
+            &req.body,
+        )
+        .await;
+
+        Ok(meta)
+    }
+
+    async fn chnot_by_meta_id(&self, namespace: &str, meta_id: &str) -> AResult<Option<Chnot>> {
+        let rsp = self
+            .mapper
+            .chnot_query(KReq {
+                body: ChnotQueryReq {
+                    query: None,
+                    meta_id: Some(meta_id.to_owned()),
+                    record_id: None,
+                    with_deleted: None,
+                    with_omitted: None,
+                    search_mode: ChnotSearchMode::default(),
+                    archived: ChnotArchiveFilter::Include,
+                    include_descendants: false,
+                    descendants: vec![],
+                    time_range: ChnotTimeRange::default(),
+                    sort: ChnotSort::default(),
+                    cursor: None,
+                    start_index: 0,
+                    page_size: 1,
+                },
+                namespace: namespace.to_owned(),
+            })
+            .await?;
+
+        Ok(rsp.data.into_iter().next())
+    }
+
+    pub async fn chnot_activity(&self, req: KReq<ChnotActivityReq>) -> AResult<ChnotActivityRsp> {
+        let offset = match req.utc_offset_minutes {
+            Some(minutes) => minutes
+                .checked_mul(60)
+                .and_then(FixedOffset::east_opt)
+                .ok_or_else(|| {
+                    KError::Validation(format!("invalid utc offset {} minutes", minutes))
+                })?,
+            None => *Local::now().offset(),
+        };
+        let before = req.before.unwrap_or_else(|| Local::now().fixed_offset());
+        let after = req.after.unwrap_or(before - TimeDelta::days(365));
+        if after >= before || before - after > TimeDelta::days(MAX_ACTIVITY_DAYS) {
+            return Err(KError::Validation(format!(
+                "invalid activity range, it needs 1 to {} days",
+                MAX_ACTIVITY_DAYS
+            ))
+            .into());
+        }
+
+        let days = self
+            .mapper
+            .chnot_activity_days(&req.namespace, after, before, offset)
+            .await?;
+
+        Ok(ChnotActivityRsp { days })
+    }
+
+    /// Search through the embedded index, the hits are read back from the
+    /// database so the pin and update states are always fresh.
+    pub async fn chnot_search(
+        &self,
+        req: KReq<ChnotSearchReq>,
+    ) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
+        let search = self
+            .search
+            .as_ref()
+            .context("search index is not configured")?;
+
+        if req.page_size == 0 || req.page_size > 500 {
+            return Err(KError::Validation(format!(
+                "invalid page_size {}, it needs 1 to 500",
+                req.page_size
+            ))
+            .into());
+        }
+        // the index collects every hit before the offset, keep it bounded
+        match req.start_index.checked_add(req.page_size) {
+            Some(end) if end <= MAX_SEARCH_HITS => {}
+            _ => {
+                return Err(KError::Validation(format!(
+                    "invalid start_index {}, the search returns the first {} hits only",
+                    req.start_index, MAX_SEARCH_HITS
+                ))
+                .into())
+            }
+        }
+
+        let hits = search
+            .search(
+                &req.namespace,
+                &req.query,
+                req.start_index as usize,
+                req.page_size as usize,
+            )
+            .await?;
+
+        let mut data = Vec::with_capacity(hits.len());
+        let mut highlights = Vec::with_capacity(hits.len());
+        for hit in hits {
+            match self.chnot_by_meta_id(&req.namespace, &hit.meta_id).await? {
+                Some(chnot) => {
+                    highlights.push(ChnotHighlight {
+                        record_id: chnot.record.id.clone(),
+                        rank: hit.score,
+                        snippet: hit.snippet,
+                    });
+                    data.push(chnot);
+                }
+                None => error!("chnot {} is in the index but not the database", hit.meta_id),
+            }
+        }
+
+        Ok(ChnotQueryRsp {
+            data,
+            start_index: req.start_index,
+            highlights,
+            next_cursor: None,
+        })
+    }
+
+    /// All chnots of the namespace matched by `query`, page by page.
+    async fn chnots_of(&self, namespace: &str, query: Option<String>) -> AResult<Vec<Chnot>> {
+        self.all_pages(KReq {
+            body: ChnotQueryReq {
+                query,
+                meta_id: None,
+                record_id: None,
+                with_deleted: None,
+                with_omitted: None,
+                search_mode: ChnotSearchMode::default(),
+                archived: ChnotArchiveFilter::Include,
+                include_descendants: false,
+                descendants: vec![],
+                time_range: ChnotTimeRange::default(),
+                sort: ChnotSort::Created,
+                cursor: None,
+                start_index: 0,
+                page_size: 0,
+            },
+            namespace: namespace.to_owned(),
+        })
+        .await
+    }
+
+    /// Every chnot matched by `req`, its sort and paging are replaced.
+    async fn all_pages(&self, mut req: KReq<ChnotQueryReq>) -> AResult<Vec<Chnot>> {
+        const PAGE_SIZE: u64 = 500;
+
+        req.body.sort = ChnotSort::Created;
+        req.body.cursor = None;
+        req.body.start_index = 0;
+        req.body.page_size = PAGE_SIZE;
+
+        let mut chnots = vec![];
+        loop {
+            let rsp = self.chnot_query(req.clone()).await?;
+            chnots.extend(rsp.data);
+            req.body.cursor = rsp.next_cursor;
+            if req.cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(chnots)
+    }
+
+    /// Move the chnots in `meta_ids` and the ones matched by `filter` to the
+    /// target namespace, with the resources they embed. The user needs the
+    /// editor role on every namespace a chnot leaves.
+    pub async fn chnot_move(&self, user: &User, req: KReq<ChnotMoveReq>) -> AResult<ChnotMoveRsp> {
+        let target = req.target_namespace.trim();
+        if !self.namespace_tree().contains(target) {
+            return Err(KError::NotFound(format!("unable to find namespace {}", target)).into());
+        }
+
+        let mut results = vec![];
+        let mut chnots: Vec<Chnot> = vec![];
+        let mut seen = HashSet::new();
+        for meta_id in req.meta_ids.iter() {
+            if !seen.insert(meta_id.clone()) {
+                continue;
+            }
+            match self.chnot_by_meta_id(&req.namespace, meta_id).await? {
+                Some(chnot) => chnots.push(chnot),
+                None => results.push(ChnotMoveResult {
+                    meta_id: meta_id.clone(),
+                    status: ChnotMoveStatus::NotFound,
+                    resources: vec![],
+                }),
+            }
+        }
+        if let Some(filter) = req.filter.clone() {
+            let matched = self
+                .all_pages(KReq {
+                    body: ChnotQueryReq {
+                        query: filter.query,
+                        meta_id: None,
+                        record_id: None,
+                        with_deleted: None,
+                        with_omitted: None,
+                        search_mode: ChnotSearchMode::Substring,
+                        archived: filter.archived,
+                        include_descendants: filter.include_descendants,
+                        descendants: vec![],
+                        time_range: filter.time_range,
+                        sort: ChnotSort::Created,
+                        cursor: None,
+                        start_index: 0,
+                        page_size: 0,
+                    },
+                    namespace: req.namespace.clone(),
+                })
+                .await?;
+            chnots.extend(
+                matched
+                    .into_iter()
+                    .filter(|chnot| seen.insert(chnot.meta.id.clone())),
+            );
+        }
+
+        let mut items = Vec::with_capacity(chnots.len());
+        for chnot in chnots.iter() {
+            if chnot.meta.namespace == target {
+                results.push(ChnotMoveResult {
+                    meta_id: chnot.meta.id.clone(),
+                    status: ChnotMoveStatus::Unchanged,
+                    resources: vec![],
+                });
+                continue;
+            }
+            items.push(ChnotMoveItem {
+                meta_id: chnot.meta.id.clone(),
+                namespace: chnot.meta.namespace.clone(),
+                resource_ids: extract_resource_ids(&chnot.record.content),
+            });
+        }
+
+        // `ns:` and `include_descendants` of the filter reach other namespaces
+        // than the one of the header.
+        let sources: HashSet<&str> = items.iter().map(|e| e.namespace.as_str()).collect();
+        for source in sources {
+            self.require_role(user, source, NamespaceRole::Editor)
+                .await?;
+        }
+
+        let moved = self.mapper.chnot_move(&items, target).await?;
+
+        // Logged in the namespace it left, the one of the header may be none
+        // of the sources.
+        for result in moved.iter() {
+            if result.status != ChnotMoveStatus::Moved {
+                continue;
+            }
+            let Some(item) = items.iter().find(|e| e.meta_id == result.meta_id) else {
+                continue;
+            };
+            self.audit(
+                user,
+                &item.namespace,
+                AuditEntityType::Chnot,
+                &result.meta_id,
+                AuditAction::Move,
+                &serde_json::json!({ "target_namespace": target, "result": result }),
+            )
+            .await;
+        }
+
+        // The namespace is a filter of the index, reindex the moved chnots.
+        if let Some(search) = self.search.as_ref() {
+            let moved_ids: HashSet<&str> = moved
+                .iter()
+                .filter(|e| e.status == ChnotMoveStatus::Moved)
+                .map(|e| e.meta_id.as_str())
+                .collect();
+            for chnot in chnots.iter() {
+                if !moved_ids.contains(chnot.meta.id.as_str()) {
+                    continue;
+                }
+                let mut chnot = chnot.clone();
+                chnot.meta.namespace = target.to_owned();
+                if let Err(err) = search.upsert(&chnot).await {
+                    error!("unable to index chnot {}: {:?}", chnot.meta.id, err);
+                }
+            }
+        }
+        results.extend(moved);
+
+        Ok(ChnotMoveRsp { results })
+    }
+
+    /// The current record of every not deleted chnot.
+    async fn all_chnots(&self) -> AResult<Vec<Chnot>> {
+        let mut chnots = vec![];
+        for namespace in self.mapper.chnot_namespaces().await? {
+            chnots.extend(self.chnots_of(&namespace, None).await?);
+        }
+
+        Ok(chnots)
+    }
+
+    /// Parse the tags of every chnot again, returns the count of chnots.
+    pub async fn rebuild_tags(&self) -> AResult<usize> {
+        let chnots = self.all_chnots().await?;
+        for chnot in chnots.iter() {
+            self.mapper
+                .chnot_tag_overwrite(&chnot.meta.id, &extract_tags(&chnot.record.content))
+                .await?;
+        }
+
+        Ok(chnots.len())
+    }
+
+    /// Parse the links of every chnot again, returns the count of chnots.
+    pub async fn rebuild_links(&self) -> AResult<usize> {
+        let chnots = self.all_chnots().await?;
+        for chnot in chnots.iter() {
+            self.mapper
+                .chnot_link_overwrite(&chnot.meta.id, &extract_links(&chnot.record.content))
+                .await?;
+        }
+
+        Ok(chnots.len())
+    }
+
+    pub async fn chnot_backlinks(
+        &self,
+        req: KReq<ChnotBacklinksReq>,
+    ) -> AResult<ChnotBacklinksRsp> {
+        let chnot = self
+            .chnot_by_meta_id(&req.namespace, &req.meta_id)
+            .await?
+            .ok_or_else(|| KError::NotFound(format!("unable to find chnot {}", req.meta_id)))?;
+
+        let mut targets = vec![chnot.meta.id.clone()];
+        let title = chnot_title(&chnot.record.content);
+        if !title.is_empty() {
+            targets.push(title.to_owned());
+        }
+
+        let sources: BTreeSet<String> = self
+            .mapper
+            .chnot_link_list(&req.namespace, Some(targets.as_slice()))
+            .await?
+            .into_iter()
+            .map(|e| e.meta_id)
+            .filter(|e| e != &chnot.meta.id)
+            .collect();
+
+        let mut chnots = Vec::with_capacity(sources.len());
+        for source in sources {
+            chnots.extend(self.chnot_by_meta_id(&req.namespace, &source).await?);
+        }
+
+        Ok(ChnotBacklinksRsp { chnots })
+    }
+
+    pub async fn chnot_graph(&self, req: KReq<ChnotGraphReq>) -> AResult<ChnotGraphRsp> {
+        let chnots = self.chnots_of(&req.namespace, None).await?;
+
+        let nodes: Vec<ChnotGraphNode> = chnots
+            .iter()
+            .map(|chnot| ChnotGraphNode {
+                meta_id: chnot.meta.id.clone(),
+                title: chnot_title(&chnot.record.content).to_owned(),
+            })
+            .collect();
+
+        // A title used by more than one chnot links to the first one, which is
+        // the pinned or the newest.
+        let mut targets: HashMap<&str, &str> = HashMap::new();
+        for node in nodes.iter() {
+            if !node.title.is_empty() {
+                targets
+                    .entry(node.title.as_str())
+                    .or_insert(node.meta_id.as_str());
+            }
+        }
+        for node in nodes.iter() {
+            targets.insert(node.meta_id.as_str(), node.meta_id.as_str());
+        }
+
+        let edges: BTreeSet<ChnotGraphEdge> = self
+            .mapper
+            .chnot_link_list(&req.namespace, None)
+            .await?
+            .into_iter()
+            .filter_map(|link| {
+                let target = targets.get(link.target.as_str())?;
+                (*target != link.meta_id).then(|| ChnotGraphEdge {
+                    source: link.meta_id,
+                    target: target.to_string(),
+                })
+            })
+            .collect();
+
+        Ok(ChnotGraphRsp {
+            nodes,
+            edges: edges.into_iter().collect(),
+        })
+    }
+
+    /// Rewrite `#from` in every chnot of the namespace, the tags are parsed
+    /// again by `save_chnot`.
+    pub async fn chnot_tag_rename(
+        &self,
+        user: &User,
+        req: KReq<ChnotTagRenameReq>,
+    ) -> AResult<ChnotTagRenameRsp> {
+        if !is_valid_tag(&req.from) || !is_valid_tag(&req.to) {
+            return Err(KError::Validation(format!(
+                "invalid tag, from: {}, to: {}",
+                req.from, req.to
+            ))
+            .into());
+        }
+        if req.from == req.to {
+            return Ok(ChnotTagRenameRsp { count: 0 });
+        }
+
+        let chnots = self
+            .chnots_of(&req.namespace, Some(format!("tag:{}", req.from)))
+            .await?;
+
+        let mut count = 0;
+        for chnot in chnots {
+            let Some(content) = replace_tag(&chnot.record.content, &req.from, &req.to) else {
+                continue;
+            };
+
+            self.save_chnot(KReq {
+                body: ChnotOverwriteReq {
+                    chnot: ChnotRecord {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        meta_id: chnot.meta.id.clone(),
+                        content,
+                        omit_time: None,
+                        insert_time: Local::now().fixed_offset(),
+                    },
+                    kind: ChnotKind::from_str(&chnot.meta.kind)?,
+                    checkpoint: true,
+                },
+                namespace: req.namespace.clone(),
+            })
+            .await?;
+            count += 1;
+        }
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::ChnotTag,
+            &req.from,
+            AuditAction::Rename,
+            &req.body,
+        )
+        .await;
+
+        Ok(ChnotTagRenameRsp { count })
+    }
+
+    /// Index the current record of every chnot again, returns the count of
+    /// indexed chnots.
+    pub async fn rebuild_search_index(&self) -> AResult<usize> {
+        let search = self
+            .search
+            .as_ref()
+            .context("search index is not configured")?;
+
+        let chnots = self.all_chnots().await?;
+        let count = chnots.len();
+        search.rebuild(chnots).await?;
+
+        Ok(count)
+    }
+
+    pub async fn chnot_diff(&self, req: KReq<ChnotDiffReq>) -> AResult<ChnotDiffRsp> {
+        let from = self
+            .mapper
+            .chnot_record_by_id(&req.namespace, &req.from_record_id)
+            .await?;
+        let to = self
+            .mapper
+            .chnot_record_by_id(&req.namespace, &req.to_record_id)
+            .await?;
+
+        let lines = diff_lines(&from.content, &to.content).ok_or_else(|| {
+            KError::Validation(format!(
+                "the records {} and {} differ in too many lines to diff",
+                req.from_record_id, req.to_record_id
+            ))
+        })?;
+
+        Ok(ChnotDiffRsp { from, to, lines })
+    }
+
+    /// Make an old record the current one again, the content is saved as a
+    /// new version through `save_chnot`.
+    pub async fn chnot_restore(
+        &self,
+        user: &User,
+        req: KReq<ChnotRestoreReq>,
+    ) -> AResult<ChnotRestoreRsp> {
+        let history = self
+            .mapper
+            .chnot_history(KReq {
+                body: ChnotHistoryReq {
+                    meta_id: req.meta_id.clone(),
+                },
+                namespace: req.namespace.clone(),
+            })
+            .await?;
+
+        let record = history
+            .records
+            .iter()
+            .find(|e| e.id == req.record_id)
+            .ok_or_else(|| {
+                KError::NotFound(format!(
+                    "chnot {} has no record {}",
+                    req.meta_id, req.record_id
+                ))
+            })?;
+
+        let rsp = self
+            .save_chnot(KReq {
+                body: ChnotOverwriteReq {
+                    chnot: ChnotRecord {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        meta_id: req.meta_id.clone(),
+                        content: record.content.clone(),
+                        omit_time: None,
+                        insert_time: Local::now().fixed_offset(),
+                    },
+                    kind: ChnotKind::from_str(&history.meta.kind)?,
+                    checkpoint: true,
+                },
+                namespace: req.namespace.clone(),
+            })
+            .await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::Chnot,
+            &req.meta_id,
+            AuditAction::Restore,
+            &req.body,
+        )
+        .await;
+
+        Ok(ChnotRestoreRsp { chnot: rsp.chnot })
+    }
+
+    pub async fn llm_chat_list_sessions(
+        &self,
+        mut req: KReq<LLMChatListSessionReq>,
+    ) -> AResult<LLMChatListSessionRsp> {
+        if req.include_descendants {
+            req.body.descendants = self.namespace_tree().descendants(&req.namespace);
+        }
+
+        self.mapper.llm_chat_list_sessions(req).await
+    }
+
+    pub async fn llm_chat_overwrite_bot(
+        &self,
+        user: &User,
+        req: KReq<LLMChatOverwriteBotReq>,
+    ) -> AResult<LLMChatOverwriteBotRsp> {
+        let rsp = self.mapper.llm_chat_overwrite_bot(req.clone()).await?;
+        // the body of a bot may have its keys
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatBot,
+            &req.bot.id,
+            AuditAction::Overwrite,
+            &serde_json::json!({ "name": req.bot.name }),
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn llm_chat_delete_bot(
+        &self,
+        user: &User,
+        req: KReq<LLMChatDeleteBotReq>,
+    ) -> AResult<LLMChatDeleteBotRsp> {
+        let rsp = self.mapper.llm_chat_delete_bot(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatBot,
+            &req.bot_id,
+            AuditAction::Delete,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn llm_chat_overwrite_template(
+        &self,
+        user: &User,
+        req: KReq<LLMChatOverwriteTemplateReq>,
+    ) -> AResult<LLMChatOverwriteTemplateRsp> {
+        let rsp = self.mapper.llm_chat_overwrite_template(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatTemplate,
+            &req.template.id,
+            AuditAction::Overwrite,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn llm_chat_delete_template(
+        &self,
+        user: &User,
+        req: KReq<LLMChatDeleteTemplateReq>,
+    ) -> AResult<LLMChatDeleteTemplateRsp> {
+        let rsp = self.mapper.llm_chat_delete_template(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatTemplate,
+            &req.template_id,
+            AuditAction::Delete,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn llm_chat_insert_session(
+        &self,
+        user: &User,
+        req: KReq<LLMChatInsertSessionReq>,
+    ) -> AResult<LLMChatInsertSessionRsp> {
+        let rsp = self.mapper.llm_chat_insert_session(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatSession,
+            &req.session.id,
+            AuditAction::Insert,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn llm_chat_update_session(
+        &self,
+        user: &User,
+        req: KReq<LLMChatUpdateSessionReq>,
+    ) -> AResult<LLMChatUpdateSessionRsp> {
+        let rsp = self.mapper.llm_chat_update_session(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatSession,
+            &req.session_id,
+            AuditAction::Update,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn llm_chat_truncate_session(
+        &self,
+        user: &User,
+        req: KReq<LLMChatTruncateSessionReq>,
+    ) -> AResult<LLMChatTruncateSessionRsp> {
+        let rsp = self.mapper.llm_chat_truncate_session(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatSession,
+            &req.session_id,
+            AuditAction::Truncate,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn llm_chat_delete_session(
+        &self,
+        user: &User,
+        req: KReq<LLMChatDeleteSessionReq>,
+    ) -> AResult<LLMChatDeleteSessionRsp> {
+        let rsp = self.mapper.llm_chat_delete_session(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatSession,
+            &req.session_id,
+            AuditAction::Delete,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    /// The content of the record is not audited, as the one of a chnot.
+    pub async fn llm_chat_insert_record(
+        &self,
+        user: &User,
+        req: KReq<LLMChatInsertRecordReq>,
+    ) -> AResult<LLMChatInsertRecordRsp> {
+        let rsp = self.mapper.llm_chat_insert_record(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::LLMChatRecord,
+            &req.record.id,
+            AuditAction::Insert,
+            &serde_json::json!({
+                "session_id": req.record.session_id,
+                "pre_record_id": req.record.pre_record_id,
+                "role": req.record.role,
+                "role_id": req.record.role_id,
+            }),
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn kv_overwrite(
+        &self,
+        user: &User,
+        req: KReq<KVOverwriteReq>,
+    ) -> AResult<KVOverwriteRsp> {
+        let rsp = self.mapper.kv_overwrite(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::KV,
+            req.kv.key.as_str(),
+            AuditAction::Overwrite,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn kv_delete(&self, user: &User, req: KReq<KVDeleteReq>) -> AResult<KVDeleteRsp> {
+        let rsp = self.mapper.kv_delete(req.clone()).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::KV,
+            req.key.as_str(),
+            AuditAction::Delete,
+            &req.body,
+        )
+        .await;
+
+        Ok(rsp)
+    }
+
+    pub async fn resource_list(&self, mut req: KReq<ResourceListReq>) -> AResult<ResourceListRsp> {
+        if req.include_descendants {
+            req.body.descendants = self.namespace_tree().descendants(&req.namespace);
+        }
+
+        self.mapper.query_resources(req).await
+    }
+
+    /// The cached namespace tree.
+    pub fn namespace_tree(&self) -> Arc<NamespaceTree> {
+        self.namespaces.load_full()
+    }
+
+    /// Read the namespace tree from the database into the cache.
+    pub async fn refresh_namespaces(&self) -> EResult {
+        let records = self.mapper.read_all_namespaces().await?;
+        let relations = self.mapper.read_all_namespace_relations().await?;
+        self.namespaces
+            .store(Arc::new(NamespaceTree::new(&records, &relations)));
+
+        Ok(())
+    }
+
+    /// Create the records of the default namespace and the namespaces in use
+    /// by chnots if they are missing, the namespace of a request must have
+    /// one.
+    pub async fn ensure_namespaces(&self) -> EResult {
+        let mut namespaces = self.mapper.chnot_namespaces().await?;
+        namespaces.extend(self.config.default_namespace.clone());
+
+        for namespace in namespaces {
+            if self.namespace_tree().contains(&namespace) {
+                continue;
+            }
+            info!("Create the missing namespace {}.", namespace);
+            let req = NamespaceCreateReq {
+                id: Some(namespace.clone()),
+                name: namespace.clone(),
+                parent_id: None,
+            };
+            if let Err(err) = self.namespace_create(req, None).await {
+                error!("unable to create namespace {}: {:?}", namespace, err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The owner gets the owner role on the new namespace, and the creation
+    /// is audited as done by the owner.
+    pub async fn namespace_create(
+        &self,
+        req: NamespaceCreateReq,
+        owner: Option<&User>,
+    ) -> AResult<NamespaceRecord> {
+        let name = req.name.trim();
+        let id = req.id.as_deref().map(|e| e.trim()).unwrap_or(name);
+        check_namespace(id, name)?;
+
+        if let Some(parent_id) = req.parent_id.as_deref() {
+            if !self.namespace_tree().contains(parent_id) {
+                return Err(
+                    KError::NotFound(format!("unable to find namespace {}", parent_id)).into(),
+                );
+            }
+        }
+
+        let record = NamespaceRecord {
+            id: id.to_owned(),
+            name: name.to_owned(),
+            delete_time: None,
+            update_time: None,
+            insert_time: Local::now().fixed_offset(),
+        };
+        if !self.mapper.namespace_insert(&record).await? {
+            return Err(KError::Conflict(format!("namespace {} already exists", id)).into());
+        }
+        if let Some(parent_id) = req.parent_id.as_deref() {
+            self.mapper
+                .namespace_set_parent(id, Some(parent_id))
+                .await?;
+        }
+        if let Some(owner) = owner {
+            self.mapper
+                .namespace_acl_overwrite(&NamespaceAcl {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    namespace: id.to_owned(),
+                    subject_kind: AclSubjectKind::User,
+                    subject_id: owner.id.clone(),
+                    role: NamespaceRole::Owner,
+                    insert_time: record.insert_time,
+                })
+                .await?;
+        }
+        self.refresh_namespaces().await?;
+        if let Some(owner) = owner {
+            self.audit(
+                owner,
+                id,
+                AuditEntityType::Namespace,
+                id,
+                AuditAction::Insert,
+                &req,
+            )
+            .await;
+        }
+
+        Ok(record)
+    }
+
+    pub async fn namespace_rename(
+        &self,
+        user: &User,
+        req: NamespaceRenameReq,
+    ) -> AResult<NamespaceRecord> {
+        let name = req.name.trim();
+        check_namespace(&req.id, name)?;
+
+        let record = self.mapper.namespace_rename(&req.id, name).await?;
+        self.refresh_namespaces().await?;
+        self.audit(
+            user,
+            &req.id,
+            AuditEntityType::Namespace,
+            &req.id,
+            AuditAction::Rename,
+            &req,
+        )
+        .await;
+
+        Ok(record)
+    }
+
+    pub async fn namespace_delete(
+        &self,
+        user: &User,
+        req: NamespaceDeleteReq,
+    ) -> AResult<NamespaceDeleteRsp> {
+        let tree = self.namespace_tree();
+        if !tree.contains(&req.id) {
+            return Err(KError::NotFound(format!("unable to find namespace {}", req.id)).into());
+        }
+        if !tree.children_of(&req.id).is_empty() {
+            return Err(KError::Conflict(format!(
+                "namespace {} has children, move or delete them first",
+                req.id
+            ))
+            .into());
+        }
+        let count = self.mapper.namespace_chnot_count(&req.id).await?;
+        if count > 0 {
+            return Err(KError::Conflict(format!(
+                "namespace {} still has {} chnots",
+                req.id, count
+            ))
+            .into());
+        }
+
+        self.mapper.namespace_delete(&req.id).await?;
+        self.refresh_namespaces().await?;
+        self.audit(
+            user,
+            &req.id,
+            AuditEntityType::Namespace,
+            &req.id,
+            AuditAction::Delete,
+            &req,
+        )
+        .await;
+
+        Ok(NamespaceDeleteRsp {})
+    }
+
+    /// The tree after the move, as the user sees it.
+    pub async fn namespace_move(
+        &self,
+        user: &User,
+        req: NamespaceMoveReq,
+    ) -> AResult<NamespaceTreeRsp> {
+        let tree = self.namespace_tree();
+        if !tree.contains(&req.id) {
+            return Err(KError::NotFound(format!("unable to find namespace {}", req.id)).into());
+        }
+        if let Some(parent_id) = req.parent_id.as_deref() {
+            if !tree.contains(parent_id) {
+                return Err(
+                    KError::NotFound(format!("unable to find namespace {}", parent_id)).into(),
+                );
+            }
+            if tree.would_cycle(&req.id, parent_id) {
+                return Err(KError::Validation(format!(
+                    "unable to move namespace {} into {}, it is the namespace itself or one of its descendants",
+                    req.id,
+                    parent_id
+                )).into());
+            }
+        }
+
+        self.mapper
+            .namespace_set_parent(&req.id, req.parent_id.as_deref())
+            .await?;
+        self.refresh_namespaces().await?;
+        self.audit(
+            user,
+            &req.id,
+            AuditEntityType::Namespace,
+            &req.id,
+            AuditAction::Move,
+            &req,
+        )
+        .await;
+
+        self.namespace_tree_of(user).await
+    }
+
+    /// The role of the user on the namespace, admins own every namespace.
+    pub async fn namespace_role(
+        &self,
+        user: &User,
+        namespace: &str,
+    ) -> AResult<Option<NamespaceRole>> {
+        let tree = self.namespace_tree();
+        if !tree.contains(namespace) {
+            return Ok(None);
+        }
+        if user.admin {
+            return Ok(Some(NamespaceRole::Owner));
+        }
+
+        let roles = NamespaceRoles::new(&self.mapper.namespace_acl_of_user(&user.id).await?);
+        Ok(roles.role_of(&tree, namespace))
+    }
+
+    /// A namespace the user has no role on is not found for the user.
+    pub async fn require_role(&self, user: &User, namespace: &str, role: NamespaceRole) -> EResult {
+        match self.namespace_role(user, namespace).await? {
+            None => Err(KError::NotFound(format!("unable to find namespace {}", namespace)).into()),
+            Some(e) if e < role => Err(KError::Forbidden(format!(
+                "the {} role on namespace {} is required",
+                role, namespace
+            ))
+            .into()),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// The namespaces the user has a role on.
+    pub async fn namespace_tree_of(&self, user: &User) -> AResult<NamespaceTreeRsp> {
+        let tree = self.namespace_tree();
+        if user.admin {
+            return Ok(NamespaceTreeRsp {
+                roots: tree.nodes(),
+            });
+        }
+
+        let roles = NamespaceRoles::new(&self.mapper.namespace_acl_of_user(&user.id).await?);
+        Ok(NamespaceTreeRsp {
+            roots: tree.visible_nodes(|id| roles.role_of(&tree, id).is_some()),
+        })
+    }
+
+    pub async fn namespace_acl_list(
+        &self,
+        req: NamespaceAclListReq,
+    ) -> AResult<NamespaceAclListRsp> {
+        Ok(NamespaceAclListRsp {
+            acls: self.mapper.namespace_acl_list(&req.namespace).await?,
+        })
+    }
+
+    pub async fn namespace_acl_overwrite(
+        &self,
+        user: &User,
+        req: NamespaceAclOverwriteReq,
+    ) -> AResult<NamespaceAcl> {
+        if !self.namespace_tree().contains(&req.namespace) {
+            return Err(
+                KError::NotFound(format!("unable to find namespace {}", req.namespace)).into(),
+            );
+        }
+        let found = match req.subject_kind {
+            AclSubjectKind::User => self.mapper.user_by_id(&req.subject_id).await?.is_some(),
+            AclSubjectKind::Group => self
+                .mapper
+                .user_group_list()
+                .await?
+                .iter()
+                .any(|e| e.id == req.subject_id),
+        };
+        if !found {
+            return Err(KError::NotFound(format!(
+                "unable to find {} {}",
+                req.subject_kind, req.subject_id
+            ))
+            .into());
+        }
+
+        let acl = NamespaceAcl {
+            id: uuid::Uuid::new_v4().to_string(),
+            namespace: req.namespace.clone(),
+            subject_kind: req.subject_kind,
+            subject_id: req.subject_id.clone(),
+            role: req.role,
+            insert_time: Local::now().fixed_offset(),
+        };
+        self.mapper.namespace_acl_overwrite(&acl).await?;
+        self.audit(
+            user,
+            &acl.namespace,
+            AuditEntityType::NamespaceAcl,
+            &acl.id,
+            AuditAction::Overwrite,
+            &req,
+        )
+        .await;
+
+        Ok(acl)
+    }
+
+    pub async fn namespace_acl_delete(
+        &self,
+        user: &User,
+        req: NamespaceAclDeleteReq,
+    ) -> AResult<NamespaceAclDeleteRsp> {
+        let deleted = self
+            .mapper
+            .namespace_acl_delete(&req.namespace, req.subject_kind, &req.subject_id)
+            .await?;
+        if !deleted {
+            return Err(KError::NotFound(format!(
+                "{} {} has no role on namespace {}",
+                req.subject_kind, req.subject_id, req.namespace
+            ))
+            .into());
+        }
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::NamespaceAcl,
+            &req.subject_id,
+            AuditAction::Delete,
+            &req,
+        )
+        .await;
+
+        Ok(NamespaceAclDeleteRsp {})
+    }
+
+    pub async fn user_list(&self) -> AResult<UserListRsp> {
+        Ok(UserListRsp {
+            users: self.mapper.user_list().await?,
+        })
+    }
+
+    pub async fn user_group_create(
+        &self,
+        user: &User,
+        req: UserGroupCreateReq,
+    ) -> AResult<UserGroup> {
+        let name = req.name.trim();
+        if name.is_empty() || name.chars().count() > 100 {
+            return Err(KError::Validation(format!(
+                "invalid group name {:?}, it needs 1 to 100 chars",
+                name
+            ))
+            .into());
+        }
+
+        let group = UserGroup {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_owned(),
+            insert_time: Local::now().fixed_offset(),
+        };
+        if !self.mapper.user_group_insert(&group).await? {
+            return Err(KError::Conflict(format!("group {} already exists", name)).into());
+        }
+        self.audit(
+            user,
+            GLOBAL_NAMESPACE,
+            AuditEntityType::UserGroup,
+            &group.id,
+            AuditAction::Insert,
+            &group,
+        )
+        .await;
+
+        Ok(group)
+    }
+
+    pub async fn user_group_list(&self) -> AResult<UserGroupListRsp> {
+        Ok(UserGroupListRsp {
+            groups: self.mapper.user_group_list().await?,
+            members: self.mapper.user_group_member_list().await?,
+        })
+    }
+
+    pub async fn user_group_member_add(
+        &self,
+        user: &User,
+        req: UserGroupMemberReq,
+    ) -> AResult<UserGroupMemberRsp> {
+        let groups = self.mapper.user_group_list().await?;
+        if !groups.iter().any(|e| e.id == req.group_id) {
+            return Err(KError::NotFound(format!("unable to find group {}", req.group_id)).into());
+        }
+        if self.mapper.user_by_id(&req.user_id).await?.is_none() {
+            return Err(KError::NotFound(format!("unable to find user {}", req.user_id)).into());
+        }
+
+        self.mapper
+            .user_group_member_insert(&UserGroupMember {
+                group_id: req.group_id.clone(),
+                user_id: req.user_id.clone(),
+                insert_time: Local::now().fixed_offset(),
+            })
+            .await?;
+        self.audit(
+            user,
+            GLOBAL_NAMESPACE,
+            AuditEntityType::UserGroupMember,
+            &req.group_id,
+            AuditAction::Insert,
+            &req,
+        )
+        .await;
+
+        Ok(UserGroupMemberRsp {})
+    }
+
+    pub async fn user_group_member_remove(
+        &self,
+        user: &User,
+        req: UserGroupMemberReq,
+    ) -> AResult<UserGroupMemberRsp> {
+        let removed = self
+            .mapper
+            .user_group_member_delete(&req.group_id, &req.user_id)
+            .await?;
+        if !removed {
+            return Err(KError::NotFound(format!(
+                "user {} is not in group {}",
+                req.user_id, req.group_id
+            ))
+            .into());
+        }
+        self.audit(
+            user,
+            GLOBAL_NAMESPACE,
+            AuditEntityType::UserGroupMember,
+            &req.group_id,
+            AuditAction::Delete,
+            &req,
+        )
+        .await;
+
+        Ok(UserGroupMemberRsp {})
+    }
+
+    /// A user without a creator is created from the command line, it is
+    /// audited as done by the system.
+    pub async fn user_create(
+        &self,
+        creator: Option<&User>,
+        username: &str,
+        password: &str,
+        admin: bool,
+    ) -> AResult<User> {
+        let username = username.trim();
+        if username.is_empty() || username.chars().count() > 100 {
+            return Err(KError::Validation(format!(
+                "invalid username {:?}, it needs 1 to 100 chars",
+                username
+            ))
+            .into());
+        }
+        if password.chars().count() < 8 {
+            return Err(
+                KError::Validation("the password needs at least 8 chars".to_owned()).into(),
+            );
+        }
+
+        let password = password.to_owned();
+        let user = User {
+            id: uuid::Uuid::new_v4().to_string(),
+            username: username.to_owned(),
+            password_hash: tokio::task::spawn_blocking(move || hash_password(&password)).await??,
+            admin,
+            delete_time: None,
+            update_time: None,
+            insert_time: Local::now().fixed_offset(),
+        };
+        if !self.mapper.user_insert(&user).await? {
+            return Err(KError::Conflict(format!("user {} already exists", username)).into());
+        }
+        // the created user and not the request, it has the password
+        self.audit_as(
+            creator
+                .map(|e| (e.id.as_str(), e.username.as_str()))
+                .unwrap_or((SYSTEM_ACTOR, SYSTEM_ACTOR)),
+            GLOBAL_NAMESPACE,
+            AuditEntityType::User,
+            &user.id,
+            AuditAction::Insert,
+            &user,
+        )
+        .await;
+
+        Ok(user)
+    }
+
+    pub async fn user_login(&self, req: UserLoginReq) -> AResult<UserLoginRsp> {
+        let user = self.mapper.user_by_name(req.username.trim()).await?;
+        // an unknown username is checked against a dummy hash, the time of
+        // the response does not tell whether the user exists
+        let hash = user.as_ref().map(|e| e.password_hash.clone());
+        // argon2 is slow on purpose, keep it off the async workers
+        let verified = tokio::task::spawn_blocking(move || {
+            verify_password(
+                &req.password,
+                hash.as_deref().unwrap_or(&DUMMY_PASSWORD_HASH),
+            )
+        })
+        .await??;
+        let user = match user {
+            Some(user) if verified => user,
+            _ => {
+                return Err(KError::Unauthorized("invalid username or password".to_owned()).into())
+            }
+        };
+
+        let now = Local::now().fixed_offset();
+        self.mapper.user_session_purge(now).await?;
+
+        let token = new_token();
+        let session = UserSession {
+            id: hash_token(&token),
+            user_id: user.id.clone(),
+            expire_time: now + TimeDelta::hours(self.config.auth.session_ttl_hours.into()),
+            insert_time: now,
+        };
+        self.mapper.user_session_insert(&session).await?;
+
+        Ok(UserLoginRsp {
+            token,
+            expire_time: session.expire_time,
+            user,
+        })
+    }
+
+    pub async fn user_logout(&self, token: &str) -> EResult {
+        self.mapper.user_session_delete(&hash_token(token)).await
+    }
+
+    pub async fn api_token_create(
+        &self,
+        user: &User,
+        req: ApiTokenCreateReq,
+    ) -> AResult<ApiTokenCreateRsp> {
+        let name = req.name.trim();
+        if name.is_empty() || name.chars().count() > 100 {
+            return Err(KError::Validation(format!(
+                "invalid token name {:?}, it needs 1 to 100 chars",
+                name
+            ))
+            .into());
+        }
+        let tree = self.namespace_tree();
+        if let Some(namespace) = req.namespaces.iter().find(|e| !tree.contains(e)) {
+            return Err(KError::NotFound(format!("unable to find namespace {}", namespace)).into());
+        }
+
+        let token = format!("{}{}", API_TOKEN_PREFIX, new_token());
+        let api_token = ApiToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user.id.clone(),
+            name: name.to_owned(),
+            token_hash: hash_token(&token),
+            namespaces: req
+                .namespaces
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            access: req.access,
+            last_used_time: None,
+            delete_time: None,
+            insert_time: Local::now().fixed_offset(),
+        };
+        self.mapper.api_token_insert(&api_token).await?;
+        self.audit(
+            user,
+            GLOBAL_NAMESPACE,
+            AuditEntityType::ApiToken,
+            &api_token.id,
+            AuditAction::Insert,
+            &api_token,
+        )
+        .await;
+
+        Ok(ApiTokenCreateRsp { token, api_token })
+    }
+
+    pub async fn api_token_list(&self, user: &User) -> AResult<ApiTokenListRsp> {
+        Ok(ApiTokenListRsp {
+            api_tokens: self.mapper.api_token_list(&user.id).await?,
+        })
+    }
+
+    pub async fn api_token_revoke(
+        &self,
+        user: &User,
+        req: ApiTokenRevokeReq,
+    ) -> AResult<ApiTokenRevokeRsp> {
+        let revoked = self
+            .mapper
+            .api_token_revoke(&user.id, &req.id, Local::now().fixed_offset())
+            .await?;
+        if !revoked {
+            return Err(KError::NotFound(format!("unable to find api token {}", req.id)).into());
+        }
+        self.audit(
+            user,
+            GLOBAL_NAMESPACE,
+            AuditEntityType::ApiToken,
+            &req.id,
+            AuditAction::Revoke,
+            &req,
+        )
+        .await;
+
+        Ok(ApiTokenRevokeRsp {})
+    }
+
+    /// The api token and its user, the last used time is recorded at most
+    /// once a minute.
+    pub async fn user_by_api_token(&self, token: &str) -> AResult<(User, ApiToken)> {
+        let invalid = || KError::Unauthorized("the api token is invalid or revoked".to_owned());
+        let api_token = self
+            .mapper
+            .api_token_by_hash(&hash_token(token))
+            .await?
+            .ok_or_else(invalid)?;
+        let user = self
+            .mapper
+            .user_by_id(&api_token.user_id)
+            .await?
+            .ok_or_else(invalid)?;
+
+        let now = Local::now().fixed_offset();
+        if api_token
+            .last_used_time
+            .is_none_or(|e| now - e > TimeDelta::minutes(1))
+        {
+            self.mapper.api_token_touch(&api_token.id, now).await?;
+        }
+
+        Ok((user, api_token))
+    }
+
+    /// The user of a valid session token.
+    pub async fn user_by_token(&self, token: &str) -> AResult<User> {
+        self.mapper
+            .user_by_session(&hash_token(token), Local::now().fixed_offset())
+            .await?
+            .ok_or_else(|| {
+                KError::Unauthorized("the session is invalid or expired".to_owned()).into()
+            })
+    }
+
+    /// Append a successful mutation to the audit log, called by the methods
+    /// doing the mutations. The mutation is done already, a failure to log
+    /// it is only reported.
+    pub async fn audit(
+        &self,
+        actor: &User,
+        namespace: &str,
+        entity_type: AuditEntityType,
+        entity_id: &str,
+        action: AuditAction,
+        payload: &impl Serialize,
+    ) {
+        self.audit_as(
+            (&actor.id, &actor.username),
+            namespace,
+            entity_type,
+            entity_id,
+            action,
+            payload,
+        )
+        .await
+    }
+
+    /// `audit` with the id and the name of the actor.
+    async fn audit_as(
+        &self,
+        (actor_id, actor_name): (&str, &str),
+        namespace: &str,
+        entity_type: AuditEntityType,
+        entity_id: &str,
+        action: AuditAction,
+        payload: &impl Serialize,
+    ) {
+        let payload = match serde_json::to_value(payload) {
+            Ok(payload) => payload,
+            Err(err) => {
+                error!("unable to serialize audit payload: {:?}", err);
+                serde_json::Value::Null
+            }
+        };
+        let log = AuditLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            actor_id: actor_id.to_owned(),
+            actor_name: actor_name.to_owned(),
+            namespace: namespace.to_owned(),
+            entity_type,
+            entity_id: entity_id.to_owned(),
+            action,
+            payload,
+            insert_time: Local::now().fixed_offset(),
+        };
+        if let Err(err) = self.mapper.audit_log_insert(&log).await {
+            error!(
+                "unable to audit {} {} {}: {:?}",
+                action, entity_type, entity_id, err
+            );
+        }
+    }
+
+    pub async fn audit_log_query(&self, req: KReq<AuditLogQueryReq>) -> AResult<AuditLogQueryRsp> {
+        if req.page_size == 0 || req.page_size > 500 {
+            return Err(KError::Validation(format!(
+                "invalid page_size {}, it needs 1 to 500",
+                req.page_size
+            ))
+            .into());
+        }
+
+        self.mapper.audit_log_query(req).await
+    }
+
+    async fn llm_chat_session_by_id(
+        &self,
+        namespace: &str,
+        session_id: &str,
+    ) -> AResult<Option<LLMChatSession>> {
+        let rsp = self
+            .mapper
+            .llm_chat_list_sessions(KReq {
+                body: LLMChatListSessionReq {
+                    include_descendants: false,
+                    descendants: vec![],
+                },
+                namespace: namespace.to_owned(),
+            })
+            .await?;
+
+        Ok(rsp.sessions.into_iter().find(|e| e.id == session_id))
+    }
+
+    /// The target must be in the namespace of the request, the link stops
+    /// working if it is moved out.
+    pub async fn share_link_create(
+        &self,
+        user: &User,
+        req: KReq<ShareLinkCreateReq>,
+    ) -> AResult<ShareLinkCreateRsp> {
+        let share = self.config.share.as_ref().ok_or_else(|| {
+            KError::Validation(
+                "share links are disabled, add a [share] section to the config".to_owned(),
+            )
+        })?;
+        let ttl_hours = req.ttl_hours.unwrap_or(share.default_ttl_hours);
+        if ttl_hours == 0 || ttl_hours > 24 * 365 {
+            return Err(KError::Validation(format!(
+                "invalid ttl_hours {}, it needs 1 to {}",
+                ttl_hours,
+                24 * 365
+            ))
+            .into());
+        }
+
+        let found = match req.target_kind {
+            ShareTargetKind::Chnot => self
+                .chnot_by_meta_id(&req.namespace, &req.target_id)
+                .await?
+                .is_some(),
+            ShareTargetKind::LLMChatSession => self
+                .llm_chat_session_by_id(&req.namespace, &req.target_id)
+                .await?
+                .is_some(),
+        };
+        if !found {
+            return Err(KError::NotFound(format!(
+                "unable to find {} {}",
+                req.target_kind, req.target_id
+            ))
+            .into());
+        }
+
+        let now = Local::now().fixed_offset();
+        let share_link = ShareLink {
+            id: uuid::Uuid::new_v4().to_string(),
+            namespace: req.namespace.clone(),
+            target_kind: req.target_kind,
+            target_id: req.target_id.clone(),
+            user_id: user.id.clone(),
+            expire_time: now + TimeDelta::hours(ttl_hours.into()),
+            delete_time: None,
+            insert_time: now,
+        };
+        self.mapper.share_link_insert(&share_link).await?;
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::ShareLink,
+            &share_link.id,
+            AuditAction::Insert,
+            &share_link,
+        )
+        .await;
+
+        let token = sign_share_token(
+            &share.secret,
+            &share_link.id,
+            share_link.expire_time.timestamp(),
+        );
+        Ok(ShareLinkCreateRsp {
+            path: format!("/s/{}", token),
+            token,
+            share_link,
+        })
+    }
+
+    pub async fn share_link_list(&self, namespace: &str) -> AResult<ShareLinkListRsp> {
+        Ok(ShareLinkListRsp {
+            share_links: self.mapper.share_link_list(namespace).await?,
+        })
+    }
+
+    pub async fn share_link_revoke(
+        &self,
+        user: &User,
+        req: KReq<ShareLinkRevokeReq>,
+    ) -> AResult<ShareLinkRevokeRsp> {
+        let revoked = self
+            .mapper
+            .share_link_revoke(&req.namespace, &req.id, Local::now().fixed_offset())
+            .await?;
+        if !revoked {
+            return Err(KError::NotFound(format!("unable to find share link {}", req.id)).into());
+        }
+        self.audit(
+            user,
+            &req.namespace,
+            AuditEntityType::ShareLink,
+            &req.id,
+            AuditAction::Revoke,
+            &req.body,
+        )
+        .await;
+
+        Ok(ShareLinkRevokeRsp {})
+    }
+
+    /// The link of a signed token which is neither expired nor revoked.
+    async fn share_link_by_token(&self, token: &str) -> AResult<ShareLink> {
+        let invalid =
+            || KError::NotFound("the share link is invalid, expired or revoked".to_owned());
+        let share = self.config.share.as_ref().ok_or_else(invalid)?;
+        let now = Local::now().fixed_offset();
+        let id = verify_share_token(&share.secret, token, now.timestamp()).ok_or_else(invalid)?;
+
+        let link = self
+            .mapper
+            .share_link_by_id(id)
+            .await?
+            .filter(|e| e.delete_time.is_none() && e.expire_time > now)
+            .ok_or_else(invalid)?;
+        Ok(link)
+    }
+
+    /// The current content of the shared target.
+    pub async fn share_content(&self, token: &str) -> AResult<ShareContent> {
+        let link = self.share_link_by_token(token).await?;
+        self.share_content_of(&link).await
+    }
+
+    async fn share_content_of(&self, link: &ShareLink) -> AResult<ShareContent> {
+        let missing = || {
+            KError::NotFound(format!(
+                "the shared {} is deleted or moved",
+                link.target_kind
+            ))
+        };
+
+        match link.target_kind {
+            ShareTargetKind::Chnot => {
+                let chnot = self
+                    .chnot_by_meta_id(&link.namespace, &link.target_id)
+                    .await?
+                    .ok_or_else(missing)?;
+                Ok(ShareContent {
+                    title: chnot_title(&chnot.record.content).to_owned(),
+                    records: vec![ShareRecord {
+                        role: None,
+                        content: chnot.record.content,
+                    }],
+                })
+            }
+            ShareTargetKind::LLMChatSession => {
+                let session = self
+                    .llm_chat_session_by_id(&link.namespace, &link.target_id)
+                    .await?
+                    .ok_or_else(missing)?;
+                let mut records = self
+                    .mapper
+                    .llm_chat_session_detail(KReq {
+                        body: LLMChatSessionDetialReq {
+                            session_id: session.id.clone(),
+                        },
+                        namespace: link.namespace.clone(),
+                    })
+                    .await?
+                    .records;
+                // the newest comes first
+                records.reverse();
+
+                Ok(ShareContent {
+                    title: session.title,
+                    records: records
+                        .into_iter()
+                        .map(|e| ShareRecord {
+                            role: Some(e.role),
+                            content: e.content,
+                        })
+                        .collect(),
+                })
+            }
+        }
+    }
+
+    /// A resource referenced by the current content of the shared target.
+    pub async fn share_resource(&self, token: &str, resource_id: &str) -> AResult<Resource> {
+        let link = self.share_link_by_token(token).await?;
+        let content = self.share_content_of(&link).await?;
+        let not_found = || KError::NotFound(format!("unable to find resource {}", resource_id));
+
+        let referenced = content.records.iter().any(|e| {
+            extract_resource_ids(&e.content)
+                .iter()
+                .any(|id| id == resource_id)
+        });
+        if !referenced {
+            return Err(not_found().into());
+        }
+
+        let resource = self.mapper.query_resource_by_id(resource_id).await?;
+        if resource.namespace != link.namespace || resource.delete_time.is_some() {
+            return Err(not_found().into());
+        }
+
+        Ok(resource)
+    }
+}
+
+/// The id is sent in the `K-namespace` header, it must fit in a header
+/// value and in the columns referring it.
+fn check_namespace(id: &str, name: &str) -> EResult {
+    if id.is_empty() || id.len() > 40 || id.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(KError::Validation(format!(
+            "invalid namespace id {:?}, it needs 1 to 40 bytes without spaces",
+            id
+        ))
+        .into());
+    }
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(KError::Validation(format!(
+            "invalid namespace name {:?}, it needs 1 to 100 chars",
+            name
+        ))
+        .into());
+    }
+    Ok(())
 }
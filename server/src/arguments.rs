@@ -5,6 +5,25 @@ use clap::Parser;
 pub struct Arguments {
     #[clap(long, short, help = "Config file to read")]
     pub config: String,
+
+    #[clap(long, help = "Print the pending schema migrations and exit")]
+    pub dry_run_migrations: bool,
+
+    #[clap(long, help = "Rebuild the search index from the database and exit")]
+    pub rebuild_search_index: bool,
+
+    #[clap(long, help = "Parse the tags of every chnot again and exit")]
+    pub rebuild_tags: bool,
+
+    #[clap(long, help = "Parse the links of every chnot again and exit")]
+    pub rebuild_links: bool,
+
+    #[clap(
+        long,
+        value_name = "USERNAME",
+        help = "Create an admin user with the password read from stdin and exit"
+    )]
+    pub create_admin: Option<String>,
 }
 
 unsafe impl Sync for Arguments {}
@@ -1,7 +1,8 @@
 use serde::Deserialize;
 
 use crate::{
-    mapper::{dump::filedump::FileBackupConfig, MapperConfig},
+    mapper::{dump::filedump::FileBackupConfig, versioning::VersioningConfig, MapperConfig},
+    search::SearchConfig,
     server::ServerConfig,
 };
 
@@ -10,24 +11,154 @@ pub struct AttachmentConfig {
     pub base_dir: String,
 }
 
+/// ```toml
+/// [trash]
+/// retention_days = 30
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrashConfig {
+    /// Chnots deleted longer than this are removed physically.
+    pub retention_days: u32,
+    #[serde(default = "default_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+fn default_purge_interval_secs() -> u64 {
+    3600
+}
+
+/// ```toml
+/// [auth]
+/// session_ttl_hours = 720
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    /// A session is valid for this long after the login.
+    #[serde(default = "default_session_ttl_hours")]
+    pub session_ttl_hours: u32,
+}
+
+fn default_session_ttl_hours() -> u32 {
+    24 * 30
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            session_ttl_hours: default_session_ttl_hours(),
+        }
+    }
+}
+
+/// ```toml
+/// [share]
+/// secret = "a long random string"
+/// default_ttl_hours = 168
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShareConfig {
+    /// Signs the share tokens, changing it breaks every issued link.
+    pub secret: String,
+    #[serde(default = "default_share_ttl_hours")]
+    pub default_ttl_hours: u32,
+}
+
+fn default_share_ttl_hours() -> u32 {
+    24 * 7
+}
+
+/// ```toml
+/// [limit]
+/// json_body_bytes = 2097152
+/// upload_body_bytes = 1073741824
+///
+/// [limit.rate]
+/// burst = 120
+/// per_second = 20
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct LimitConfig {
+    /// The largest body of any route but the uploads.
+    #[serde(default = "default_json_body_bytes")]
+    pub json_body_bytes: usize,
+    /// The largest body of a resource upload.
+    #[serde(default = "default_upload_body_bytes")]
+    pub upload_body_bytes: usize,
+    /// Requests are not rate limited without this section.
+    pub rate: Option<RateLimitConfig>,
+}
+
+fn default_json_body_bytes() -> usize {
+    2 * 1024 * 1024
+}
+
+fn default_upload_body_bytes() -> usize {
+    1024 * 1024 * 1024
+}
+
+impl Default for LimitConfig {
+    fn default() -> Self {
+        Self {
+            json_body_bytes: default_json_body_bytes(),
+            upload_body_bytes: default_upload_body_bytes(),
+            rate: None,
+        }
+    }
+}
+
+/// A token bucket per api token, or per client ip for the other requests.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    /// The requests allowed at once.
+    pub burst: u32,
+    /// The requests allowed per second in the long run.
+    pub per_second: f64,
+    /// Take the client ip from `X-Forwarded-For`, only behind a reverse
+    /// proxy which sets it.
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
+    /// The namespace of requests without the `K-namespace` header, they are
+    /// rejected without it. It is created on startup if missing.
+    pub default_namespace: Option<String>,
     pub server: Option<ServerConfig>,
     pub mapper: MapperConfig,
     pub file_backup: Option<FileBackupConfig>,
     pub attachment: AttachmentConfig,
+    #[serde(default)]
+    pub versioning: VersioningConfig,
+    /// The embedded search index is disabled without this section.
+    pub search: Option<SearchConfig>,
+    /// The trash is kept forever without this section.
+    pub trash: Option<TrashConfig>,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Share links are disabled without this section.
+    pub share: Option<ShareConfig>,
+    #[serde(default)]
+    pub limit: LimitConfig,
 }
 
+#[cfg(test)]
 pub mod tests {
+    use crate::mapper::MapperConfig;
+
     #[test]
     fn test_db_deserialize() {
         let toml_str = r#"
-        [db_config]
+        [mapper]
         type = "sqlite"
         filepath = "/home/123"
+
+        [attachment]
+        base_dir = "/home/123/attachments"
     "#;
 
         let config: super::Config = toml::from_str(toml_str).unwrap();
         println!("{:?}", config);
+        assert!(matches!(config.mapper, MapperConfig::Sqlite(_)));
     }
 }
@@ -0,0 +1,130 @@
+use std::fmt::Display;
+
+use axum::{
+    http::{header::RETRY_AFTER, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tracing::{error, info};
+
+use crate::mapper::query::QueryParseError;
+
+/// Errors a client can act on, any other error is an internal one.
+///
+/// They travel through `anyhow::Error` like every other error, so a
+/// mapper returns `Err(KError::NotFound(..).into())` and the response finds
+/// the kind back by downcasting.
+#[derive(Debug)]
+pub enum KError {
+    NotFound(String),
+    /// The request is malformed or breaks a rule.
+    Validation(String),
+    /// A `Validation` of the query syntax, the response has the position
+    /// of the error too.
+    QueryParse(QueryParseError),
+    /// The request conflicts with the current state, e.g. a taken id.
+    Conflict(String),
+    Unauthorized(String),
+    /// The user is known but not allowed to do it.
+    Forbidden(String),
+    /// The body is over the limit of the route.
+    PayloadTooLarge(String),
+    /// The rate limit of the client is used up, sent with `Retry-After`.
+    TooManyRequests {
+        retry_after_secs: u64,
+    },
+    Internal(anyhow::Error),
+}
+
+impl KError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            KError::NotFound(_) => StatusCode::NOT_FOUND,
+            KError::Validation(_) | KError::QueryParse(_) => StatusCode::BAD_REQUEST,
+            KError::Conflict(_) => StatusCode::CONFLICT,
+            KError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            KError::Forbidden(_) => StatusCode::FORBIDDEN,
+            KError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            KError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            KError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Clients match on the code, the message may change.
+    pub fn code(&self) -> &'static str {
+        match self {
+            KError::NotFound(_) => "not_found",
+            KError::Validation(_) | KError::QueryParse(_) => "validation",
+            KError::Conflict(_) => "conflict",
+            KError::Unauthorized(_) => "unauthorized",
+            KError::Forbidden(_) => "forbidden",
+            KError::PayloadTooLarge(_) => "payload_too_large",
+            KError::TooManyRequests { .. } => "too_many_requests",
+            KError::Internal(_) => "internal",
+        }
+    }
+}
+
+impl Display for KError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KError::NotFound(msg)
+            | KError::Validation(msg)
+            | KError::Conflict(msg)
+            | KError::Unauthorized(msg)
+            | KError::Forbidden(msg)
+            | KError::PayloadTooLarge(msg) => f.write_str(msg),
+            KError::QueryParse(err) => Display::fmt(err, f),
+            KError::TooManyRequests { retry_after_secs } => write!(
+                f,
+                "too many requests, retry after {} seconds",
+                retry_after_secs
+            ),
+            KError::Internal(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for KError {}
+
+impl From<anyhow::Error> for KError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<KError>() {
+            Ok(err) => err,
+            // the query is written by the user
+            Err(err) => match err.downcast::<QueryParseError>() {
+                Ok(err) => KError::QueryParse(err),
+                Err(err) => KError::Internal(err),
+            },
+        }
+    }
+}
+
+impl IntoResponse for KError {
+    fn into_response(self) -> Response {
+        let mut body = json!({ "code": self.code(), "msg": self.to_string() });
+        let mut headers = HeaderMap::new();
+
+        match &self {
+            KError::Internal(err) => {
+                // the debug format has the backtrace
+                error!("Error Occured: {:?}", err);
+                #[cfg(debug_assertions)]
+                {
+                    body["backtrace"] = err.backtrace().to_string().into();
+                }
+            }
+            KError::TooManyRequests { retry_after_secs } => {
+                headers.insert(RETRY_AFTER, HeaderValue::from(*retry_after_secs));
+            }
+            KError::QueryParse(err) => {
+                info!("Rejected request: {}", self);
+                body["position"] = err.position.into();
+            }
+            _ => info!("Rejected request: {}", self),
+        }
+
+        (self.status(), headers, Json(body)).into_response()
+    }
+}
@@ -1,4 +1,5 @@
 use app::{AppState, ShareAppState};
+use arc_swap::ArcSwap;
 use arguments::Arguments;
 use chin_tools::wrapper::anyhow::{AResult, EResult};
 use clap::Parser;
@@ -10,16 +11,21 @@ use mapper::{
     },
     MapperType,
 };
+use search::SearchIndex;
 use server::controller;
-use tracing::{info, Level};
+use std::time::Duration;
+use tracing::{error, info, Level};
 use tracing_log::LogTracer;
+use util::{namespace::NamespaceTree, ratelimit::RateLimiter};
 
 pub(crate) mod app;
 pub(crate) mod arguments;
 pub(crate) mod config;
+pub(crate) mod error;
 pub(crate) mod magics;
 pub(crate) mod mapper;
 pub(crate) mod model;
+pub(crate) mod search;
 pub(crate) mod server;
 pub(crate) mod toent;
 pub(crate) mod util;
@@ -45,16 +51,55 @@ async fn main() -> EResult {
     let config_file = tokio::fs::read_to_string(args.config.as_str()).await?;
     let config: Config = toml::from_str(config_file.as_str())?;
     let mapper = AResult::<MapperType>::from(config.mapper.clone().into())?;
+    if args.dry_run_migrations {
+        mapper.print_pending_migrations().await?;
+        return Ok(());
+    }
     mapper.ensure_tables().await?;
+    let search = config.search.as_ref().map(SearchIndex::open).transpose()?;
     let state = AppState {
         config: config.clone(),
         mapper,
+        search,
+        namespaces: ArcSwap::from_pointee(NamespaceTree::default()),
+        rate_limiter: config
+            .limit
+            .rate
+            .as_ref()
+            .map(|e| RateLimiter::new(e.burst, e.per_second)),
+        resource_files: Default::default(),
     };
+    state.refresh_namespaces().await?;
+    state.ensure_namespaces().await?;
+    if args.rebuild_search_index {
+        let count = state.rebuild_search_index().await?;
+        info!("Rebuilt search index with {} chnots.", count);
+        return Ok(());
+    }
+    if args.rebuild_tags {
+        let count = state.rebuild_tags().await?;
+        info!("Rebuilt tags of {} chnots.", count);
+        return Ok(());
+    }
+    if args.rebuild_links {
+        let count = state.rebuild_links().await?;
+        info!("Rebuilt links of {} chnots.", count);
+        return Ok(());
+    }
+    if let Some(username) = args.create_admin.as_deref() {
+        let password = rpassword::prompt_password(format!("Password for {}: ", username))?;
+        let user = state.user_create(None, username, &password, true).await?;
+        info!("Created admin user {}.", user.username);
+        return Ok(());
+    }
     let state: ShareAppState = state.into();
     {
         let state = state.clone();
-        std::thread::spawn(|| {
-            futures::executor::block_on(async move {
+        // the dump worker is not Send, run it on its own thread but keep the
+        // tokio context, the sqlite pool needs it for blocking calls.
+        let handle = tokio::runtime::Handle::current();
+        std::thread::spawn(move || {
+            handle.block_on(async move {
                 let worker = FileDumpWorker::new(&state, "chnots", BackupType::All)
                     .await
                     .unwrap();
@@ -69,6 +114,22 @@ async fn main() -> EResult {
         });
     }
 
+    if let Some(trash) = config.trash.clone() {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(trash.purge_interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                match state.purge_trash(trash.retention_days).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Purged {} chnots from the trash.", count),
+                    Err(err) => error!("unable to purge the trash: {:?}", err),
+                }
+            }
+        });
+    }
+
     controller::serve(state).await?;
 
     Ok(())
@@ -0,0 +1,113 @@
+use std::str::FromStr;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::KError,
+    model::dto::chnot::{Chnot, ChnotSort},
+};
+
+/// The position of the last chnot of a page. `chnot_query` continues after
+/// it instead of skipping an offset, so a page does not drift when chnots
+/// are inserted meanwhile.
+///
+/// It is opaque to the client, the encoded string is sent back as it is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChnotCursor {
+    /// Only used by `ChnotSort::PinnedFirst`.
+    #[serde(rename = "p")]
+    pub pin_time: Option<DateTime<FixedOffset>>,
+    /// The time of the sort key, the creation or the edit time.
+    #[serde(rename = "t")]
+    pub insert_time: DateTime<FixedOffset>,
+    /// The record id, it breaks ties of the times.
+    pub id: String,
+}
+
+impl ChnotCursor {
+    pub fn of(chnot: &Chnot, sort: ChnotSort) -> ChnotCursor {
+        ChnotCursor {
+            pin_time: match sort {
+                ChnotSort::PinnedFirst => chnot.meta.pin_time,
+                ChnotSort::Created | ChnotSort::Edited => None,
+            },
+            insert_time: match sort {
+                ChnotSort::Created => chnot.meta.insert_time,
+                ChnotSort::PinnedFirst | ChnotSort::Edited => chnot.record.insert_time,
+            },
+            id: chnot.record.id.clone(),
+        }
+    }
+
+    /// The cursor after the last chnot if the page is full, a page with
+    /// fewer chnots is the last one.
+    pub fn next_of(chnots: &[Chnot], sort: ChnotSort, page_size: u64) -> Option<String> {
+        if (chnots.len() as u64) < page_size {
+            return None;
+        }
+        chnots.last().map(|e| ChnotCursor::of(e, sort).encode())
+    }
+
+    pub fn encode(&self) -> String {
+        // the offsets are kept, sqlite compares the times with them
+        let json = serde_json::to_string(self).expect("cursor is always serializable");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+}
+
+/// The cursor comes from the client, a broken one is its mistake.
+fn invalid_cursor<E>(_: E) -> KError {
+    KError::Validation("invalid cursor".to_owned())
+}
+
+impl FromStr for ChnotCursor {
+    type Err = KError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let json = URL_SAFE_NO_PAD.decode(s).map_err(invalid_cursor)?;
+        serde_json::from_slice(&json).map_err(invalid_cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::ChnotCursor;
+    use crate::error::KError;
+
+    #[test]
+    fn round_trip() {
+        let cursor = ChnotCursor {
+            pin_time: None,
+            insert_time: DateTime::parse_from_rfc3339("2025-01-02T03:04:05.678+08:00").unwrap(),
+            id: "0194-abc".to_owned(),
+        };
+        let encoded = cursor.encode();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(encoded.parse::<ChnotCursor>().unwrap(), cursor);
+
+        let pinned = ChnotCursor {
+            pin_time: Some(cursor.insert_time),
+            ..cursor
+        };
+        let decoded = pinned.encode().parse::<ChnotCursor>().unwrap();
+        assert_eq!(decoded, pinned);
+        // equality of times ignores the offsets
+        assert_eq!(decoded.insert_time.offset(), pinned.insert_time.offset());
+    }
+
+    #[test]
+    fn invalid() {
+        for s in ["not a cursor!", "e30"] {
+            assert!(matches!(
+                s.parse::<ChnotCursor>(),
+                Err(KError::Validation(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+
+use super::DeserializeMapper;
+use crate::{
+    mapper::AclMapper,
+    model::db::acl::{AclSubjectKind, NamespaceAcl, UserGroup, UserGroupMember},
+};
+
+use super::Postgres;
+
+impl AclMapper for Postgres {
+    async fn namespace_acl_list(&self, namespace: &str) -> AResult<Vec<NamespaceAcl>> {
+        self.client()
+            .await?
+            .query(
+                "select * from namespace_acl where namespace = $1 order by insert_time",
+                &[&namespace],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_namespace_acl)
+            .collect()
+    }
+
+    async fn namespace_acl_of_user(&self, user_id: &str) -> AResult<Vec<NamespaceAcl>> {
+        self.client()
+            .await?
+            .query(
+                "select * from namespace_acl where (subject_kind = 'user' and subject_id = $1)
+or (subject_kind = 'group' and subject_id in (select group_id from user_group_member where user_id = $1))",
+                &[&user_id],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_namespace_acl)
+            .collect()
+    }
+
+    async fn namespace_acl_overwrite(&self, acl: &NamespaceAcl) -> EResult {
+        self.client()
+            .await?
+            .execute(
+                "insert into namespace_acl(id, namespace, subject_kind, subject_id, role, insert_time) values($1, $2, $3, $4, $5, $6)
+on conflict (namespace, subject_kind, subject_id) do update set role = excluded.role",
+                &[
+                    &acl.id,
+                    &acl.namespace,
+                    &acl.subject_kind.to_string(),
+                    &acl.subject_id,
+                    &acl.role.to_string(),
+                    &acl.insert_time,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn namespace_acl_delete(
+        &self,
+        namespace: &str,
+        subject_kind: AclSubjectKind,
+        subject_id: &str,
+    ) -> AResult<bool> {
+        let count = self
+            .client()
+            .await?
+            .execute(
+                "delete from namespace_acl where namespace = $1 and subject_kind = $2 and subject_id = $3",
+                &[&namespace, &subject_kind.to_string(), &subject_id],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn user_group_insert(&self, group: &UserGroup) -> AResult<bool> {
+        let count = self
+            .client()
+            .await?
+            .execute(
+                "insert into user_group(id, name, insert_time) values($1, $2, $3) on conflict do nothing",
+                &[&group.id, &group.name, &group.insert_time],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn user_group_list(&self) -> AResult<Vec<UserGroup>> {
+        self.client()
+            .await?
+            .query("select * from user_group order by name", &[])
+            .await?
+            .into_iter()
+            .map(Self::to_user_group)
+            .collect()
+    }
+
+    async fn user_group_member_list(&self) -> AResult<Vec<UserGroupMember>> {
+        self.client()
+            .await?
+            .query("select * from user_group_member order by insert_time", &[])
+            .await?
+            .into_iter()
+            .map(Self::to_user_group_member)
+            .collect()
+    }
+
+    async fn user_group_member_insert(&self, member: &UserGroupMember) -> EResult {
+        self.client()
+            .await?
+            .execute(
+                "insert into user_group_member(group_id, user_id, insert_time) values($1, $2, $3) on conflict do nothing",
+                &[&member.group_id, &member.user_id, &member.insert_time],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn user_group_member_delete(&self, group_id: &str, user_id: &str) -> AResult<bool> {
+        let count = self
+            .client()
+            .await?
+            .execute(
+                "delete from user_group_member where group_id = $1 and user_id = $2",
+                &[&group_id, &user_id],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+}
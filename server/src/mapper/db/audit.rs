@@ -0,0 +1,80 @@
+use anyhow::Context;
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+
+use super::sql::{LimitOffset, PlaceHolderType, SqlSegBuilder, Wheres};
+use super::DeserializeMapper;
+use crate::{
+    mapper::AuditLogMapper,
+    model::{
+        db::audit::AuditLog,
+        dto::{
+            audit::{AuditLogQueryReq, AuditLogQueryRsp},
+            KReq,
+        },
+    },
+    to_sql,
+};
+
+use super::Postgres;
+
+impl AuditLogMapper for Postgres {
+    async fn audit_log_insert(&self, log: &AuditLog) -> EResult {
+        self.client()
+            .await?
+            .execute(
+                "insert into audit_log(id, actor_id, actor_name, namespace, entity_type, entity_id, action, payload, insert_time) values($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                &[
+                    &log.id,
+                    &log.actor_id,
+                    &log.actor_name,
+                    &log.namespace,
+                    &log.entity_type.to_string(),
+                    &log.entity_id,
+                    &log.action.to_string(),
+                    &log.payload.to_string(),
+                    &log.insert_time,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn audit_log_query(&self, req: KReq<AuditLogQueryReq>) -> AResult<AuditLogQueryRsp> {
+        let query = SqlSegBuilder::new()
+            .raw("select * from audit_log")
+            .r#where(Wheres::and([
+                Wheres::equal("namespace", req.namespace.as_str()),
+                Wheres::if_some(req.actor_id.as_deref(), |e| Wheres::equal("actor_id", e)),
+                Wheres::if_some(req.entity_type, |e| {
+                    Wheres::equal("entity_type", e.to_string())
+                }),
+                Wheres::if_some(req.entity_id.as_deref(), |e| Wheres::equal("entity_id", e)),
+                Wheres::if_some(req.action, |e| Wheres::equal("action", e.to_string())),
+                Wheres::if_some(req.after, |t| Wheres::ge("insert_time", t)),
+                Wheres::if_some(req.before, |t| Wheres::lt("insert_time", t)),
+            ]))
+            .raw("order by insert_time desc, id")
+            .custom(
+                LimitOffset::new(req.page_size)
+                    .offset_if_some(Some(req.start_index))
+                    .to_box(),
+            )
+            .build(&mut PlaceHolderType::dollar_number())
+            .context("Unable to build args")?;
+
+        let logs = self
+            .client()
+            .await?
+            .query(query.seg.as_str(), to_sql!(query.values))
+            .await?
+            .into_iter()
+            .map(Self::to_audit_log)
+            .collect::<AResult<Vec<AuditLog>>>()?;
+
+        Ok(AuditLogQueryRsp {
+            logs,
+            start_index: req.start_index,
+        })
+    }
+}
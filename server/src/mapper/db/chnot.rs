@@ -1,16 +1,24 @@
-use super::sql::{LimitOffset, PlaceHolderType, SqlSegBuilder, SqlUpdater, Wheres};
+use super::sql::{LimitOffset, PlaceHolderType, SqlSegBuilder, Wheres};
 use crate::{
-    mapper::ChnotMapper,
+    error::KError,
+    mapper::{
+        cursor::ChnotCursor,
+        query::{ChnotQuery, QueryFilter, QueryTerm},
+        versioning::VersionPolicy,
+        ChnotMapper, ChnotTrashMapper, DeserializeMapper,
+    },
     model::{
-        db::chnot::{ChnotKind, ChnotMetadata, ChnotRecord},
+        db::chnot::{ChnotKind, ChnotMetadata, ChnotMove, ChnotRecord},
         dto::KReq,
     },
     to_sql,
+    util::{activity::DayActivity, share::escape_html},
 };
-use chin_tools::wrapper::anyhow::{AResult, EResult};
-use chrono::{DateTime, FixedOffset, Local, TimeDelta};
+use chin_tools::wrapper::anyhow::AResult;
+use chrono::{DateTime, FixedOffset, Local};
 use postgres_types::{to_sql_checked, FromSql, ToSql};
 use std::str::FromStr;
+use tokio_postgres::Row;
 use tracing::{error, info};
 
 use crate::model::dto::chnot::*;
@@ -53,38 +61,215 @@ impl ToSql for ChnotKind {
     to_sql_checked!();
 }
 
-impl ChnotMapper for Postgres {
-    async fn ensure_table_chnot_record(&self) -> EResult {
-        self.create_table(
-            "create table IF NOT EXISTS chnot_record (
-    id VARCHAR(40) NOT NULL,
-    meta_id VARCHAR(40) NOT NULL,
-    content TEXT NOT NULL,
-    omit_time timestamptz DEFAULT NULL,
-    insert_time timestamptz NOT NULL default CURRENT_TIMESTAMP,
-    primary key (id)
-)",
-        )
-        .await
+pub(super) fn row_to_chnot(row: &Row) -> AResult<Chnot> {
+    let record = ChnotRecord {
+        id: row.try_get("rid")?,
+        meta_id: row.try_get("mid")?,
+        content: row.try_get("content")?,
+        omit_time: row.try_get("omit_time")?,
+        insert_time: row.try_get("version_time")?,
+    };
+    let meta = ChnotMetadata {
+        id: row.try_get("mid")?,
+        namespace: row.try_get("namespace")?,
+        kind: row.try_get("kind")?,
+        pin_time: row.try_get("pin_time")?,
+        archive_time: row.try_get("archive_time")?,
+        delete_time: row.try_get("delete_time")?,
+        update_time: row.try_get("update_time")?,
+        insert_time: row.try_get("init_time")?,
+    };
+    Ok(Chnot { record, meta })
+}
+
+/// `tag_ids` is the chnot ids of a tag term.
+fn query_term_to_wheres<'a>(term: &'a QueryTerm, tag_ids: Option<&[String]>) -> Wheres<'a> {
+    let wheres = match &term.filter {
+        QueryFilter::Text(text) => Wheres::ilike("content", text.as_str()),
+        QueryFilter::Tag(_) => match tag_ids {
+            Some(ids) if !ids.is_empty() => Wheres::r#in("m.id", ids.to_vec()),
+            // nothing to exclude, a tag without chnots is returned early
+            _ => return Wheres::none(),
+        },
+        QueryFilter::Kind(kind) => Wheres::equal("kind", kind.as_str()),
+        QueryFilter::Pinned(true) => Wheres::is_not_null("pin_time"),
+        QueryFilter::Pinned(false) => Wheres::is_null("pin_time"),
+        QueryFilter::Before(time) => Wheres::lt("m.insert_time", *time),
+        QueryFilter::After(time) => Wheres::ge("m.insert_time", *time),
+    };
+
+    if term.exclude {
+        Wheres::not(wheres)
+    } else {
+        wheres
+    }
+}
+
+/// The condition appended to a hand-written `WHERE` of `m.archive_time`.
+fn archive_clause(filter: ChnotArchiveFilter) -> &'static str {
+    match filter {
+        ChnotArchiveFilter::Exclude => "AND m.archive_time is null",
+        ChnotArchiveFilter::Include => "",
+        ChnotArchiveFilter::Only => "AND m.archive_time is not null",
+    }
+}
+
+/// Wrap the hits of `ts_headline`, control characters are never html so
+/// the content can be escaped before they become `<mark>`.
+const HIT_START: &str = "\u{1}";
+const HIT_STOP: &str = "\u{2}";
+
+fn snippet_to_html(snippet: &str) -> String {
+    escape_html(snippet)
+        .replace(HIT_START, "<mark>")
+        .replace(HIT_STOP, "</mark>")
+}
+
+fn time_range_to_wheres(range: &ChnotTimeRange) -> Wheres<'static> {
+    Wheres::and([
+        Wheres::if_some(range.inserted_after, |t| Wheres::ge("m.insert_time", t)),
+        Wheres::if_some(range.inserted_before, |t| Wheres::lt("m.insert_time", t)),
+        Wheres::if_some(range.updated_after, |t| Wheres::ge("m.update_time", t)),
+        Wheres::if_some(range.updated_before, |t| Wheres::lt("m.update_time", t)),
+        Wheres::if_some(range.version_after, |t| Wheres::ge("r.insert_time", t)),
+        Wheres::if_some(range.version_before, |t| Wheres::lt("r.insert_time", t)),
+    ])
+}
+
+/// `time` and `id` is after the cursor in a descending order.
+fn after_time<'a>(column: &'a str, cursor: &'a ChnotCursor) -> Wheres<'a> {
+    Wheres::or([
+        Wheres::lt(column, cursor.insert_time),
+        Wheres::and([
+            Wheres::equal(column, cursor.insert_time),
+            Wheres::lt("r.id", cursor.id.as_str()),
+        ]),
+    ])
+}
+
+/// The chnots after the cursor in the order of `sort`.
+fn cursor_to_wheres(sort: ChnotSort, cursor: &ChnotCursor) -> Wheres<'_> {
+    match (sort, cursor.pin_time) {
+        (ChnotSort::Created, _) => after_time("m.insert_time", cursor),
+        (ChnotSort::Edited, _) => after_time("r.insert_time", cursor),
+        // the not pinned ones are after all pinned ones
+        (ChnotSort::PinnedFirst, Some(pin_time)) => Wheres::or([
+            Wheres::is_null("m.pin_time"),
+            Wheres::lt("m.pin_time", pin_time),
+            Wheres::and([
+                Wheres::equal("m.pin_time", pin_time),
+                after_time("r.insert_time", cursor),
+            ]),
+        ]),
+        (ChnotSort::PinnedFirst, None) => Wheres::and([
+            Wheres::is_null("m.pin_time"),
+            after_time("r.insert_time", cursor),
+        ]),
     }
+}
 
-    async fn ensure_table_chnot_metadata(&self) -> EResult {
-        self.create_table(
-            "create table IF NOT EXISTS chnot_metadata (
-    id VARCHAR(40) NOT NULL,
-    namespace VARCHAR(100) NOT NULL,
-    kind VARCHAR(100) NOT NULL,
-    pin_time timestamptz DEFAULT NULL,
-    delete_time timestamptz DEFAULT NULL,    
-    update_time timestamptz DEFAULT NULL,
-    insert_time timestamptz NOT NULL default CURRENT_TIMESTAMP,
-    primary key (id)
-)",
-        )
-        .await
+fn sort_to_order_by(sort: ChnotSort) -> &'static str {
+    match sort {
+        // postgres puts nulls first for desc by default
+        ChnotSort::PinnedFirst => {
+            "ORDER BY m.pin_time DESC NULLS LAST, r.insert_time DESC, r.id DESC"
+        }
+        ChnotSort::Created => "ORDER BY m.insert_time DESC, r.id DESC",
+        ChnotSort::Edited => "ORDER BY r.insert_time DESC, r.id DESC",
     }
+}
+
+impl Postgres {
+    /// Ranked search on the generated `content_tsv` column, only the current
+    /// records of the namespace and the requested descendants are searched.
+    async fn chnot_query_fulltext(
+        &self,
+        req: &KReq<ChnotQueryReq>,
+        query: &str,
+    ) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
+        let namespaces: Vec<String> = std::iter::once(req.namespace.clone())
+            .chain(req.descendants.iter().cloned())
+            .collect();
+        let limit = req.page_size as i64;
+        let offset = req.start_index as i64;
+        let headline_options = format!(
+            "StartSel={}, StopSel={}, MaxFragments=3",
+            HIT_START, HIT_STOP
+        );
+        let mut params: Vec<&(dyn ToSql + Sync)> =
+            vec![&namespaces, &query, &limit, &offset, &headline_options];
+
+        let range = &req.time_range;
+        let mut time_clause = String::new();
+        for (column, op, time) in [
+            ("m.insert_time", ">=", &range.inserted_after),
+            ("m.insert_time", "<", &range.inserted_before),
+            ("m.update_time", ">=", &range.updated_after),
+            ("m.update_time", "<", &range.updated_before),
+            ("r.insert_time", ">=", &range.version_after),
+            ("r.insert_time", "<", &range.version_before),
+        ] {
+            if let Some(time) = time {
+                params.push(time);
+                time_clause += &format!(" AND {} {} ${}", column, op, params.len());
+            }
+        }
+
+        let rows = self
+            .client()
+            .await?
+            .query(
+                &format!(
+                    "SELECT r.id as rid, r.content, r.omit_time, r.insert_time as version_time,
+    m.id as mid, m.namespace, m.kind, m.pin_time, m.archive_time, m.delete_time, m.update_time, m.insert_time as init_time,
+    ts_rank_cd(r.content_tsv, q) as rank,
+    ts_headline('simple', r.content, q, $5) as snippet
+FROM chnot_record r JOIN chnot_metadata m ON r.meta_id = m.id, websearch_to_tsquery('simple', $2) q
+WHERE m.namespace = ANY($1) AND r.omit_time is null AND m.delete_time is null AND r.content_tsv @@ q {}{}
+ORDER BY rank DESC, r.insert_time DESC
+LIMIT $3 OFFSET $4",
+                    archive_clause(req.archived),
+                    time_clause
+                ),
+                &params,
+            )
+            .await?;
 
-    async fn chnot_overwrite(&self, req: KReq<ChnotOverwriteReq>) -> AResult<ChnotOverwriteRsp> {
+        let mut data = Vec::with_capacity(rows.len());
+        let mut highlights = Vec::with_capacity(rows.len());
+        for row in rows.iter() {
+            let highlight = row_to_chnot(row).and_then(|chnot| {
+                let highlight = ChnotHighlight {
+                    record_id: chnot.record.id.clone(),
+                    rank: row.try_get("rank")?,
+                    snippet: snippet_to_html(row.try_get("snippet")?),
+                };
+                Ok((chnot, highlight))
+            });
+            match highlight {
+                Ok((chnot, highlight)) => {
+                    data.push(chnot);
+                    highlights.push(highlight);
+                }
+                Err(err) => error!("unable to remap: {:?}", err),
+            }
+        }
+
+        Ok(ChnotQueryRsp {
+            data,
+            start_index: req.start_index,
+            highlights,
+            next_cursor: None,
+        })
+    }
+}
+
+impl ChnotMapper for Postgres {
+    async fn chnot_overwrite(
+        &self,
+        req: KReq<ChnotOverwriteReq>,
+        policy: &VersionPolicy,
+    ) -> AResult<ChnotOverwriteRsp> {
         tracing::debug!("begin to overwrite chnot, {}", req.id);
         let chnot = &req.body.chnot;
 
@@ -92,6 +277,26 @@ impl ChnotMapper for Postgres {
 
         let transaction = client.build_transaction().start().await?;
 
+        // the row is locked so a concurrent move can not slip in between
+        let meta_insert_time: Option<DateTime<FixedOffset>> = match transaction
+            .query_opt(
+                "select namespace, insert_time from chnot_metadata where id = $1 for update",
+                &[&req.meta_id],
+            )
+            .await?
+        {
+            Some(row) => {
+                let namespace: String = row.try_get("namespace")?;
+                if namespace != req.namespace {
+                    return Err(
+                        KError::NotFound(format!("unable to find chnot {}", req.meta_id)).into(),
+                    );
+                }
+                Some(row.try_get("insert_time")?)
+            }
+            None => None,
+        };
+
         let old_record = transaction
             .query_opt(
                 "select id, content, insert_time from chnot_record where meta_id = $1 and omit_time is null",
@@ -105,14 +310,6 @@ impl ChnotMapper for Postgres {
                 Some((id, content, insert_time))
             });
 
-        let meta_insert_time: Option<DateTime<FixedOffset>> = transaction
-            .query_opt(
-                "select insert_time from chnot_metadata where id = $1",
-                &[&req.meta_id],
-            )
-            .await?
-            .and_then(|e| e.try_get("insert_time").ok());
-
         transaction.execute(
             "insert into chnot_metadata(id, insert_time, namespace, kind) values($1, $2, $3, $4) on CONFLICT (id) DO UPDATE SET update_time = $2",
             &[
@@ -123,18 +320,22 @@ impl ChnotMapper for Postgres {
             ]
         ).await?;
 
-        let id = if let Some((old_id, old_content, old_insert_time)) = old_record.as_ref() {
-            let distince = textdistance::str::sift4_simple(&old_content, &chnot.content);
-            if distince <= 50
-                && chnot.insert_time.signed_duration_since(old_insert_time) < TimeDelta::hours(1)
-            {
-                old_id
-            } else {
-                &chnot.id
-            }
-        } else {
-            &chnot.id
+        let new_version = policy.is_new_version(
+            old_record
+                .as_ref()
+                .map(|(_, content, insert_time)| (content.as_str(), insert_time)),
+            &chnot.content,
+            &chnot.insert_time,
+            req.checkpoint,
+        );
+
+        // a merged edit keeps the id of the record, the ids handed out by
+        // the history stay valid
+        let id = match old_record.as_ref() {
+            Some((old_id, _, _)) if !new_version => old_id,
+            _ => &chnot.id,
         };
+        let record_id = id.clone();
 
         if let Some((old_id, _, _)) = old_record.as_ref() {
             if &chnot.id == id {
@@ -148,13 +349,12 @@ impl ChnotMapper for Postgres {
         }
 
         transaction.execute(
-            "insert into chnot_record(id, meta_id, content, insert_time) values($1, $2, $3, $4) on CONFLICT (id) DO UPDATE SET content=$3,id=$5,insert_time=$4",
+            "insert into chnot_record(id, meta_id, content, insert_time) values($1, $2, $3, $4) on CONFLICT (id) DO UPDATE SET content=$3,insert_time=$4",
             &[
                 id,
                 &chnot.meta_id,
                 &chnot.content,
                 &chnot.insert_time,
-                &chnot.id
             ]
         ).await?;
 
@@ -167,34 +367,94 @@ impl ChnotMapper for Postgres {
                     namespace: req.namespace.clone(),
                     kind: req.kind.to_string(),
                     pin_time: None,
+                    archive_time: None,
                     delete_time: None,
                     update_time: None,
                     insert_time: meta_insert_time.unwrap_or(req.insert_time.clone()),
                 },
-                record: req.chnot.clone(),
+                record: ChnotRecord {
+                    id: record_id,
+                    ..req.chnot.clone()
+                },
             },
+            new_version,
         })
     }
 
     async fn chnot_delete(&self, req: KReq<ChnotDeletionReq>) -> AResult<ChnotDeletionRsp> {
         let client = self.client().await?;
 
-        client
+        if !req.logic {
+            client
+                .query_opt(
+                    "select id from chnot_metadata where id = $1 and namespace = $2",
+                    &[&req.chnot_id, &req.namespace],
+                )
+                .await?
+                .ok_or_else(|| {
+                    KError::NotFound(format!("unable to find chnot {}", req.chnot_id))
+                })?;
+            self.chnot_purge(&[req.chnot_id.clone()]).await?;
+            return Ok(ChnotDeletionRsp {});
+        }
+
+        let count = client
             .execute(
-                "update chnot_metadata set delete_time = CURRENT_TIMESTAMP where id = $1",
-                &[&req.chnot_id],
+                "update chnot_metadata set delete_time = CURRENT_TIMESTAMP where id = $1 and namespace = $2",
+                &[&req.chnot_id, &req.namespace],
             )
             .await?;
+        if count == 0 {
+            return Err(KError::NotFound(format!("unable to find chnot {}", req.chnot_id)).into());
+        }
 
         Ok(ChnotDeletionRsp {})
     }
 
     async fn chnot_query(&self, req: KReq<ChnotQueryReq>) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
+        if let (ChnotSearchMode::FullText, Some(query)) = (req.search_mode, req.query.as_ref()) {
+            return self.chnot_query_fulltext(&req, query).await;
+        }
+
+        let query = req
+            .query
+            .as_deref()
+            .map(ChnotQuery::from_str)
+            .transpose()?
+            .unwrap_or_default();
+        let namespaces: Vec<String> =
+            std::iter::once(query.namespace.clone().unwrap_or(req.namespace.clone()))
+                .chain(req.descendants.iter().cloned())
+                .collect();
+        let cursor = req
+            .cursor
+            .as_deref()
+            .map(ChnotCursor::from_str)
+            .transpose()?;
+
+        // tags live in chnot_tag, they are resolved into chnot ids first
+        let mut tag_ids = Vec::with_capacity(query.terms.len());
+        for term in query.terms.iter() {
+            let ids = match &term.filter {
+                QueryFilter::Tag(tag) => Some(self.chnot_tag_meta_ids(&namespaces, tag).await?),
+                _ => None,
+            };
+            if !term.exclude && ids.as_ref().is_some_and(|e| e.is_empty()) {
+                return Ok(ChnotQueryRsp {
+                    data: vec![],
+                    start_index: req.start_index,
+                    highlights: vec![],
+                    next_cursor: None,
+                });
+            }
+            tag_ids.push(ids);
+        }
+
         let client = self.client().await?;
 
         let chnot_sql = SqlSegBuilder::new()
             .raw("SELECT r.id as rid, r.content, r.omit_time, r.insert_time as version_time,")
-            .raw("m.id as mid, m.namespace, m.kind, m.pin_time, m.delete_time, m.update_time, m.insert_time as init_time")
+            .raw("m.id as mid, m.namespace, m.kind, m.pin_time, m.archive_time, m.delete_time, m.update_time, m.insert_time as init_time")
             .raw("FROM chnot_record r LEFT JOIN chnot_metadata m ON r.meta_id = m.id")
             .r#where(Wheres::and(
                 [
@@ -215,10 +475,21 @@ impl ChnotMapper for Postgres {
                             Wheres::is_null("omit_time")
                         }
                     }),
-                    Wheres::equal("namespace", req.namespace.clone()),
-                    Wheres::if_some(req.query.as_ref(), |content| {
-                        Wheres::ilike("content", content)
-                    }),
+                    match req.archived {
+                        ChnotArchiveFilter::Exclude => Wheres::is_null("archive_time"),
+                        ChnotArchiveFilter::Include => Wheres::none(),
+                        ChnotArchiveFilter::Only => Wheres::is_not_null("archive_time"),
+                    },
+                    Wheres::r#in("namespace", namespaces),
+                    time_range_to_wheres(&req.time_range),
+                    Wheres::and(
+                        query
+                            .terms
+                            .iter()
+                            .zip(tag_ids.iter())
+                            .map(|(term, ids)| query_term_to_wheres(term, ids.as_deref()))
+                            .collect::<Vec<_>>()
+                    ),
                     // TODO how to use as_ref?
                     Wheres::if_some(req.record_id.to_owned(), |id| {
                         Wheres::equal("r.id", id)
@@ -227,39 +498,27 @@ impl ChnotMapper for Postgres {
                     Wheres::if_some(req.meta_id.to_owned(), |id| {
                         Wheres::equal("r.meta_id", id)
                     }),
+                    match cursor.as_ref() {
+                        Some(cursor) => cursor_to_wheres(req.sort, cursor),
+                        None => Wheres::none(),
+                    },
                 ]
             ))
-            .raw("ORDER BY m.pin_time DESC, r.insert_time desc")
+            .raw(sort_to_order_by(req.sort))
             .custom(
-                LimitOffset::new(req.page_size).offset_if_some(Some(req.start_index)).to_box()
+                LimitOffset::new(req.page_size)
+                    .offset_if_some(cursor.is_none().then_some(req.start_index))
+                    .to_box()
             )
             .build(&mut PlaceHolderType::DollarNumber(0))
             .expect("error occured when build sql");
         info!("sql is {}", chnot_sql.seg);
 
-        let cs = client
+        let cs: Vec<Chnot> = client
             .query(&chnot_sql.seg, to_sql!(chnot_sql.values))
             .await?
             .iter()
-            .map(|row| {
-                let record = ChnotRecord {
-                    id: row.try_get("rid")?,
-                    meta_id: row.try_get("mid")?,
-                    content: row.try_get("content")?,
-                    omit_time: row.try_get("omit_time")?,
-                    insert_time: row.try_get("version_time")?,
-                };
-                let meta = ChnotMetadata {
-                    id: row.try_get("mid")?,
-                    namespace: row.try_get("namespace")?,
-                    kind: row.try_get("kind")?,
-                    pin_time: row.try_get("pin_time")?,
-                    delete_time: row.try_get("delete_time")?,
-                    update_time: row.try_get("update_time")?,
-                    insert_time: row.try_get("init_time")?,
-                };
-                Ok(Chnot { record, meta })
-            })
+            .map(row_to_chnot)
             .filter_map(|e: AResult<Chnot>| {
                 if e.is_err() {
                     error!("unable to remap: {:?}", e.err());
@@ -271,29 +530,215 @@ impl ChnotMapper for Postgres {
             .collect();
 
         Ok(ChnotQueryRsp {
+            next_cursor: ChnotCursor::next_of(&cs, req.sort, req.page_size),
             data: cs,
             start_index: req.start_index,
+            highlights: vec![],
         })
     }
 
-    async fn chnot_update(&self, req: KReq<ChnotUpdateReq>) -> AResult<ChnotUpdateRsp> {
+    async fn chnot_update(&self, req: KReq<ChnotUpdateReq>) -> AResult<ChnotMetadata> {
         let client = self.client().await?;
+        let now = Local::now().fixed_offset();
+
+        let pin_time = req.pinned.map(|e| e.then_some(now));
+        let archive_time = req.archive.map(|e| e.then_some(now));
+
+        // pin_time and archive_time are set to now or cleared, the other
+        // columns are only set when given.
+        let mut sets: Vec<String> = vec![];
+        let mut values: Vec<&(dyn ToSql + Sync)> = vec![&req.meta_id, &req.namespace];
+        if let Some(pin_time) = pin_time.as_ref() {
+            values.push(pin_time);
+            sets.push(format!("pin_time = ${}", values.len()));
+        }
+        if let Some(archive_time) = archive_time.as_ref() {
+            values.push(archive_time);
+            sets.push(format!("archive_time = ${}", values.len()));
+        }
+        if req.update_time {
+            values.push(&now);
+            sets.push(format!("update_time = ${}", values.len()));
+        }
+
+        if !sets.is_empty() {
+            let sql = format!(
+                "update chnot_metadata set {} where id = $1 and namespace = $2",
+                sets.join(", ")
+            );
+            client.execute(sql.as_str(), &values).await?;
+        }
 
-        let su = SqlUpdater::new("chnot_metadata")
-            .set_if_some("pinned", req.pinned)
-            .set_if_some(
-                "archive_time",
-                req.archive.map(|_| Local::now().fixed_offset()),
+        let row = client
+            .query_opt(
+                "select * from chnot_metadata where id = $1 and namespace = $2",
+                &[&req.meta_id, &req.namespace],
             )
-            .set_if_some("namespace", req.body.namespace.as_ref())
-            .r#where(Wheres::equal("id", &req.meta_id).into());
+            .await?
+            .ok_or_else(|| KError::NotFound(format!("unable to find chnot {}", req.meta_id)))?;
 
-        let ss = su.build(PlaceHolderType::dollar_number());
+        Self::to_chnot_meta(row)
+    }
 
-        if let Some(ss) = ss {
-            client.execute(ss.seg.as_str(), to_sql!(ss.values)).await?;
+    async fn chnot_move(
+        &self,
+        items: &[ChnotMoveItem],
+        target: &str,
+    ) -> AResult<Vec<ChnotMoveResult>> {
+        let mut client = self.client().await?;
+        let transaction = client.build_transaction().start().await?;
+        let now = Local::now().fixed_offset();
+
+        let mut results = Vec::with_capacity(items.len());
+        for item in items {
+            let moved = transaction
+                .execute(
+                    "update chnot_metadata set namespace = $1 where id = $2 and namespace = $3",
+                    &[&target, &item.meta_id, &item.namespace],
+                )
+                .await?;
+            if moved == 0 {
+                results.push(ChnotMoveResult {
+                    meta_id: item.meta_id.clone(),
+                    status: ChnotMoveStatus::NotFound,
+                    resources: vec![],
+                });
+                continue;
+            }
+
+            let resources = transaction
+                .query(
+                    "update resources set namespace = $1 where id = any($2) and namespace = $3 returning id",
+                    &[&target, &item.resource_ids, &item.namespace],
+                )
+                .await?
+                .iter()
+                .map(|row| row.try_get("id"))
+                .collect::<Result<Vec<String>, _>>()?;
+            transaction
+                .execute(
+                    "insert into chnot_move(id, meta_id, from_namespace, to_namespace, insert_time) values($1, $2, $3, $4, $5)",
+                    &[
+                        &uuid::Uuid::new_v4().to_string(),
+                        &item.meta_id,
+                        &item.namespace,
+                        &target,
+                        &now,
+                    ],
+                )
+                .await?;
+
+            results.push(ChnotMoveResult {
+                meta_id: item.meta_id.clone(),
+                status: ChnotMoveStatus::Moved,
+                resources,
+            });
         }
 
-        Ok(ChnotUpdateRsp {})
+        transaction.commit().await?;
+
+        Ok(results)
+    }
+
+    async fn chnot_history(&self, req: KReq<ChnotHistoryReq>) -> AResult<ChnotHistoryRsp> {
+        let client = self.client().await?;
+
+        let meta = client
+            .query_opt(
+                "select * from chnot_metadata where id = $1 and namespace = $2",
+                &[&req.meta_id, &req.namespace],
+            )
+            .await?
+            .ok_or_else(|| KError::NotFound(format!("unable to find chnot {}", req.meta_id)))?;
+        let meta = Self::to_chnot_meta(meta)?;
+
+        let records = client
+            .query(
+                "select * from chnot_record where meta_id = $1 order by insert_time asc",
+                &[&req.meta_id],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_chnot_record)
+            .collect::<AResult<Vec<ChnotRecord>>>()?;
+
+        let moves = client
+            .query(
+                "select * from chnot_move where meta_id = $1 order by insert_time asc",
+                &[&req.meta_id],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_chnot_move)
+            .collect::<AResult<Vec<ChnotMove>>>()?;
+
+        Ok(ChnotHistoryRsp {
+            meta,
+            records,
+            moves,
+        })
+    }
+
+    async fn chnot_record_by_id(&self, namespace: &str, record_id: &str) -> AResult<ChnotRecord> {
+        let row = self
+            .client()
+            .await?
+            .query_opt(
+                "select r.* from chnot_record r join chnot_metadata m on r.meta_id = m.id where r.id = $1 and m.namespace = $2",
+                &[&record_id, &namespace],
+            )
+            .await?
+            .ok_or_else(|| KError::NotFound(format!("unable to find chnot record {}", record_id)))?;
+
+        Self::to_chnot_record(row)
+    }
+
+    async fn chnot_namespaces(&self) -> AResult<Vec<String>> {
+        let rows = self
+            .client()
+            .await?
+            .query("select distinct namespace from chnot_metadata", &[])
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.try_get("namespace"))
+            .collect::<Result<Vec<String>, _>>()?)
+    }
+
+    async fn chnot_activity_days(
+        &self,
+        namespace: &str,
+        after: DateTime<FixedOffset>,
+        before: DateTime<FixedOffset>,
+        offset: FixedOffset,
+    ) -> AResult<Vec<DayActivity>> {
+        let offset_secs = offset.local_minus_utc() as f64;
+        let rows = self
+            .client()
+            .await?
+            .query(
+                "select day, sum(created)::bigint as created, sum(edited)::bigint as edited from (
+select (insert_time at time zone make_interval(secs => $4))::date as day, 1 as created, 0 as edited
+from chnot_metadata
+where namespace = $1 and delete_time is null and insert_time >= $2 and insert_time < $3
+union all
+select (r.insert_time at time zone make_interval(secs => $4))::date as day, 0 as created, 1 as edited
+from chnot_record r join chnot_metadata m on r.meta_id = m.id
+where m.namespace = $1 and m.delete_time is null and r.insert_time >= $2 and r.insert_time < $3
+) a group by day order by day",
+                &[&namespace, &after, &before, &offset_secs],
+            )
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(DayActivity {
+                    date: row.try_get("day")?,
+                    created: row.try_get::<_, i64>("created")? as u32,
+                    edited: row.try_get::<_, i64>("edited")? as u32,
+                })
+            })
+            .collect()
     }
 }
@@ -67,17 +67,4 @@ impl KVMapper for Postgres {
 
         Ok(KVDeleteRsp {})
     }
-
-    async fn ensure_table_kv(&self) -> chin_tools::wrapper::anyhow::EResult {
-        self.create_table(
-            "CREATE TABLE IF NOT EXISTS kv (
-                key VARCHAR(300) PRIMARY KEY,
-                value TEXT NOT NULL,
-                update_time TIMESTAMPTZ,
-                insert_time TIMESTAMPTZ NOT NULL,
-            )",
-        )
-        .await?;
-        Ok(())
-    }
 }
@@ -0,0 +1,65 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+
+use crate::{mapper::ChnotLinkMapper, model::db::chnot::ChnotLink};
+
+use super::Postgres;
+
+impl ChnotLinkMapper for Postgres {
+    async fn chnot_link_overwrite(&self, meta_id: &str, targets: &[String]) -> EResult {
+        let mut client = self.client().await?;
+        let transaction = client.build_transaction().start().await?;
+
+        transaction
+            .execute("delete from chnot_link where meta_id = $1", &[&meta_id])
+            .await?;
+        for target in targets {
+            transaction
+                .execute(
+                    "insert into chnot_link(meta_id, target) values($1, $2)",
+                    &[&meta_id, target],
+                )
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn chnot_link_list(
+        &self,
+        namespace: &str,
+        targets: Option<&[String]>,
+    ) -> AResult<Vec<ChnotLink>> {
+        let client = self.client().await?;
+        let rows = match targets {
+            Some(targets) => {
+                client
+                    .query(
+                        "select l.meta_id, l.target from chnot_link l join chnot_metadata m on l.meta_id = m.id
+where m.namespace = $1 and m.delete_time is null and l.target = any($2)",
+                        &[&namespace, &targets],
+                    )
+                    .await?
+            }
+            None => {
+                client
+                    .query(
+                        "select l.meta_id, l.target from chnot_link l join chnot_metadata m on l.meta_id = m.id
+where m.namespace = $1 and m.delete_time is null",
+                        &[&namespace],
+                    )
+                    .await?
+            }
+        };
+
+        rows.iter()
+            .map(|row| {
+                Ok(ChnotLink {
+                    meta_id: row.try_get("meta_id")?,
+                    target: row.try_get("target")?,
+                })
+            })
+            .collect()
+    }
+}
@@ -7,6 +7,7 @@ use chrono::Local;
 
 use super::sql::{Wheres, SqlSegBuilder, PlaceHolderType, SqlUpdater};
 use crate::{
+    error::KError,
     mapper::LLMChatMapper,
     model::{
         db::llmchat::{LLMChatBot, LLMChatRecord, LLMChatSession, LLMChatTemplate},
@@ -18,6 +19,27 @@ use crate::{
 use super::DeserializeMapper;
 use super::Postgres;
 
+fn session_not_found(session_id: &str) -> anyhow::Error {
+    KError::NotFound(format!("unable to find llm chat session {}", session_id)).into()
+}
+
+impl Postgres {
+    /// The session ids come from the body, only the namespace of the header
+    /// is checked against the acl.
+    async fn llm_chat_check_session(&self, namespace: &str, session_id: &str) -> EResult {
+        self.client()
+            .await?
+            .query_opt(
+                "select id from llm_chat_session where id = $1 and namespace = $2",
+                &[&session_id, &namespace],
+            )
+            .await?
+            .ok_or_else(|| session_not_found(session_id))?;
+
+        Ok(())
+    }
+}
+
 impl LLMChatMapper for Postgres {
     async fn llm_chat_overwrite_bot(
         &self,
@@ -67,7 +89,7 @@ impl LLMChatMapper for Postgres {
                 &req.session.bot_id,
                 &req.session.template_id,
                 &title,
-                &req.session.namespace,
+                &req.namespace,
                 &req.session.insert_time
             ]
         ).await?;
@@ -79,6 +101,8 @@ impl LLMChatMapper for Postgres {
         &self,
         req: KReq<LLMChatInsertRecordReq>,
     ) -> AResult<LLMChatInsertRecordRsp> {
+        self.llm_chat_check_session(&req.namespace, &req.record.session_id)
+            .await?;
         self.client().await?.execute(
             "insert into llm_chat_record(id, session_id, pre_record_id, content, role, role_id, insert_time) values($1, $2, $3, $4, $5, $6, $7)",
             &[
@@ -144,11 +168,14 @@ impl LLMChatMapper for Postgres {
         &self,
         req: KReq<LLMChatListSessionReq>,
     ) -> AResult<LLMChatListSessionRsp> {
+        let namespaces: Vec<String> = std::iter::once(req.namespace.clone())
+            .chain(req.descendants.iter().cloned())
+            .collect();
         let query = SqlSegBuilder::new()
             .raw("select * from llm_chat_session")
             .r#where(Wheres::and([
                 Wheres::is_null("delete_time"),
-                Wheres::equal("namespace", req.namespace),
+                Wheres::r#in("namespace", namespaces),
             ]))
             .raw("order by insert_time desc")
             .build(&mut PlaceHolderType::dollar_number())
@@ -172,13 +199,16 @@ impl LLMChatMapper for Postgres {
         &self,
         req: KReq<LLMChatSessionDetialReq>,
     ) -> AResult<LLMChatSessionDetailRsp> {
+        self.llm_chat_check_session(&req.namespace, &req.session_id)
+            .await?;
         let query = SqlSegBuilder::new()
-            .raw("select * from llm_chat_record")
+            .raw("select r.* from llm_chat_record r join llm_chat_session s on r.session_id = s.id")
             .r#where(Wheres::and([
-                Wheres::equal("session_id", req.session_id.clone()),
-                Wheres::is_null("omit_time"),
+                Wheres::equal("r.session_id", req.session_id.clone()),
+                Wheres::equal("s.namespace", req.namespace.clone()),
+                Wheres::is_null("r.omit_time"),
             ]))
-            .raw("order by insert_time desc")
+            .raw("order by r.insert_time desc")
             .build(&mut PlaceHolderType::dollar_number())
             .context("Unable to build args")?;
 
@@ -236,85 +266,25 @@ impl LLMChatMapper for Postgres {
     ) -> AResult<LLMChatDeleteSessionRsp> {
         let updater = SqlUpdater::new("llm_chat_session")
             .set("delete_time", Local::now().fixed_offset())
-            .r#where(Wheres::equal("id", &req.session_id))
+            .r#where(Wheres::and([
+                Wheres::equal("id", &req.session_id),
+                Wheres::equal("namespace", &req.namespace),
+            ]))
             .build(PlaceHolderType::dollar_number())
             .context("unable to build delete template")?;
 
-        self.client()
+        let count = self
+            .client()
             .await?
             .execute(&updater.seg, to_sql!(updater.values))
             .await?;
+        if count == 0 {
+            return Err(session_not_found(&req.session_id));
+        }
 
         Ok(LLMChatDeleteSessionRsp {})
     }
 
-    async fn ensure_table_llm_chat_bot(&self) -> EResult {
-        self.create_table(
-            "CREATE TABLE IF NOT EXISTS llm_chat_bot (
-                id VARCHAR(40) PRIMARY KEY,
-                name VARCHAR(40) NOT NULL,
-                body TEXT NOT NULL,
-                delete_time TIMESTAMPTZ,
-                update_time TIMESTAMPTZ,
-                insert_time TIMESTAMPTZ NOT NULL,
-                svg_logo TEXT NULL
-            )",
-        )
-        .await?;
-        Ok(())
-    }
-
-    async fn ensure_table_llm_chat_template(&self) -> EResult {
-        self.create_table(
-            "CREATE TABLE IF NOT EXISTS llm_chat_template (
-                id VARCHAR(40) PRIMARY KEY,
-                name VARCHAR(40) NOT NULL,
-                prompt TEXT NOT NULL,
-                icon_name VARCHAR(200),
-                delete_time TIMESTAMPTZ,
-                update_time TIMESTAMPTZ,
-                insert_time TIMESTAMPTZ NOT NULL,
-                svg_logo TEXT NULL
-            )",
-        )
-        .await?;
-        Ok(())
-    }
-
-    async fn ensure_table_llm_chat_session(&self) -> EResult {
-        self.create_table(
-            "CREATE TABLE IF NOT EXISTS llm_chat_session (
-                id VARCHAR(40) PRIMARY KEY,
-                bot_id VARCHAR(40) NOT NULL REFERENCES llm_chat_bot(id),
-                template_id VARCHAR(40) NOT NULL REFERENCES llm_chat_template(id),
-                title VARCHAR(300) NOT NULL,
-                namespace VARCHAR(40) NOT NULL,
-                delete_time TIMESTAMPTZ,
-                update_time TIMESTAMPTZ,
-                insert_time TIMESTAMPTZ NOT NULL
-            )",
-        )
-        .await?;
-        Ok(())
-    }
-
-    async fn ensure_table_llm_chat_record(&self) -> EResult {
-        self.create_table(
-            "CREATE TABLE IF NOT EXISTS llm_chat_record (
-                id VARCHAR(40) PRIMARY KEY,
-                session_id VARCHAR(40) NOT NULL REFERENCES llm_chat_session(id),
-                pre_record_id VARCHAR(40),
-                content TEXT NOT NULL,
-                omit_time TIMESTAMPTZ,
-                role VARCHAR(40) NOT NULL,
-                role_id VARCHAR(40),
-                insert_time TIMESTAMPTZ NOT NULL
-            )",
-        )
-        .await?;
-        Ok(())
-    }
-
     async fn llm_chat_update_session(
         &self,
         req: KReq<LLMChatUpdateSessionReq>,
@@ -328,14 +298,21 @@ impl LLMChatMapper for Postgres {
                     None
                 }
             })
-            .r#where(Wheres::and([Wheres::equal("id", req.session_id.as_str())]))
+            .r#where(Wheres::and([
+                Wheres::equal("id", req.session_id.as_str()),
+                Wheres::equal("namespace", req.namespace.as_str()),
+            ]))
             .build(PlaceHolderType::DollarNumber(0))
             .context("unable to build sql")?;
 
-        self.client()
+        let count = self
+            .client()
             .await?
             .execute(&updater.seg, to_sql!(updater.values))
             .await?;
+        if count == 0 {
+            return Err(session_not_found(&req.session_id));
+        }
 
         Ok(LLMChatUpdateSessionRsp {})
     }
@@ -0,0 +1,58 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+
+use crate::mapper::{migration::Migration, MigrationMapper};
+
+use super::Postgres;
+
+impl MigrationMapper for Postgres {
+    async fn ensure_table_schema_version(&self) -> EResult {
+        self.create_table(
+            "create table IF NOT EXISTS schema_version (
+    version BIGINT NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    insert_time timestamptz NOT NULL default CURRENT_TIMESTAMP,
+    primary key (version)
+)",
+        )
+        .await
+    }
+
+    async fn read_schema_version(&self) -> AResult<i64> {
+        let client = self.client().await?;
+
+        let existed: bool = client
+            .query_one(
+                "select to_regclass('schema_version') is not null as existed",
+                &[],
+            )
+            .await?
+            .try_get("existed")?;
+        if !existed {
+            return Ok(0);
+        }
+
+        let version: Option<i64> = client
+            .query_one("select max(version) as version from schema_version", &[])
+            .await?
+            .try_get("version")?;
+
+        Ok(version.unwrap_or(0))
+    }
+
+    async fn apply_migration(&self, migration: &'static Migration) -> EResult {
+        let mut client = self.client().await?;
+        let transaction = client.build_transaction().start().await?;
+
+        transaction.batch_execute(migration.postgres).await?;
+        transaction
+            .execute(
+                "insert into schema_version(version, name) values($1, $2)",
+                &[&migration.version, &migration.name],
+            )
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
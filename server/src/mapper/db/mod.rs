@@ -1,18 +1,38 @@
+pub mod acl;
+pub mod audit;
 pub mod backup;
 pub mod chnot;
 pub mod kv;
+pub mod link;
 pub mod llmchat;
+pub mod migration;
 pub mod namespace;
 pub mod resource;
+pub mod share;
+pub mod tag;
+pub mod trash;
+pub mod user;
 pub mod helper;
 
+use std::str::FromStr;
+
 use chin_tools::wrapper::anyhow::{AResult, EResult};
 use deadpool_postgres::{Client, Pool, PoolError};
 use serde::Deserialize;
 use tokio_postgres::Row;
 use chin_tools::sql;
 
-use crate::model::db::{chnot::*, kv::KV, llmchat::*, namespace::*, resource::Resource};
+use crate::model::db::{
+    acl::{AclSubjectKind, NamespaceAcl, NamespaceRole, UserGroup, UserGroupMember},
+    audit::{AuditAction, AuditEntityType, AuditLog},
+    chnot::*,
+    kv::KV,
+    llmchat::*,
+    namespace::*,
+    resource::Resource,
+    share::{ShareLink, ShareTargetKind},
+    user::{ApiToken, ApiTokenAccess, User},
+};
 
 use super::DeserializeMapper;
 
@@ -88,6 +108,7 @@ impl DeserializeMapper for Postgres {
             namespace: row.try_get("namespace")?,
             kind: row.try_get("kind")?,
             pin_time: row.try_get("pin_time")?,
+            archive_time: row.try_get("archive_time")?,
             delete_time: row.try_get("delete_time")?,
             update_time: row.try_get("update_time")?,
             insert_time: row.try_get("insert_time")?,
@@ -106,6 +127,17 @@ impl DeserializeMapper for Postgres {
         Ok(chnot)
     }
 
+    fn to_chnot_move(row: Self::RowType) -> AResult<ChnotMove> {
+        let obj = ChnotMove {
+            id: row.try_get("id")?,
+            meta_id: row.try_get("meta_id")?,
+            from_namespace: row.try_get("from_namespace")?,
+            to_namespace: row.try_get("to_namespace")?,
+            insert_time: row.try_get("insert_time")?,
+        };
+        Ok(obj)
+    }
+
     fn to_llmchat_bot(row: Self::RowType) -> AResult<LLMChatBot> {
         let obj = LLMChatBot {
             id: row.try_get("id")?,
@@ -190,6 +222,94 @@ impl DeserializeMapper for Postgres {
             namespace: row.try_get("namespace")?,
             ori_filename: row.try_get("ori_filename")?,
             content_type: row.try_get("content_type")?,
+            sha256: row.try_get("sha256")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_user(row: Self::RowType) -> AResult<User> {
+        let obj = User {
+            id: row.try_get("id")?,
+            username: row.try_get("username")?,
+            password_hash: row.try_get("password_hash")?,
+            admin: row.try_get("admin")?,
+            delete_time: row.try_get("delete_time")?,
+            update_time: row.try_get("update_time")?,
+            insert_time: row.try_get("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_api_token(row: Self::RowType) -> AResult<ApiToken> {
+        let obj = ApiToken {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            name: row.try_get("name")?,
+            token_hash: row.try_get("token_hash")?,
+            namespaces: serde_json::from_str(&row.try_get::<String>("namespaces")?)?,
+            access: ApiTokenAccess::from_str(&row.try_get::<String>("access")?)?,
+            last_used_time: row.try_get("last_used_time")?,
+            delete_time: row.try_get("delete_time")?,
+            insert_time: row.try_get("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_namespace_acl(row: Self::RowType) -> AResult<NamespaceAcl> {
+        let obj = NamespaceAcl {
+            id: row.try_get("id")?,
+            namespace: row.try_get("namespace")?,
+            subject_kind: AclSubjectKind::from_str(&row.try_get::<String>("subject_kind")?)?,
+            subject_id: row.try_get("subject_id")?,
+            role: NamespaceRole::from_str(&row.try_get::<String>("role")?)?,
+            insert_time: row.try_get("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_user_group(row: Self::RowType) -> AResult<UserGroup> {
+        let obj = UserGroup {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            insert_time: row.try_get("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_user_group_member(row: Self::RowType) -> AResult<UserGroupMember> {
+        let obj = UserGroupMember {
+            group_id: row.try_get("group_id")?,
+            user_id: row.try_get("user_id")?,
+            insert_time: row.try_get("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_share_link(row: Self::RowType) -> AResult<ShareLink> {
+        let obj = ShareLink {
+            id: row.try_get("id")?,
+            namespace: row.try_get("namespace")?,
+            target_kind: ShareTargetKind::from_str(&row.try_get::<String>("target_kind")?)?,
+            target_id: row.try_get("target_id")?,
+            user_id: row.try_get("user_id")?,
+            expire_time: row.try_get("expire_time")?,
+            delete_time: row.try_get("delete_time")?,
+            insert_time: row.try_get("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_audit_log(row: Self::RowType) -> AResult<AuditLog> {
+        let obj = AuditLog {
+            id: row.try_get("id")?,
+            actor_id: row.try_get("actor_id")?,
+            actor_name: row.try_get("actor_name")?,
+            namespace: row.try_get("namespace")?,
+            entity_type: AuditEntityType::from_str(&row.try_get::<String>("entity_type")?)?,
+            entity_id: row.try_get("entity_id")?,
+            action: AuditAction::from_str(&row.try_get::<String>("action")?)?,
+            payload: serde_json::from_str(&row.try_get::<String>("payload")?)?,
+            insert_time: row.try_get("insert_time")?,
         };
         Ok(obj)
     }
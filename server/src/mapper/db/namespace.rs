@@ -3,6 +3,7 @@ use chrono::Local;
 
 use super::DeserializeMapper;
 use crate::{
+    error::KError,
     mapper::NamespaceMapper,
     model::db::namespace::{NamespaceRecord, NamespaceRelation},
 };
@@ -14,7 +15,7 @@ impl NamespaceMapper for Postgres {
         let stmt = self.client().await?;
         let rows = stmt
             .query(
-                "select * from namespace_record where delete_time is not null",
+                "select * from namespace_record where delete_time is null",
                 &[],
             )
             .await?;
@@ -31,7 +32,7 @@ impl NamespaceMapper for Postgres {
         let stmt = self.client().await?;
         let rows = stmt
             .query(
-                "select * from namespace_relation where delete_time is not null",
+                "select * from namespace_relation where delete_time is null order by insert_time",
                 &[],
             )
             .await?;
@@ -44,50 +45,93 @@ impl NamespaceMapper for Postgres {
         Ok(nrs)
     }
 
-    async fn ensure_table_namespace_record(&self) -> EResult {
-        self.create_table(
-            "create table IF NOT EXISTS namespace_record (
-                id VARCHAR(40) PRIMARY KEY,
-                name VARCHAR(100) NOT NULL,
-                delete_time TIMESTAMPTZ,
-                update_time TIMESTAMPTZ,
-                insert_time TIMESTAMPTZ NOT NULL
-            )",
-        )
-        .await?;
-
-        let fast_create = |name: &str| NamespaceRecord {
-            id: name.to_owned(),
-            name: name.to_owned(),
-            delete_time: None,
-            update_time: None,
-            insert_time: Local::now().into(),
-        };
-
-        for v in [
-            fast_create("private"),
-            fast_create("public"),
-            fast_create("work"),
-        ] {
-            self.client().await?.execute("insert into namespace_record(id, name, insert_time) values($1,$2,$3) on conflict do nothing", &[
-                &v.id, &v.name, &v.insert_time
-            ]).await?;
+    async fn namespace_insert(&self, record: &NamespaceRecord) -> AResult<bool> {
+        let count = self
+            .client()
+            .await?
+            .execute(
+                "insert into namespace_record(id, name, insert_time) values($1, $2, $3) on conflict do nothing",
+                &[&record.id, &record.name, &record.insert_time],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn namespace_rename(&self, id: &str, name: &str) -> AResult<NamespaceRecord> {
+        let row = self
+            .client()
+            .await?
+            .query_opt(
+                "update namespace_record set name = $2, update_time = $3
+where id = $1 and delete_time is null returning *",
+                &[&id, &name, &Local::now().fixed_offset()],
+            )
+            .await?
+            .ok_or_else(|| KError::NotFound(format!("unable to find namespace {}", id)))?;
+
+        Self::to_namespace_record(row)
+    }
+
+    async fn namespace_delete(&self, id: &str) -> EResult {
+        let now = Local::now().fixed_offset();
+        let mut client = self.client().await?;
+        let transaction = client.build_transaction().start().await?;
+
+        transaction
+            .execute(
+                "update namespace_relation set delete_time = $2
+where (sub_id = $1 or parent_id = $1) and delete_time is null",
+                &[&id, &now],
+            )
+            .await?;
+        transaction
+            .execute(
+                "update namespace_record set delete_time = $2 where id = $1 and delete_time is null",
+                &[&id, &now],
+            )
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn namespace_set_parent(&self, id: &str, parent_id: Option<&str>) -> EResult {
+        let now = Local::now().fixed_offset();
+        let mut client = self.client().await?;
+        let transaction = client.build_transaction().start().await?;
+
+        transaction
+            .execute(
+                "update namespace_relation set delete_time = $2 where sub_id = $1 and delete_time is null",
+                &[&id, &now],
+            )
+            .await?;
+        if let Some(parent_id) = parent_id {
+            transaction
+                .execute(
+                    "insert into namespace_relation(id, sub_id, parent_id, insert_time) values($1, $2, $3, $4)",
+                    &[&uuid::Uuid::new_v4().to_string(), &id, &parent_id, &now],
+                )
+                .await?;
         }
 
+        transaction.commit().await?;
+
         Ok(())
     }
 
-    async fn ensure_table_namespace_relation(&self) -> EResult {
-        self.create_table(
-            "create table IF NOT EXISTS namespace_relation (
-            id VARCHAR(40) PRIMARY KEY,
-            sub_id varchar(40),
-            parent_id varchar(40),
-            delete_time TIMESTAMPTZ,
-            update_time TIMESTAMPTZ,
-            insert_time TIMESTAMPTZ NOT NULL
-        )",
-        )
-        .await
+    async fn namespace_chnot_count(&self, id: &str) -> AResult<i64> {
+        let row = self
+            .client()
+            .await?
+            .query_one(
+                "select count(*) as count from chnot_metadata where namespace = $1",
+                &[&id],
+            )
+            .await?;
+
+        Ok(row.try_get("count")?)
     }
 }
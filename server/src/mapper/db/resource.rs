@@ -1,60 +1,31 @@
 use anyhow::Context;
-use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chin_tools::wrapper::anyhow::AResult;
 
 use super::DeserializeMapper;
 use crate::{
+    error::KError,
     mapper::ResourceMapper,
     model::{
         db::resource::{InlineResource, Resource},
-        dto::{InsertInlineResourceRsp, KReq, QueryInlineResourceRsp},
+        dto::{
+            InsertInlineResourceRsp, KReq, QueryInlineResourceRsp, ResourceListReq,
+            ResourceListRsp,
+        },
     },
     to_sql
 };
 
-
 use super::Postgres;
 use super::sql::{Wheres, SqlSegBuilder, PlaceHolderType};
 
 impl ResourceMapper for Postgres {
-    async fn ensure_table_resource(&self) -> EResult {
-        self.create_table(
-            "create table IF NOT EXISTS resources (
-    id VARCHAR(40) PRIMARY KEY,
-
-    namespace VARCHAR(100) NOT NULL,
-    ori_filename VARCHAR(300) NOT NULL,
-
-    content_type VARCHAR(100) NOT NULL,
-
-    delete_time TIMESTAMPTZ,
-    insert_time TIMESTAMPTZ NOT NULL
-)",
-        )
-        .await
-    }
-
-    async fn ensure_table_inline_resource(&self) -> EResult {
-        self.create_table(
-            "create table IF NOT EXISTS inline_resource (
-    id VARCHAR(40) PRIMARY KEY,
-
-    name VARCHAR(300) NOT NULL,
-    content_type VARCHAR(100) NOT NULL,
-    content TEXT NOT NULL,
-    
-    delete_time TIMESTAMPTZ,
-    insert_time TIMESTAMPTZ NOT NULL
-)",
-        )
-        .await
-    }
-
     async fn insert_resource(&self, res: &Resource) -> AResult<Resource> {
         let Resource {
             ori_filename,
             id,
             content_type,
             namespace,
+            sha256,
             delete_time: _,
             insert_time: _,
         } = res;
@@ -64,8 +35,8 @@ impl ResourceMapper for Postgres {
         let insert_time = chrono::Utc::now().to_owned();
 
         stmt.execute(
-            "insert into resources(id, namespace, ori_filename, content_type, insert_time) values ($1,$2,$3,$4, $5)",
-            &[&id, &namespace, &ori_filename, &content_type, &insert_time]
+            "insert into resources(id, namespace, ori_filename, content_type, sha256, insert_time) values ($1,$2,$3,$4,$5,$6)",
+            &[&id, &namespace, &ori_filename, &content_type, &sha256, &insert_time]
         ).await
             .map_err(|e| anyhow::Error::new(e))
             .map(|_| Resource {
@@ -73,6 +44,7 @@ impl ResourceMapper for Postgres {
                 namespace: namespace.to_owned(),
                 ori_filename: ori_filename.to_string(),
                 content_type: content_type.to_owned(),
+                sha256: sha256.to_owned(),
                 insert_time,
                 delete_time: None,
             })
@@ -81,12 +53,68 @@ impl ResourceMapper for Postgres {
     async fn query_resource_by_id(&self, id: &str) -> AResult<Resource> {
         let stmt = self.pool.get().await?;
         let row = stmt
-            .query_one("select * from resources where id = $1", &[&id])
-            .await?;
+            .query_opt("select * from resources where id = $1", &[&id])
+            .await?
+            .ok_or_else(|| KError::NotFound(format!("unable to find resource {}", id)))?;
+
+        Self::to_resource(row)
+    }
+
+    async fn query_resources(&self, req: KReq<ResourceListReq>) -> AResult<ResourceListRsp> {
+        let namespaces: Vec<String> = std::iter::once(req.namespace.clone())
+            .chain(req.descendants.iter().cloned())
+            .collect();
+        let query = SqlSegBuilder::new()
+            .raw("select * from resources")
+            .r#where(Wheres::and([
+                Wheres::is_null("delete_time"),
+                Wheres::r#in("namespace", namespaces),
+            ]))
+            .raw("order by insert_time desc")
+            .build(&mut PlaceHolderType::dollar_number())
+            .context("Unable to build args")?;
+
+        let resources: AResult<Vec<Resource>> = self
+            .client()
+            .await?
+            .query(query.seg.as_str(), to_sql!(query.values))
+            .await?
+            .into_iter()
+            .map(Self::to_resource)
+            .collect();
+
+        Ok(ResourceListRsp {
+            resources: resources?,
+        })
+    }
+
+    async fn delete_resource(&self, namespace: &str, id: &str) -> AResult<Resource> {
+        let row = self
+            .client()
+            .await?
+            .query_opt(
+                "delete from resources where id = $1 and namespace = $2 returning *",
+                &[&id, &namespace],
+            )
+            .await?
+            .ok_or_else(|| KError::NotFound(format!("unable to find resource {}", id)))?;
 
         Self::to_resource(row)
     }
 
+    async fn count_resources_by_sha256(&self, sha256: &str) -> AResult<i64> {
+        let row = self
+            .client()
+            .await?
+            .query_one(
+                "select count(*) as count from resources where sha256 = $1",
+                &[&sha256],
+            )
+            .await?;
+
+        Ok(row.try_get("count")?)
+    }
+
     async fn insert_inline_resource(
         &self,
         req: &KReq<crate::model::dto::InsertInlineResourceReq>,
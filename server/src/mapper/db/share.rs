@@ -0,0 +1,69 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{DateTime, FixedOffset};
+
+use super::DeserializeMapper;
+use crate::{mapper::ShareLinkMapper, model::db::share::ShareLink};
+
+use super::Postgres;
+
+impl ShareLinkMapper for Postgres {
+    async fn share_link_insert(&self, link: &ShareLink) -> EResult {
+        self.client()
+            .await?
+            .execute(
+                "insert into share_link(id, namespace, target_kind, target_id, user_id, expire_time, insert_time) values($1, $2, $3, $4, $5, $6, $7)",
+                &[
+                    &link.id,
+                    &link.namespace,
+                    &link.target_kind.to_string(),
+                    &link.target_id,
+                    &link.user_id,
+                    &link.expire_time,
+                    &link.insert_time,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn share_link_list(&self, namespace: &str) -> AResult<Vec<ShareLink>> {
+        self.client()
+            .await?
+            .query(
+                "select * from share_link where namespace = $1 and delete_time is null order by insert_time desc",
+                &[&namespace],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_share_link)
+            .collect()
+    }
+
+    async fn share_link_by_id(&self, id: &str) -> AResult<Option<ShareLink>> {
+        self.client()
+            .await?
+            .query_opt("select * from share_link where id = $1", &[&id])
+            .await?
+            .map(Self::to_share_link)
+            .transpose()
+    }
+
+    async fn share_link_revoke(
+        &self,
+        namespace: &str,
+        id: &str,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<bool> {
+        let count = self
+            .client()
+            .await?
+            .execute(
+                "update share_link set delete_time = $3 where id = $1 and namespace = $2 and delete_time is null",
+                &[&id, &namespace, &time],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+}
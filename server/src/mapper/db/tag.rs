@@ -0,0 +1,87 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+
+use crate::{
+    mapper::ChnotTagMapper,
+    model::dto::{
+        chnot::{ChnotTagCount, ChnotTagListReq, ChnotTagListRsp},
+        KReq,
+    },
+};
+
+use super::Postgres;
+
+impl Postgres {
+    /// Ids of the chnots in the namespaces tagged with `tag` or one of its
+    /// nested tags.
+    pub(super) async fn chnot_tag_meta_ids(
+        &self,
+        namespaces: &[String],
+        tag: &str,
+    ) -> AResult<Vec<String>> {
+        let rows = self
+            .client()
+            .await?
+            .query(
+                "select distinct t.meta_id from chnot_tag t join chnot_metadata m on t.meta_id = m.id
+where m.namespace = ANY($1) and (t.tag = $2 or t.tag like $3)",
+                &[
+                    &namespaces,
+                    &tag,
+                    &format!("{}/%", tag.replace('_', "\\_")),
+                ],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.try_get("meta_id"))
+            .collect::<Result<Vec<String>, _>>()?)
+    }
+}
+
+impl ChnotTagMapper for Postgres {
+    async fn chnot_tag_overwrite(&self, meta_id: &str, tags: &[String]) -> EResult {
+        let mut client = self.client().await?;
+        let transaction = client.build_transaction().start().await?;
+
+        transaction
+            .execute("delete from chnot_tag where meta_id = $1", &[&meta_id])
+            .await?;
+        for tag in tags {
+            transaction
+                .execute(
+                    "insert into chnot_tag(meta_id, tag) values($1, $2)",
+                    &[&meta_id, tag],
+                )
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn chnot_tag_list(&self, req: KReq<ChnotTagListReq>) -> AResult<ChnotTagListRsp> {
+        let rows = self
+            .client()
+            .await?
+            .query(
+                "select t.tag, count(*) as count from chnot_tag t join chnot_metadata m on t.meta_id = m.id
+where m.namespace = $1 and m.delete_time is null group by t.tag order by count desc, t.tag",
+                &[&req.namespace],
+            )
+            .await?;
+
+        let tags = rows
+            .iter()
+            .map(|row| {
+                Ok(ChnotTagCount {
+                    tag: row.try_get("tag")?,
+                    count: row.try_get("count")?,
+                })
+            })
+            .collect::<AResult<Vec<ChnotTagCount>>>()?;
+
+        Ok(ChnotTagListRsp { tags })
+    }
+}
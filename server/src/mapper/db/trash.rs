@@ -0,0 +1,110 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    error::KError,
+    mapper::{ChnotTrashMapper, DeserializeMapper},
+    model::{
+        db::chnot::ChnotMetadata,
+        dto::{
+            chnot::{Chnot, ChnotQueryRsp, ChnotTrashListReq, ChnotTrashRestoreReq},
+            KReq,
+        },
+    },
+};
+
+use super::{chnot::row_to_chnot, Postgres};
+
+impl ChnotTrashMapper for Postgres {
+    async fn chnot_trash_list(
+        &self,
+        req: KReq<ChnotTrashListReq>,
+    ) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
+        let rows = self
+            .client()
+            .await?
+            .query(
+                "SELECT r.id as rid, r.content, r.omit_time, r.insert_time as version_time,
+    m.id as mid, m.namespace, m.kind, m.pin_time, m.archive_time, m.delete_time, m.update_time, m.insert_time as init_time
+FROM chnot_record r JOIN chnot_metadata m ON r.meta_id = m.id
+WHERE m.namespace = $1 AND m.delete_time is not null AND r.omit_time is null
+ORDER BY m.delete_time DESC, m.id
+LIMIT $2 OFFSET $3",
+                &[
+                    &req.namespace,
+                    &(req.page_size as i64),
+                    &(req.start_index as i64),
+                ],
+            )
+            .await?;
+
+        Ok(ChnotQueryRsp {
+            data: rows.iter().map(row_to_chnot).collect::<AResult<_>>()?,
+            start_index: req.start_index,
+            highlights: vec![],
+            next_cursor: None,
+        })
+    }
+
+    async fn chnot_trash_restore(&self, req: KReq<ChnotTrashRestoreReq>) -> AResult<ChnotMetadata> {
+        let row = self
+            .client()
+            .await?
+            .query_opt(
+                "update chnot_metadata set delete_time = null
+where id = $1 and namespace = $2 and delete_time is not null returning *",
+                &[&req.chnot_id, &req.namespace],
+            )
+            .await?
+            .ok_or_else(|| {
+                KError::NotFound(format!("unable to find chnot {} in trash", req.chnot_id))
+            })?;
+
+        Self::to_chnot_meta(row)
+    }
+
+    async fn chnot_trash_expired(
+        &self,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<Vec<ChnotMetadata>> {
+        self.client()
+            .await?
+            .query(
+                "select * from chnot_metadata where delete_time < $1",
+                &[&time],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_chnot_meta)
+            .collect()
+    }
+
+    async fn chnot_purge(&self, meta_ids: &[String]) -> EResult {
+        if meta_ids.is_empty() {
+            return Ok(());
+        }
+
+        let meta_ids = meta_ids.to_vec();
+        let mut client = self.client().await?;
+        let transaction = client.build_transaction().start().await?;
+
+        for table in ["chnot_tag", "chnot_link", "chnot_move", "chnot_record"] {
+            transaction
+                .execute(
+                    &format!("delete from {} where meta_id = any($1)", table),
+                    &[&meta_ids],
+                )
+                .await?;
+        }
+        transaction
+            .execute(
+                "delete from chnot_metadata where id = any($1)",
+                &[&meta_ids],
+            )
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
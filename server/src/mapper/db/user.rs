@@ -0,0 +1,197 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{DateTime, FixedOffset};
+
+use super::DeserializeMapper;
+use crate::{
+    mapper::{ApiTokenMapper, UserMapper},
+    model::db::user::{ApiToken, User, UserSession},
+};
+
+use super::Postgres;
+
+impl UserMapper for Postgres {
+    async fn user_insert(&self, user: &User) -> AResult<bool> {
+        let count = self
+            .client()
+            .await?
+            .execute(
+                "insert into user_account(id, username, password_hash, admin, insert_time) values($1, $2, $3, $4, $5) on conflict do nothing",
+                &[
+                    &user.id,
+                    &user.username,
+                    &user.password_hash,
+                    &user.admin,
+                    &user.insert_time,
+                ],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn user_by_name(&self, username: &str) -> AResult<Option<User>> {
+        self.client()
+            .await?
+            .query_opt(
+                "select * from user_account where username = $1 and delete_time is null",
+                &[&username],
+            )
+            .await?
+            .map(Self::to_user)
+            .transpose()
+    }
+
+    async fn user_by_id(&self, id: &str) -> AResult<Option<User>> {
+        self.client()
+            .await?
+            .query_opt(
+                "select * from user_account where id = $1 and delete_time is null",
+                &[&id],
+            )
+            .await?
+            .map(Self::to_user)
+            .transpose()
+    }
+
+    async fn user_list(&self) -> AResult<Vec<User>> {
+        self.client()
+            .await?
+            .query(
+                "select * from user_account where delete_time is null order by username",
+                &[],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_user)
+            .collect()
+    }
+
+    async fn user_session_insert(&self, session: &UserSession) -> EResult {
+        self.client()
+            .await?
+            .execute(
+                "insert into user_session(id, user_id, expire_time, insert_time) values($1, $2, $3, $4)",
+                &[
+                    &session.id,
+                    &session.user_id,
+                    &session.expire_time,
+                    &session.insert_time,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn user_by_session(
+        &self,
+        session_id: &str,
+        now: DateTime<FixedOffset>,
+    ) -> AResult<Option<User>> {
+        self.client()
+            .await?
+            .query_opt(
+                "select u.* from user_account u join user_session s on s.user_id = u.id
+where s.id = $1 and s.expire_time > $2 and u.delete_time is null",
+                &[&session_id, &now],
+            )
+            .await?
+            .map(Self::to_user)
+            .transpose()
+    }
+
+    async fn user_session_delete(&self, session_id: &str) -> EResult {
+        self.client()
+            .await?
+            .execute("delete from user_session where id = $1", &[&session_id])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn user_session_purge(&self, now: DateTime<FixedOffset>) -> EResult {
+        self.client()
+            .await?
+            .execute("delete from user_session where expire_time <= $1", &[&now])
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl ApiTokenMapper for Postgres {
+    async fn api_token_insert(&self, token: &ApiToken) -> EResult {
+        self.client()
+            .await?
+            .execute(
+                "insert into api_token(id, user_id, name, token_hash, namespaces, access, insert_time) values($1, $2, $3, $4, $5, $6, $7)",
+                &[
+                    &token.id,
+                    &token.user_id,
+                    &token.name,
+                    &token.token_hash,
+                    &serde_json::to_string(&token.namespaces)?,
+                    &token.access.to_string(),
+                    &token.insert_time,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn api_token_list(&self, user_id: &str) -> AResult<Vec<ApiToken>> {
+        self.client()
+            .await?
+            .query(
+                "select * from api_token where user_id = $1 and delete_time is null order by insert_time desc",
+                &[&user_id],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_api_token)
+            .collect()
+    }
+
+    async fn api_token_by_hash(&self, token_hash: &str) -> AResult<Option<ApiToken>> {
+        self.client()
+            .await?
+            .query_opt(
+                "select * from api_token where token_hash = $1 and delete_time is null",
+                &[&token_hash],
+            )
+            .await?
+            .map(Self::to_api_token)
+            .transpose()
+    }
+
+    async fn api_token_touch(&self, id: &str, time: DateTime<FixedOffset>) -> EResult {
+        self.client()
+            .await?
+            .execute(
+                "update api_token set last_used_time = $2 where id = $1",
+                &[&id, &time],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn api_token_revoke(
+        &self,
+        user_id: &str,
+        id: &str,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<bool> {
+        let count = self
+            .client()
+            .await?
+            .execute(
+                "update api_token set delete_time = $3 where id = $1 and user_id = $2 and delete_time is null",
+                &[&id, &user_id, &time],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+}
@@ -1,16 +1,37 @@
 use chin_tools::{utils::sort_util, wrapper::anyhow::{AResult, EResult}};
+use chrono::{DateTime, FixedOffset};
+use tracing::info;
 
 use crate::model::{db::namespace::NamespaceRelation, dto::InsertInlineResourceRsp};
+use crate::util::activity::DayActivity;
 
 use super::{
-    dump::TableRowCallbackEnum, db::Postgres, DumpMapper, ChnotDeletionRsp, ChnotMapper,
-    ChnotOverwriteReq, ChnotOverwriteRsp, KVMapper, LLMChatMapper, MapperConfig, MapperType,
-    NamespaceMapper, ResourceMapper,
+    dump::TableRowCallbackEnum,
+    db::Postgres,
+    migration::{pending_migrations, Migration},
+    sqlite::Sqlite,
+    versioning::VersionPolicy,
+    DumpMapper, MigrationMapper, ChnotDeletionRsp, ChnotLinkMapper, ChnotMapper, ChnotTagMapper,
+    ChnotTrashMapper, ChnotOverwriteReq, ChnotOverwriteRsp, KVMapper, LLMChatMapper, MapperConfig,
+    MapperType, NamespaceMapper, ResourceMapper, UserMapper, ApiTokenMapper, AclMapper,
+    ShareLinkMapper, AuditLogMapper,
 };
 
 use crate::model::{
-    db::{namespace::NamespaceRecord, resource::Resource},
-    dto::{chnot::*, KReq},
+    db::{
+        acl::{AclSubjectKind, NamespaceAcl, UserGroup, UserGroupMember},
+        audit::AuditLog,
+        chnot::{ChnotLink, ChnotMetadata, ChnotRecord},
+        namespace::NamespaceRecord,
+        resource::Resource,
+        share::ShareLink,
+        user::{ApiToken, User, UserSession},
+    },
+    dto::{
+        audit::{AuditLogQueryReq, AuditLogQueryRsp},
+        chnot::*,
+        KReq, ResourceListReq, ResourceListRsp,
+    },
 };
 
 impl Into<AResult<MapperType>> for MapperConfig {
@@ -20,62 +41,228 @@ impl Into<AResult<MapperType>> for MapperConfig {
                 let pg = Postgres::new(config)?;
                 Ok(MapperType::Postgres(pg))
             }
+            MapperConfig::Sqlite(config) => {
+                let sqlite = Sqlite::new(config)?;
+                Ok(MapperType::Sqlite(sqlite))
+            }
         }
     }
 }
 
 impl MapperType {
+    /// Apply all pending migrations in order.
     pub async fn ensure_tables(&self) -> EResult {
-        self.ensure_table_chnot_record().await?;
-        self.ensure_table_namespace_record().await?;
-        self.ensure_table_namespace_relation().await?;
-        self.ensure_table_chnot_metadata().await?;
-        self.ensure_table_resource().await?;
-        self.ensure_table_inline_resource().await?;
-
-        self.ensure_table_llm_chat_bot().await?;
-        self.ensure_table_llm_chat_template().await?;
-        self.ensure_table_llm_chat_session().await?;
-        self.ensure_table_llm_chat_record().await?;
+        self.ensure_table_schema_version().await?;
+
+        let current = self.read_schema_version().await?;
+        for migration in pending_migrations(current) {
+            info!(
+                "applying migration {:04}_{}",
+                migration.version, migration.name
+            );
+            self.apply_migration(migration).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the sql of the pending migrations without touching the database.
+    pub async fn print_pending_migrations(&self) -> EResult {
+        let current = self.read_schema_version().await?;
+        info!("current schema version: {}", current);
+
+        for migration in pending_migrations(current) {
+            println!("-- {:04}_{}", migration.version, migration.name);
+            println!("{}", self.migration_sql(migration));
+        }
 
         Ok(())
     }
+
+    fn migration_sql(&self, migration: &Migration) -> &'static str {
+        match self {
+            MapperType::Postgres(_) => migration.postgres,
+            MapperType::Sqlite(_) => migration.sqlite,
+        }
+    }
+}
+
+impl MigrationMapper for MapperType {
+    async fn ensure_table_schema_version(&self) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.ensure_table_schema_version().await,
+            MapperType::Sqlite(db) => db.ensure_table_schema_version().await,
+        }
+    }
+
+    async fn read_schema_version(&self) -> AResult<i64> {
+        match self {
+            MapperType::Postgres(db) => db.read_schema_version().await,
+            MapperType::Sqlite(db) => db.read_schema_version().await,
+        }
+    }
+
+    async fn apply_migration(&self, migration: &'static Migration) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.apply_migration(migration).await,
+            MapperType::Sqlite(db) => db.apply_migration(migration).await,
+        }
+    }
 }
 
 impl ChnotMapper for MapperType {
-    async fn chnot_overwrite(&self, req: KReq<ChnotOverwriteReq>) -> AResult<ChnotOverwriteRsp> {
+    async fn chnot_overwrite(
+        &self,
+        req: KReq<ChnotOverwriteReq>,
+        policy: &VersionPolicy,
+    ) -> AResult<ChnotOverwriteRsp> {
         match self {
-            MapperType::Postgres(db) => db.chnot_overwrite(req).await,
+            MapperType::Postgres(db) => db.chnot_overwrite(req, policy).await,
+            MapperType::Sqlite(db) => db.chnot_overwrite(req, policy).await,
         }
     }
 
     async fn chnot_delete(&self, req: KReq<ChnotDeletionReq>) -> AResult<ChnotDeletionRsp> {
         match self {
             MapperType::Postgres(db) => db.chnot_delete(req).await,
+            MapperType::Sqlite(db) => db.chnot_delete(req).await,
         }
     }
 
     async fn chnot_query(&self, req: KReq<ChnotQueryReq>) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
         match self {
             MapperType::Postgres(db) => db.chnot_query(req).await,
+            MapperType::Sqlite(db) => db.chnot_query(req).await,
         }
     }
 
-    async fn chnot_update(&self, req: KReq<ChnotUpdateReq>) -> AResult<ChnotUpdateRsp> {
+    async fn chnot_update(&self, req: KReq<ChnotUpdateReq>) -> AResult<ChnotMetadata> {
         match self {
             MapperType::Postgres(db) => db.chnot_update(req).await,
+            MapperType::Sqlite(db) => db.chnot_update(req).await,
+        }
+    }
+
+    async fn chnot_move(
+        &self,
+        items: &[ChnotMoveItem],
+        target: &str,
+    ) -> AResult<Vec<ChnotMoveResult>> {
+        match self {
+            MapperType::Postgres(db) => db.chnot_move(items, target).await,
+            MapperType::Sqlite(db) => db.chnot_move(items, target).await,
+        }
+    }
+
+    async fn chnot_history(&self, req: KReq<ChnotHistoryReq>) -> AResult<ChnotHistoryRsp> {
+        match self {
+            MapperType::Postgres(db) => db.chnot_history(req).await,
+            MapperType::Sqlite(db) => db.chnot_history(req).await,
         }
     }
 
-    async fn ensure_table_chnot_record(&self) -> EResult {
+    async fn chnot_record_by_id(&self, namespace: &str, record_id: &str) -> AResult<ChnotRecord> {
         match self {
-            MapperType::Postgres(db) => db.ensure_table_chnot_record().await,
+            MapperType::Postgres(db) => db.chnot_record_by_id(namespace, record_id).await,
+            MapperType::Sqlite(db) => db.chnot_record_by_id(namespace, record_id).await,
         }
     }
 
-    async fn ensure_table_chnot_metadata(&self) -> EResult {
+    async fn chnot_namespaces(&self) -> AResult<Vec<String>> {
         match self {
-            MapperType::Postgres(db) => db.ensure_table_chnot_metadata().await,
+            MapperType::Postgres(db) => db.chnot_namespaces().await,
+            MapperType::Sqlite(db) => db.chnot_namespaces().await,
+        }
+    }
+
+    async fn chnot_activity_days(
+        &self,
+        namespace: &str,
+        after: DateTime<FixedOffset>,
+        before: DateTime<FixedOffset>,
+        offset: FixedOffset,
+    ) -> AResult<Vec<DayActivity>> {
+        match self {
+            MapperType::Postgres(db) => {
+                db.chnot_activity_days(namespace, after, before, offset)
+                    .await
+            }
+            MapperType::Sqlite(db) => {
+                db.chnot_activity_days(namespace, after, before, offset)
+                    .await
+            }
+        }
+    }
+}
+
+impl ChnotTagMapper for MapperType {
+    async fn chnot_tag_overwrite(&self, meta_id: &str, tags: &[String]) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.chnot_tag_overwrite(meta_id, tags).await,
+            MapperType::Sqlite(db) => db.chnot_tag_overwrite(meta_id, tags).await,
+        }
+    }
+
+    async fn chnot_tag_list(&self, req: KReq<ChnotTagListReq>) -> AResult<ChnotTagListRsp> {
+        match self {
+            MapperType::Postgres(db) => db.chnot_tag_list(req).await,
+            MapperType::Sqlite(db) => db.chnot_tag_list(req).await,
+        }
+    }
+}
+
+impl ChnotLinkMapper for MapperType {
+    async fn chnot_link_overwrite(&self, meta_id: &str, targets: &[String]) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.chnot_link_overwrite(meta_id, targets).await,
+            MapperType::Sqlite(db) => db.chnot_link_overwrite(meta_id, targets).await,
+        }
+    }
+
+    async fn chnot_link_list(
+        &self,
+        namespace: &str,
+        targets: Option<&[String]>,
+    ) -> AResult<Vec<ChnotLink>> {
+        match self {
+            MapperType::Postgres(db) => db.chnot_link_list(namespace, targets).await,
+            MapperType::Sqlite(db) => db.chnot_link_list(namespace, targets).await,
+        }
+    }
+}
+
+impl ChnotTrashMapper for MapperType {
+    async fn chnot_trash_list(
+        &self,
+        req: KReq<ChnotTrashListReq>,
+    ) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
+        match self {
+            MapperType::Postgres(db) => db.chnot_trash_list(req).await,
+            MapperType::Sqlite(db) => db.chnot_trash_list(req).await,
+        }
+    }
+
+    async fn chnot_trash_restore(&self, req: KReq<ChnotTrashRestoreReq>) -> AResult<ChnotMetadata> {
+        match self {
+            MapperType::Postgres(db) => db.chnot_trash_restore(req).await,
+            MapperType::Sqlite(db) => db.chnot_trash_restore(req).await,
+        }
+    }
+
+    async fn chnot_trash_expired(
+        &self,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<Vec<ChnotMetadata>> {
+        match self {
+            MapperType::Postgres(db) => db.chnot_trash_expired(time).await,
+            MapperType::Sqlite(db) => db.chnot_trash_expired(time).await,
+        }
+    }
+
+    async fn chnot_purge(&self, meta_ids: &[String]) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.chnot_purge(meta_ids).await,
+            MapperType::Sqlite(db) => db.chnot_purge(meta_ids).await,
         }
     }
 }
@@ -84,18 +271,35 @@ impl ResourceMapper for MapperType {
     async fn insert_resource(&self, resource: &Resource) -> anyhow::Result<Resource> {
         match self {
             MapperType::Postgres(db) => db.insert_resource(resource).await,
+            MapperType::Sqlite(db) => db.insert_resource(resource).await,
         }
     }
 
     async fn query_resource_by_id(&self, id: &str) -> anyhow::Result<Resource> {
         match self {
             MapperType::Postgres(db) => db.query_resource_by_id(id).await,
+            MapperType::Sqlite(db) => db.query_resource_by_id(id).await,
+        }
+    }
+
+    async fn query_resources(&self, req: KReq<ResourceListReq>) -> AResult<ResourceListRsp> {
+        match self {
+            MapperType::Postgres(db) => db.query_resources(req).await,
+            MapperType::Sqlite(db) => db.query_resources(req).await,
         }
     }
 
-    async fn ensure_table_resource(&self) -> EResult {
+    async fn delete_resource(&self, namespace: &str, id: &str) -> AResult<Resource> {
         match self {
-            MapperType::Postgres(db) => db.ensure_table_resource().await,
+            MapperType::Postgres(db) => db.delete_resource(namespace, id).await,
+            MapperType::Sqlite(db) => db.delete_resource(namespace, id).await,
+        }
+    }
+
+    async fn count_resources_by_sha256(&self, sha256: &str) -> AResult<i64> {
+        match self {
+            MapperType::Postgres(db) => db.count_resources_by_sha256(sha256).await,
+            MapperType::Sqlite(db) => db.count_resources_by_sha256(sha256).await,
         }
     }
 
@@ -105,6 +309,7 @@ impl ResourceMapper for MapperType {
     ) -> anyhow::Result<InsertInlineResourceRsp> {
         match self {
             MapperType::Postgres(db) => db.insert_inline_resource(req).await,
+            MapperType::Sqlite(db) => db.insert_inline_resource(req).await,
         }
     }
 
@@ -114,12 +319,7 @@ impl ResourceMapper for MapperType {
     ) -> anyhow::Result<crate::model::dto::QueryInlineResourceRsp> {
         match self {
             MapperType::Postgres(db) => db.query_inline_resource(req).await,
-        }
-    }
-
-    async fn ensure_table_inline_resource(&self) -> EResult {
-        match self {
-            MapperType::Postgres(db) => db.ensure_table_inline_resource().await,
+            MapperType::Sqlite(db) => db.query_inline_resource(req).await,
         }
     }
 }
@@ -128,24 +328,280 @@ impl NamespaceMapper for MapperType {
     async fn read_all_namespaces(&self) -> AResult<Vec<NamespaceRecord>> {
         match self {
             MapperType::Postgres(db) => db.read_all_namespaces().await,
+            MapperType::Sqlite(db) => db.read_all_namespaces().await,
         }
     }
 
     async fn read_all_namespace_relations(&self) -> AResult<Vec<NamespaceRelation>> {
         match self {
             MapperType::Postgres(db) => db.read_all_namespace_relations().await,
+            MapperType::Sqlite(db) => db.read_all_namespace_relations().await,
+        }
+    }
+
+    async fn namespace_insert(&self, record: &NamespaceRecord) -> AResult<bool> {
+        match self {
+            MapperType::Postgres(db) => db.namespace_insert(record).await,
+            MapperType::Sqlite(db) => db.namespace_insert(record).await,
+        }
+    }
+
+    async fn namespace_rename(&self, id: &str, name: &str) -> AResult<NamespaceRecord> {
+        match self {
+            MapperType::Postgres(db) => db.namespace_rename(id, name).await,
+            MapperType::Sqlite(db) => db.namespace_rename(id, name).await,
+        }
+    }
+
+    async fn namespace_delete(&self, id: &str) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.namespace_delete(id).await,
+            MapperType::Sqlite(db) => db.namespace_delete(id).await,
+        }
+    }
+
+    async fn namespace_set_parent(&self, id: &str, parent_id: Option<&str>) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.namespace_set_parent(id, parent_id).await,
+            MapperType::Sqlite(db) => db.namespace_set_parent(id, parent_id).await,
+        }
+    }
+
+    async fn namespace_chnot_count(&self, id: &str) -> AResult<i64> {
+        match self {
+            MapperType::Postgres(db) => db.namespace_chnot_count(id).await,
+            MapperType::Sqlite(db) => db.namespace_chnot_count(id).await,
+        }
+    }
+}
+
+impl UserMapper for MapperType {
+    async fn user_insert(&self, user: &User) -> AResult<bool> {
+        match self {
+            MapperType::Postgres(db) => db.user_insert(user).await,
+            MapperType::Sqlite(db) => db.user_insert(user).await,
+        }
+    }
+
+    async fn user_by_name(&self, username: &str) -> AResult<Option<User>> {
+        match self {
+            MapperType::Postgres(db) => db.user_by_name(username).await,
+            MapperType::Sqlite(db) => db.user_by_name(username).await,
+        }
+    }
+
+    async fn user_by_id(&self, id: &str) -> AResult<Option<User>> {
+        match self {
+            MapperType::Postgres(db) => db.user_by_id(id).await,
+            MapperType::Sqlite(db) => db.user_by_id(id).await,
+        }
+    }
+
+    async fn user_list(&self) -> AResult<Vec<User>> {
+        match self {
+            MapperType::Postgres(db) => db.user_list().await,
+            MapperType::Sqlite(db) => db.user_list().await,
+        }
+    }
+
+    async fn user_session_insert(&self, session: &UserSession) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.user_session_insert(session).await,
+            MapperType::Sqlite(db) => db.user_session_insert(session).await,
+        }
+    }
+
+    async fn user_by_session(
+        &self,
+        session_id: &str,
+        now: DateTime<FixedOffset>,
+    ) -> AResult<Option<User>> {
+        match self {
+            MapperType::Postgres(db) => db.user_by_session(session_id, now).await,
+            MapperType::Sqlite(db) => db.user_by_session(session_id, now).await,
+        }
+    }
+
+    async fn user_session_delete(&self, session_id: &str) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.user_session_delete(session_id).await,
+            MapperType::Sqlite(db) => db.user_session_delete(session_id).await,
+        }
+    }
+
+    async fn user_session_purge(&self, now: DateTime<FixedOffset>) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.user_session_purge(now).await,
+            MapperType::Sqlite(db) => db.user_session_purge(now).await,
+        }
+    }
+}
+
+impl ApiTokenMapper for MapperType {
+    async fn api_token_insert(&self, token: &ApiToken) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.api_token_insert(token).await,
+            MapperType::Sqlite(db) => db.api_token_insert(token).await,
+        }
+    }
+
+    async fn api_token_list(&self, user_id: &str) -> AResult<Vec<ApiToken>> {
+        match self {
+            MapperType::Postgres(db) => db.api_token_list(user_id).await,
+            MapperType::Sqlite(db) => db.api_token_list(user_id).await,
+        }
+    }
+
+    async fn api_token_by_hash(&self, token_hash: &str) -> AResult<Option<ApiToken>> {
+        match self {
+            MapperType::Postgres(db) => db.api_token_by_hash(token_hash).await,
+            MapperType::Sqlite(db) => db.api_token_by_hash(token_hash).await,
         }
     }
 
-    async fn ensure_table_namespace_record(&self) -> EResult {
+    async fn api_token_touch(&self, id: &str, time: DateTime<FixedOffset>) -> EResult {
         match self {
-            MapperType::Postgres(db) => db.ensure_table_namespace_record().await,
+            MapperType::Postgres(db) => db.api_token_touch(id, time).await,
+            MapperType::Sqlite(db) => db.api_token_touch(id, time).await,
         }
     }
 
-    async fn ensure_table_namespace_relation(&self) -> EResult {
+    async fn api_token_revoke(
+        &self,
+        user_id: &str,
+        id: &str,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<bool> {
         match self {
-            MapperType::Postgres(db) => db.ensure_table_namespace_relation().await,
+            MapperType::Postgres(db) => db.api_token_revoke(user_id, id, time).await,
+            MapperType::Sqlite(db) => db.api_token_revoke(user_id, id, time).await,
+        }
+    }
+}
+
+impl AclMapper for MapperType {
+    async fn namespace_acl_list(&self, namespace: &str) -> AResult<Vec<NamespaceAcl>> {
+        match self {
+            MapperType::Postgres(db) => db.namespace_acl_list(namespace).await,
+            MapperType::Sqlite(db) => db.namespace_acl_list(namespace).await,
+        }
+    }
+
+    async fn namespace_acl_of_user(&self, user_id: &str) -> AResult<Vec<NamespaceAcl>> {
+        match self {
+            MapperType::Postgres(db) => db.namespace_acl_of_user(user_id).await,
+            MapperType::Sqlite(db) => db.namespace_acl_of_user(user_id).await,
+        }
+    }
+
+    async fn namespace_acl_overwrite(&self, acl: &NamespaceAcl) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.namespace_acl_overwrite(acl).await,
+            MapperType::Sqlite(db) => db.namespace_acl_overwrite(acl).await,
+        }
+    }
+
+    async fn namespace_acl_delete(
+        &self,
+        namespace: &str,
+        subject_kind: AclSubjectKind,
+        subject_id: &str,
+    ) -> AResult<bool> {
+        match self {
+            MapperType::Postgres(db) => {
+                db.namespace_acl_delete(namespace, subject_kind, subject_id)
+                    .await
+            }
+            MapperType::Sqlite(db) => {
+                db.namespace_acl_delete(namespace, subject_kind, subject_id)
+                    .await
+            }
+        }
+    }
+
+    async fn user_group_insert(&self, group: &UserGroup) -> AResult<bool> {
+        match self {
+            MapperType::Postgres(db) => db.user_group_insert(group).await,
+            MapperType::Sqlite(db) => db.user_group_insert(group).await,
+        }
+    }
+
+    async fn user_group_list(&self) -> AResult<Vec<UserGroup>> {
+        match self {
+            MapperType::Postgres(db) => db.user_group_list().await,
+            MapperType::Sqlite(db) => db.user_group_list().await,
+        }
+    }
+
+    async fn user_group_member_list(&self) -> AResult<Vec<UserGroupMember>> {
+        match self {
+            MapperType::Postgres(db) => db.user_group_member_list().await,
+            MapperType::Sqlite(db) => db.user_group_member_list().await,
+        }
+    }
+
+    async fn user_group_member_insert(&self, member: &UserGroupMember) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.user_group_member_insert(member).await,
+            MapperType::Sqlite(db) => db.user_group_member_insert(member).await,
+        }
+    }
+
+    async fn user_group_member_delete(&self, group_id: &str, user_id: &str) -> AResult<bool> {
+        match self {
+            MapperType::Postgres(db) => db.user_group_member_delete(group_id, user_id).await,
+            MapperType::Sqlite(db) => db.user_group_member_delete(group_id, user_id).await,
+        }
+    }
+}
+
+impl ShareLinkMapper for MapperType {
+    async fn share_link_insert(&self, link: &ShareLink) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.share_link_insert(link).await,
+            MapperType::Sqlite(db) => db.share_link_insert(link).await,
+        }
+    }
+
+    async fn share_link_list(&self, namespace: &str) -> AResult<Vec<ShareLink>> {
+        match self {
+            MapperType::Postgres(db) => db.share_link_list(namespace).await,
+            MapperType::Sqlite(db) => db.share_link_list(namespace).await,
+        }
+    }
+
+    async fn share_link_by_id(&self, id: &str) -> AResult<Option<ShareLink>> {
+        match self {
+            MapperType::Postgres(db) => db.share_link_by_id(id).await,
+            MapperType::Sqlite(db) => db.share_link_by_id(id).await,
+        }
+    }
+
+    async fn share_link_revoke(
+        &self,
+        namespace: &str,
+        id: &str,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<bool> {
+        match self {
+            MapperType::Postgres(db) => db.share_link_revoke(namespace, id, time).await,
+            MapperType::Sqlite(db) => db.share_link_revoke(namespace, id, time).await,
+        }
+    }
+}
+
+impl AuditLogMapper for MapperType {
+    async fn audit_log_insert(&self, log: &AuditLog) -> EResult {
+        match self {
+            MapperType::Postgres(db) => db.audit_log_insert(log).await,
+            MapperType::Sqlite(db) => db.audit_log_insert(log).await,
+        }
+    }
+
+    async fn audit_log_query(&self, req: KReq<AuditLogQueryReq>) -> AResult<AuditLogQueryRsp> {
+        match self {
+            MapperType::Postgres(db) => db.audit_log_query(req).await,
+            MapperType::Sqlite(db) => db.audit_log_query(req).await,
         }
     }
 }
@@ -157,6 +613,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatOverwriteBotRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_overwrite_bot(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_overwrite_bot(req).await,
         }
     }
 
@@ -166,6 +623,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatOverwriteTemplateRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_overwrite_template(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_overwrite_template(req).await,
         }
     }
 
@@ -175,6 +633,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatInsertSessionRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_insert_session(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_insert_session(req).await,
         }
     }
 
@@ -184,6 +643,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatInsertRecordRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_insert_record(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_insert_record(req).await,
         }
     }
 
@@ -193,6 +653,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatListBotRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_list_bots(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_list_bots(req).await,
         }
     }
 
@@ -202,6 +663,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatListTemplateRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_list_templates(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_list_templates(req).await,
         }
     }
 
@@ -211,6 +673,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatListSessionRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_list_sessions(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_list_sessions(req).await,
         }
     }
 
@@ -220,6 +683,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatSessionDetailRsp> {
         let mut raw_result = match self {
             MapperType::Postgres(db) => db.llm_chat_session_detail(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_session_detail(req).await,
         }?;
 
         sort_util::sort_by_prev(
@@ -239,6 +703,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<crate::model::dto::llmchat::LLMChatUpdateSessionRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_update_session(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_update_session(req).await,
         }
     }
 
@@ -248,6 +713,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatDeleteBotRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_delete_bot(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_delete_bot(req).await,
         }
     }
 
@@ -257,6 +723,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatDeleteTemplateRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_delete_template(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_delete_template(req).await,
         }
     }
 
@@ -266,30 +733,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<super::LLMChatDeleteSessionRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_delete_session(req).await,
-        }
-    }
-
-    async fn ensure_table_llm_chat_record(&self) -> EResult {
-        match self {
-            MapperType::Postgres(db) => db.ensure_table_llm_chat_record().await,
-        }
-    }
-
-    async fn ensure_table_llm_chat_template(&self) -> EResult {
-        match self {
-            MapperType::Postgres(db) => db.ensure_table_llm_chat_template().await,
-        }
-    }
-
-    async fn ensure_table_llm_chat_session(&self) -> EResult {
-        match self {
-            MapperType::Postgres(db) => db.ensure_table_llm_chat_session().await,
-        }
-    }
-
-    async fn ensure_table_llm_chat_bot(&self) -> EResult {
-        match self {
-            MapperType::Postgres(db) => db.ensure_table_llm_chat_bot().await,
+            MapperType::Sqlite(db) => db.llm_chat_delete_session(req).await,
         }
     }
 
@@ -299,6 +743,7 @@ impl LLMChatMapper for MapperType {
     ) -> AResult<crate::model::dto::llmchat::LLMChatTruncateSessionRsp> {
         match self {
             MapperType::Postgres(db) => db.llm_chat_truncate_session(req).await,
+            MapperType::Sqlite(db) => db.llm_chat_truncate_session(req).await,
         }
     }
 }
@@ -307,6 +752,7 @@ impl MapperType {
     pub async fn dump_and_backup(&self, writer: &TableRowCallbackEnum) -> EResult {
         match self {
             MapperType::Postgres(db) => db.dump_and_callback(writer).await,
+            MapperType::Sqlite(db) => db.dump_and_callback(writer).await,
         }
     }
 }
@@ -318,6 +764,7 @@ impl KVMapper for MapperType {
     ) -> AResult<crate::model::dto::kv::KVOverwriteRsp> {
         match self {
             MapperType::Postgres(db) => db.kv_overwrite(req).await,
+            MapperType::Sqlite(db) => db.kv_overwrite(req).await,
         }
     }
 
@@ -327,18 +774,14 @@ impl KVMapper for MapperType {
     ) -> AResult<crate::model::dto::kv::KVQueryRsp> {
         match self {
             MapperType::Postgres(db) => db.kv_query(req).await,
-        }
-    }
-
-    async fn ensure_table_kv(&self) -> EResult {
-        match self {
-            MapperType::Postgres(db) => db.ensure_table_kv().await,
+            MapperType::Sqlite(db) => db.kv_query(req).await,
         }
     }
 
     async fn kv_delete(&self, req: KReq<super::KVDeleteReq>) -> AResult<super::KVDeleteRsp> {
         match self {
             MapperType::Postgres(db) => db.kv_delete(req).await,
+            MapperType::Sqlite(db) => db.kv_delete(req).await,
         }
     }
 }
@@ -0,0 +1,65 @@
+/// Versioned schema migrations.
+///
+/// Every migration is applied once, in order of its version, and recorded in
+/// the `schema_version` table. A migration is never edited after it is
+/// released, schema changes always go into a new one.
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub postgres: &'static str,
+    pub sqlite: &'static str,
+}
+
+macro_rules! migration {
+    ($version:literal, $name:literal, $file:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            postgres: include_str!(concat!("postgres/", $file)),
+            sqlite: include_str!(concat!("sqlite/", $file)),
+        }
+    };
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    migration!(1, "init", "0001_init.sql"),
+    migration!(2, "chnot_record_fulltext", "0002_chnot_record_fulltext.sql"),
+    migration!(3, "chnot_tag", "0003_chnot_tag.sql"),
+    migration!(4, "chnot_link", "0004_chnot_link.sql"),
+    migration!(5, "chnot_archive", "0005_chnot_archive.sql"),
+    migration!(6, "chnot_move", "0006_chnot_move.sql"),
+    migration!(7, "user", "0007_user.sql"),
+    migration!(8, "api_token", "0008_api_token.sql"),
+    migration!(9, "namespace_acl", "0009_namespace_acl.sql"),
+    migration!(10, "share_link", "0010_share_link.sql"),
+    migration!(11, "audit_log", "0011_audit_log.sql"),
+    migration!(12, "resource_sha256", "0012_resource_sha256.sql"),
+];
+
+/// Migrations whose version is newer than `current`, in applying order.
+pub fn pending_migrations(current: i64) -> impl Iterator<Item = &'static Migration> {
+    MIGRATIONS.iter().filter(move |m| m.version > current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pending_migrations, MIGRATIONS};
+
+    #[test]
+    fn versions_are_increasing() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version);
+        }
+        for m in MIGRATIONS {
+            assert!(!m.postgres.trim().is_empty(), "{}", m.name);
+            assert!(!m.sqlite.trim().is_empty(), "{}", m.name);
+        }
+    }
+
+    #[test]
+    fn pending() {
+        assert_eq!(pending_migrations(0).count(), MIGRATIONS.len());
+        let last = MIGRATIONS.last().unwrap().version;
+        assert_eq!(pending_migrations(last).count(), 0);
+    }
+}
@@ -0,0 +1,110 @@
+create table IF NOT EXISTS chnot_record (
+    id VARCHAR(40) NOT NULL,
+    meta_id VARCHAR(40) NOT NULL,
+    content TEXT NOT NULL,
+    omit_time timestamptz DEFAULT NULL,
+    insert_time timestamptz NOT NULL default CURRENT_TIMESTAMP,
+    primary key (id)
+);
+
+create table IF NOT EXISTS chnot_metadata (
+    id VARCHAR(40) NOT NULL,
+    namespace VARCHAR(100) NOT NULL,
+    kind VARCHAR(100) NOT NULL,
+    pin_time timestamptz DEFAULT NULL,
+    delete_time timestamptz DEFAULT NULL,
+    update_time timestamptz DEFAULT NULL,
+    insert_time timestamptz NOT NULL default CURRENT_TIMESTAMP,
+    primary key (id)
+);
+
+create table IF NOT EXISTS namespace_record (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    delete_time TIMESTAMPTZ,
+    update_time TIMESTAMPTZ,
+    insert_time TIMESTAMPTZ NOT NULL
+);
+
+insert into namespace_record(id, name, insert_time) values
+    ('private', 'private', CURRENT_TIMESTAMP),
+    ('public', 'public', CURRENT_TIMESTAMP),
+    ('work', 'work', CURRENT_TIMESTAMP)
+on conflict do nothing;
+
+create table IF NOT EXISTS namespace_relation (
+    id VARCHAR(40) PRIMARY KEY,
+    sub_id varchar(40),
+    parent_id varchar(40),
+    delete_time TIMESTAMPTZ,
+    update_time TIMESTAMPTZ,
+    insert_time TIMESTAMPTZ NOT NULL
+);
+
+create table IF NOT EXISTS resources (
+    id VARCHAR(40) PRIMARY KEY,
+    namespace VARCHAR(100) NOT NULL,
+    ori_filename VARCHAR(300) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    delete_time TIMESTAMPTZ,
+    insert_time TIMESTAMPTZ NOT NULL
+);
+
+create table IF NOT EXISTS inline_resource (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(300) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    content TEXT NOT NULL,
+    delete_time TIMESTAMPTZ,
+    insert_time TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_chat_bot (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(40) NOT NULL,
+    body TEXT NOT NULL,
+    delete_time TIMESTAMPTZ,
+    update_time TIMESTAMPTZ,
+    insert_time TIMESTAMPTZ NOT NULL,
+    svg_logo TEXT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_chat_template (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(40) NOT NULL,
+    prompt TEXT NOT NULL,
+    icon_name VARCHAR(200),
+    delete_time TIMESTAMPTZ,
+    update_time TIMESTAMPTZ,
+    insert_time TIMESTAMPTZ NOT NULL,
+    svg_logo TEXT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_chat_session (
+    id VARCHAR(40) PRIMARY KEY,
+    bot_id VARCHAR(40) NOT NULL REFERENCES llm_chat_bot(id),
+    template_id VARCHAR(40) NOT NULL REFERENCES llm_chat_template(id),
+    title VARCHAR(300) NOT NULL,
+    namespace VARCHAR(40) NOT NULL,
+    delete_time TIMESTAMPTZ,
+    update_time TIMESTAMPTZ,
+    insert_time TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_chat_record (
+    id VARCHAR(40) PRIMARY KEY,
+    session_id VARCHAR(40) NOT NULL REFERENCES llm_chat_session(id),
+    pre_record_id VARCHAR(40),
+    content TEXT NOT NULL,
+    omit_time TIMESTAMPTZ,
+    role VARCHAR(40) NOT NULL,
+    role_id VARCHAR(40),
+    insert_time TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS kv (
+    key VARCHAR(300) PRIMARY KEY,
+    value TEXT NOT NULL,
+    update_time TIMESTAMPTZ,
+    insert_time TIMESTAMPTZ NOT NULL
+);
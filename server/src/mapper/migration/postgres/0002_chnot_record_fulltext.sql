@@ -0,0 +1,5 @@
+-- 'simple' keeps every word as it is, notes are written in more than one language.
+ALTER TABLE chnot_record ADD COLUMN IF NOT EXISTS content_tsv tsvector
+    GENERATED ALWAYS AS (to_tsvector('simple', content)) STORED;
+
+CREATE INDEX IF NOT EXISTS chnot_record_content_tsv_idx ON chnot_record USING GIN (content_tsv);
@@ -0,0 +1,9 @@
+-- tags parsed from the current record of every chnot, a nested tag like
+-- `proj/chnots` is stored as it is.
+CREATE TABLE IF NOT EXISTS chnot_tag (
+    meta_id VARCHAR(40) NOT NULL,
+    tag VARCHAR(200) NOT NULL,
+    primary key (meta_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS chnot_tag_tag_idx ON chnot_tag (tag);
@@ -0,0 +1,9 @@
+-- `[[target]]` links of the current record of every chnot, the target is a
+-- chnot id or a chnot title and is resolved when it is read.
+CREATE TABLE IF NOT EXISTS chnot_link (
+    meta_id VARCHAR(40) NOT NULL,
+    target VARCHAR(300) NOT NULL,
+    primary key (meta_id, target)
+);
+
+CREATE INDEX IF NOT EXISTS chnot_link_target_idx ON chnot_link (target);
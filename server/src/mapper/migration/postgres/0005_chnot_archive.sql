@@ -0,0 +1 @@
+ALTER TABLE chnot_metadata ADD COLUMN IF NOT EXISTS archive_time timestamptz DEFAULT NULL;
@@ -0,0 +1,10 @@
+-- Moves of chnots between namespaces, they are part of the chnot history.
+CREATE TABLE IF NOT EXISTS chnot_move (
+    id VARCHAR(40) PRIMARY KEY,
+    meta_id VARCHAR(40) NOT NULL,
+    from_namespace VARCHAR(100) NOT NULL,
+    to_namespace VARCHAR(100) NOT NULL,
+    insert_time timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS chnot_move_meta_id_idx ON chnot_move (meta_id);
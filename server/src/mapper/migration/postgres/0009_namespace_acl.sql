@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS user_group (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    insert_time timestamptz NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS user_group_member (
+    group_id VARCHAR(40) NOT NULL,
+    user_id VARCHAR(40) NOT NULL,
+    insert_time timestamptz NOT NULL,
+    PRIMARY KEY (group_id, user_id)
+);
+
+-- Roles of users and groups on namespaces, a role applies to the
+-- descendants of the namespace too.
+CREATE TABLE IF NOT EXISTS namespace_acl (
+    id VARCHAR(40) PRIMARY KEY,
+    namespace VARCHAR(100) NOT NULL,
+    subject_kind VARCHAR(10) NOT NULL,
+    subject_id VARCHAR(40) NOT NULL,
+    role VARCHAR(10) NOT NULL,
+    insert_time timestamptz NOT NULL,
+    UNIQUE (namespace, subject_kind, subject_id)
+);
+
+CREATE INDEX IF NOT EXISTS namespace_acl_subject_idx ON namespace_acl (subject_kind, subject_id);
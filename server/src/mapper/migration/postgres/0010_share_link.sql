@@ -0,0 +1,14 @@
+-- Public read-only links to a chnot or a llm chat session, the token is
+-- signed and carries the id and the expire time of the link.
+CREATE TABLE IF NOT EXISTS share_link (
+    id VARCHAR(40) PRIMARY KEY,
+    namespace VARCHAR(100) NOT NULL,
+    target_kind VARCHAR(20) NOT NULL,
+    target_id VARCHAR(40) NOT NULL,
+    user_id VARCHAR(40) NOT NULL,
+    expire_time timestamptz NOT NULL,
+    delete_time timestamptz,
+    insert_time timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS share_link_namespace_idx ON share_link (namespace);
@@ -0,0 +1,16 @@
+-- Every successful mutation of the api, appended only.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id VARCHAR(40) PRIMARY KEY,
+    actor_id VARCHAR(40) NOT NULL,
+    actor_name VARCHAR(100) NOT NULL,
+    namespace VARCHAR(100) NOT NULL,
+    entity_type VARCHAR(40) NOT NULL,
+    entity_id TEXT NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    -- the json of the request
+    payload TEXT NOT NULL,
+    insert_time timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_namespace_idx ON audit_log (namespace, insert_time);
+CREATE INDEX IF NOT EXISTS audit_log_entity_idx ON audit_log (entity_type, entity_id);
@@ -0,0 +1,6 @@
+-- The sha256 of the file, uploads with the same content share one file
+-- which is removed with the last row. Null for the older uploads, which
+-- keep a file of their own under the id.
+ALTER TABLE resources ADD COLUMN IF NOT EXISTS sha256 VARCHAR(64) DEFAULT NULL;
+
+CREATE INDEX IF NOT EXISTS resources_sha256_idx ON resources (sha256);
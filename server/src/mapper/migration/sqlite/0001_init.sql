@@ -0,0 +1,114 @@
+create table IF NOT EXISTS chnot_record (
+    id VARCHAR(40) NOT NULL,
+    meta_id VARCHAR(40) NOT NULL,
+    content TEXT NOT NULL,
+    omit_time INTEGER DEFAULT NULL,
+    insert_time INTEGER NOT NULL,
+    primary key (id)
+);
+
+create table IF NOT EXISTS chnot_metadata (
+    id VARCHAR(40) NOT NULL,
+    namespace VARCHAR(100) NOT NULL,
+    kind VARCHAR(100) NOT NULL,
+    pin_time INTEGER DEFAULT NULL,
+    delete_time INTEGER DEFAULT NULL,
+    update_time INTEGER DEFAULT NULL,
+    insert_time INTEGER NOT NULL,
+    primary key (id)
+);
+
+create table IF NOT EXISTS namespace_record (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    delete_time INTEGER,
+    update_time INTEGER,
+    insert_time INTEGER NOT NULL
+);
+
+-- insert_time is encoded as sqltype::Timestamptz in UTC.
+insert or ignore into namespace_record(id, name, insert_time)
+select
+    column1,
+    column1,
+    (cast(strftime('%Y', 'now') as integer) * 1000 + cast(strftime('%j', 'now') as integer)) * 1000000000000
+        + (cast(strftime('%s', 'now') as integer) % 86400) * 10000000
+        + 5000
+from (values ('private'), ('public'), ('work'));
+
+create table IF NOT EXISTS namespace_relation (
+    id VARCHAR(40) PRIMARY KEY,
+    sub_id varchar(40),
+    parent_id varchar(40),
+    delete_time INTEGER,
+    update_time INTEGER,
+    insert_time INTEGER NOT NULL
+);
+
+create table IF NOT EXISTS resources (
+    id VARCHAR(40) PRIMARY KEY,
+    namespace VARCHAR(100) NOT NULL,
+    ori_filename VARCHAR(300) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    delete_time INTEGER,
+    insert_time INTEGER NOT NULL
+);
+
+create table IF NOT EXISTS inline_resource (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(300) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    content TEXT NOT NULL,
+    delete_time INTEGER,
+    insert_time INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_chat_bot (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(40) NOT NULL,
+    body TEXT NOT NULL,
+    delete_time INTEGER,
+    update_time INTEGER,
+    insert_time INTEGER NOT NULL,
+    svg_logo TEXT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_chat_template (
+    id VARCHAR(40) PRIMARY KEY,
+    name VARCHAR(40) NOT NULL,
+    prompt TEXT NOT NULL,
+    icon_name VARCHAR(200),
+    delete_time INTEGER,
+    update_time INTEGER,
+    insert_time INTEGER NOT NULL,
+    svg_logo TEXT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_chat_session (
+    id VARCHAR(40) PRIMARY KEY,
+    bot_id VARCHAR(40) NOT NULL REFERENCES llm_chat_bot(id),
+    template_id VARCHAR(40) NOT NULL REFERENCES llm_chat_template(id),
+    title VARCHAR(300) NOT NULL,
+    namespace VARCHAR(40) NOT NULL,
+    delete_time INTEGER,
+    update_time INTEGER,
+    insert_time INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS llm_chat_record (
+    id VARCHAR(40) PRIMARY KEY,
+    session_id VARCHAR(40) NOT NULL REFERENCES llm_chat_session(id),
+    pre_record_id VARCHAR(40),
+    content TEXT NOT NULL,
+    omit_time INTEGER,
+    role VARCHAR(40) NOT NULL,
+    role_id VARCHAR(40),
+    insert_time INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS kv (
+    key VARCHAR(300) PRIMARY KEY,
+    value TEXT NOT NULL,
+    update_time INTEGER,
+    insert_time INTEGER NOT NULL
+);
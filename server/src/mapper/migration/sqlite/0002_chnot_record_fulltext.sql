@@ -0,0 +1,6 @@
+-- Nothing to change on sqlite, the migration is kept so the versions of both
+-- databases stay the same.
+--
+-- tsvector and its GIN index are postgres only. The sqlite mapper serves
+-- `search_mode = full_text` as a substring match of the whole query, so no
+-- column or index is needed for it.
@@ -0,0 +1 @@
+ALTER TABLE chnot_metadata ADD COLUMN archive_time INTEGER DEFAULT NULL;
@@ -0,0 +1,20 @@
+-- Local users, the password is an argon2 phc string.
+CREATE TABLE IF NOT EXISTS user_account (
+    id VARCHAR(40) PRIMARY KEY,
+    username VARCHAR(100) NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    admin INTEGER NOT NULL DEFAULT 0,
+    delete_time INTEGER,
+    update_time INTEGER,
+    insert_time INTEGER NOT NULL
+);
+
+-- Login sessions, only the sha256 of the token is kept.
+CREATE TABLE IF NOT EXISTS user_session (
+    id VARCHAR(64) PRIMARY KEY,
+    user_id VARCHAR(40) NOT NULL,
+    expire_time INTEGER NOT NULL,
+    insert_time INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS user_session_user_id_idx ON user_session (user_id);
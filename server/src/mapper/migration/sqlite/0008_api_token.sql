@@ -0,0 +1,15 @@
+-- Personal api tokens, only the sha256 of the token is kept.
+CREATE TABLE IF NOT EXISTS api_token (
+    id VARCHAR(40) PRIMARY KEY,
+    user_id VARCHAR(40) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    -- a json array of namespace ids, an empty one for every namespace
+    namespaces TEXT NOT NULL,
+    access VARCHAR(10) NOT NULL,
+    last_used_time INTEGER,
+    delete_time INTEGER,
+    insert_time INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS api_token_user_id_idx ON api_token (user_id);
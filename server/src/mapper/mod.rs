@@ -1,27 +1,41 @@
+pub mod cursor;
 pub mod dump;
 pub mod mappertype;
 pub mod db;
+pub mod migration;
+pub mod query;
 pub mod sqlite;
+pub mod versioning;
 
 use dump::{tabledumpsql::TableDumpSql, TableRowCallbackEnum};
+use migration::Migration;
+use versioning::VersionPolicy;
 use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{DateTime, FixedOffset};
 use db::{Postgres, PostgresConfig};
 use serde::{Deserialize, Serialize};
+use sqlite::{Sqlite, SqliteConfig};
 
+use crate::util::activity::DayActivity;
 use crate::model::{
     db::{
-        chnot::{ChnotMetadata, ChnotRecord},
+        acl::{AclSubjectKind, NamespaceAcl, UserGroup, UserGroupMember},
+        audit::AuditLog,
+        chnot::{ChnotLink, ChnotMetadata, ChnotMove, ChnotRecord},
         kv::KV,
         llmchat::{LLMChatBot, LLMChatRecord, LLMChatSession, LLMChatTemplate},
         namespace::{NamespaceRecord, NamespaceRelation},
         resource::Resource,
+        share::ShareLink,
+        user::{ApiToken, User, UserSession},
     },
     dto::{
+        audit::{AuditLogQueryReq, AuditLogQueryRsp},
         chnot::*,
         kv::*,
         llmchat::*,
         InsertInlineResourceReq, InsertInlineResourceRsp, KReq, QueryInlineResourceReq,
-        QueryInlineResourceRsp,
+        QueryInlineResourceRsp, ResourceListReq, ResourceListRsp,
     },
 };
 
@@ -30,25 +44,88 @@ use crate::model::{
 pub enum MapperConfig {
     #[serde(rename = "postgres")]
     Postgres(PostgresConfig),
+    #[serde(rename = "sqlite")]
+    Sqlite(SqliteConfig),
 }
 
 pub enum MapperType {
     Postgres(Postgres),
+    Sqlite(Sqlite),
 }
 
 pub trait ChnotMapper {
-    async fn chnot_overwrite(&self, req: KReq<ChnotOverwriteReq>) -> AResult<ChnotOverwriteRsp>;
+    async fn chnot_overwrite(
+        &self,
+        req: KReq<ChnotOverwriteReq>,
+        policy: &VersionPolicy,
+    ) -> AResult<ChnotOverwriteRsp>;
     async fn chnot_delete(&self, req: KReq<ChnotDeletionReq>) -> AResult<ChnotDeletionRsp>;
     async fn chnot_query(&self, req: KReq<ChnotQueryReq>) -> AResult<ChnotQueryRsp<Vec<Chnot>>>;
-    async fn chnot_update(&self, req: KReq<ChnotUpdateReq>) -> AResult<ChnotUpdateRsp>;
+    async fn chnot_update(&self, req: KReq<ChnotUpdateReq>) -> AResult<ChnotMetadata>;
+    /// Move the chnots and their resources to `target` in one transaction, a
+    /// chnot which is not in its namespace any more is not found.
+    async fn chnot_move(
+        &self,
+        items: &[ChnotMoveItem],
+        target: &str,
+    ) -> AResult<Vec<ChnotMoveResult>>;
+    async fn chnot_history(&self, req: KReq<ChnotHistoryReq>) -> AResult<ChnotHistoryRsp>;
+    async fn chnot_record_by_id(&self, namespace: &str, record_id: &str) -> AResult<ChnotRecord>;
+    /// Every namespace that has chnots, whether the namespace record exists.
+    async fn chnot_namespaces(&self) -> AResult<Vec<String>>;
+    /// Created chnots and saved records in `[after, before)` counted by their
+    /// date at `offset`, the deleted chnots are left out.
+    async fn chnot_activity_days(
+        &self,
+        namespace: &str,
+        after: DateTime<FixedOffset>,
+        before: DateTime<FixedOffset>,
+        offset: FixedOffset,
+    ) -> AResult<Vec<DayActivity>>;
+}
+
+pub trait ChnotTrashMapper {
+    /// The current records of the logic deleted chnots, the latest deleted
+    /// first.
+    async fn chnot_trash_list(
+        &self,
+        req: KReq<ChnotTrashListReq>,
+    ) -> AResult<ChnotQueryRsp<Vec<Chnot>>>;
+    async fn chnot_trash_restore(&self, req: KReq<ChnotTrashRestoreReq>) -> AResult<ChnotMetadata>;
+    /// The chnots deleted before `time`, in every namespace.
+    async fn chnot_trash_expired(&self, time: DateTime<FixedOffset>)
+        -> AResult<Vec<ChnotMetadata>>;
+    /// Remove the chnots with all their records, tags and links.
+    async fn chnot_purge(&self, meta_ids: &[String]) -> EResult;
+}
+
+pub trait ChnotTagMapper {
+    /// Replace the tags of the chnot.
+    async fn chnot_tag_overwrite(&self, meta_id: &str, tags: &[String]) -> EResult;
+    async fn chnot_tag_list(&self, req: KReq<ChnotTagListReq>) -> AResult<ChnotTagListRsp>;
+}
 
-    async fn ensure_table_chnot_record(&self) -> EResult;
-    async fn ensure_table_chnot_metadata(&self) -> EResult;
+pub trait ChnotLinkMapper {
+    /// Replace the link targets of the chnot.
+    async fn chnot_link_overwrite(&self, meta_id: &str, targets: &[String]) -> EResult;
+    /// Links of the not deleted chnots in the namespace, only the ones to
+    /// `targets` if given.
+    async fn chnot_link_list(
+        &self,
+        namespace: &str,
+        targets: Option<&[String]>,
+    ) -> AResult<Vec<ChnotLink>>;
 }
 
 pub trait ResourceMapper {
     async fn insert_resource(&self, resource: &Resource) -> anyhow::Result<Resource>;
     async fn query_resource_by_id(&self, id: &str) -> anyhow::Result<Resource>;
+    async fn query_resources(&self, req: KReq<ResourceListReq>) -> AResult<ResourceListRsp>;
+    /// Remove the row of the resource in the namespace, the file is left to
+    /// the caller.
+    async fn delete_resource(&self, namespace: &str, id: &str) -> AResult<Resource>;
+    /// The rows sharing the file of the sha256.
+    async fn count_resources_by_sha256(&self, sha256: &str) -> AResult<i64>;
     async fn insert_inline_resource(
         &self,
         req: &KReq<InsertInlineResourceReq>,
@@ -57,17 +134,95 @@ pub trait ResourceMapper {
         &self,
         req: KReq<QueryInlineResourceReq>,
     ) -> anyhow::Result<QueryInlineResourceRsp>;
-
-    async fn ensure_table_resource(&self) -> EResult;
-    async fn ensure_table_inline_resource(&self) -> EResult;
 }
 
 pub trait NamespaceMapper {
     async fn read_all_namespaces(&self) -> AResult<Vec<NamespaceRecord>>;
     async fn read_all_namespace_relations(&self) -> AResult<Vec<NamespaceRelation>>;
+    /// Returns false if the id is taken, by a deleted namespace too.
+    async fn namespace_insert(&self, record: &NamespaceRecord) -> AResult<bool>;
+    async fn namespace_rename(&self, id: &str, name: &str) -> AResult<NamespaceRecord>;
+    /// Delete the namespace with the relations to its parent and children.
+    async fn namespace_delete(&self, id: &str) -> EResult;
+    /// Replace the parent of the namespace, no parent makes it a root.
+    async fn namespace_set_parent(&self, id: &str, parent_id: Option<&str>) -> EResult;
+    /// Count of chnots in the namespace, the ones in the trash too.
+    async fn namespace_chnot_count(&self, id: &str) -> AResult<i64>;
+}
 
-    async fn ensure_table_namespace_record(&self) -> EResult;
-    async fn ensure_table_namespace_relation(&self) -> EResult;
+pub trait UserMapper {
+    /// Returns false if the username is taken.
+    async fn user_insert(&self, user: &User) -> AResult<bool>;
+    async fn user_by_name(&self, username: &str) -> AResult<Option<User>>;
+    async fn user_by_id(&self, id: &str) -> AResult<Option<User>>;
+    async fn user_list(&self) -> AResult<Vec<User>>;
+    async fn user_session_insert(&self, session: &UserSession) -> EResult;
+    /// The user of the session which expires after `now`.
+    async fn user_by_session(
+        &self,
+        session_id: &str,
+        now: DateTime<FixedOffset>,
+    ) -> AResult<Option<User>>;
+    async fn user_session_delete(&self, session_id: &str) -> EResult;
+    /// Remove the sessions which expired before `now`.
+    async fn user_session_purge(&self, now: DateTime<FixedOffset>) -> EResult;
+}
+
+pub trait ApiTokenMapper {
+    async fn api_token_insert(&self, token: &ApiToken) -> EResult;
+    /// The not revoked tokens of the user, the newest first.
+    async fn api_token_list(&self, user_id: &str) -> AResult<Vec<ApiToken>>;
+    async fn api_token_by_hash(&self, token_hash: &str) -> AResult<Option<ApiToken>>;
+    async fn api_token_touch(&self, id: &str, time: DateTime<FixedOffset>) -> EResult;
+    /// Returns false if the user has no such token.
+    async fn api_token_revoke(
+        &self,
+        user_id: &str,
+        id: &str,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<bool>;
+}
+
+pub trait AclMapper {
+    async fn namespace_acl_list(&self, namespace: &str) -> AResult<Vec<NamespaceAcl>>;
+    /// The acls granted to the user directly or by its groups.
+    async fn namespace_acl_of_user(&self, user_id: &str) -> AResult<Vec<NamespaceAcl>>;
+    /// Insert the acl or replace the role of its subject on the namespace.
+    async fn namespace_acl_overwrite(&self, acl: &NamespaceAcl) -> EResult;
+    /// Returns false if the subject has no role on the namespace.
+    async fn namespace_acl_delete(
+        &self,
+        namespace: &str,
+        subject_kind: AclSubjectKind,
+        subject_id: &str,
+    ) -> AResult<bool>;
+    /// Returns false if the name is taken.
+    async fn user_group_insert(&self, group: &UserGroup) -> AResult<bool>;
+    async fn user_group_list(&self) -> AResult<Vec<UserGroup>>;
+    async fn user_group_member_list(&self) -> AResult<Vec<UserGroupMember>>;
+    async fn user_group_member_insert(&self, member: &UserGroupMember) -> EResult;
+    /// Returns false if the user is not in the group.
+    async fn user_group_member_delete(&self, group_id: &str, user_id: &str) -> AResult<bool>;
+}
+
+pub trait ShareLinkMapper {
+    async fn share_link_insert(&self, link: &ShareLink) -> EResult;
+    /// The not revoked links of the namespace, the newest first.
+    async fn share_link_list(&self, namespace: &str) -> AResult<Vec<ShareLink>>;
+    /// The link even if it is revoked or expired.
+    async fn share_link_by_id(&self, id: &str) -> AResult<Option<ShareLink>>;
+    /// Returns false if the namespace has no such link.
+    async fn share_link_revoke(
+        &self,
+        namespace: &str,
+        id: &str,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<bool>;
+}
+
+pub trait AuditLogMapper {
+    async fn audit_log_insert(&self, log: &AuditLog) -> EResult;
+    async fn audit_log_query(&self, req: KReq<AuditLogQueryReq>) -> AResult<AuditLogQueryRsp>;
 }
 
 pub trait LLMChatMapper {
@@ -122,18 +277,20 @@ pub trait LLMChatMapper {
         &self,
         req: KReq<LLMChatDeleteSessionReq>,
     ) -> AResult<LLMChatDeleteSessionRsp>;
-
-    async fn ensure_table_llm_chat_bot(&self) -> EResult;
-    async fn ensure_table_llm_chat_template(&self) -> EResult;
-    async fn ensure_table_llm_chat_session(&self) -> EResult;
-    async fn ensure_table_llm_chat_record(&self) -> EResult;
 }
 
 pub trait KVMapper {
     async fn kv_overwrite(&self, req: KReq<KVOverwriteReq>) -> AResult<KVOverwriteRsp>;
     async fn kv_query(&self, req: KReq<KVQueryReq>) -> AResult<KVQueryRsp>;
     async fn kv_delete(&self, req: KReq<KVDeleteReq>) -> AResult<KVDeleteRsp>;
-    async fn ensure_table_kv(&self) -> EResult;
+}
+
+pub trait MigrationMapper {
+    async fn ensure_table_schema_version(&self) -> EResult;
+    /// The newest applied migration version, 0 for a fresh database.
+    async fn read_schema_version(&self) -> AResult<i64>;
+    /// Apply the migration and record its version in one transaction.
+    async fn apply_migration(&self, migration: &'static Migration) -> EResult;
 }
 
 pub trait DumpMapper {
@@ -156,6 +313,7 @@ pub trait DeserializeMapper {
 
     fn to_chnot_meta(row: Self::RowType) -> AResult<ChnotMetadata>;
     fn to_chnot_record(row: Self::RowType) -> AResult<ChnotRecord>;
+    fn to_chnot_move(row: Self::RowType) -> AResult<ChnotMove>;
 
     fn to_llmchat_bot(row: Self::RowType) -> AResult<LLMChatBot>;
     fn to_llmchat_template(row: Self::RowType) -> AResult<LLMChatTemplate>;
@@ -167,5 +325,16 @@ pub trait DeserializeMapper {
 
     fn to_resource(row: Self::RowType) -> AResult<Resource>;
 
+    fn to_user(row: Self::RowType) -> AResult<User>;
+    fn to_api_token(row: Self::RowType) -> AResult<ApiToken>;
+
+    fn to_namespace_acl(row: Self::RowType) -> AResult<NamespaceAcl>;
+    fn to_user_group(row: Self::RowType) -> AResult<UserGroup>;
+    fn to_user_group_member(row: Self::RowType) -> AResult<UserGroupMember>;
+
+    fn to_share_link(row: Self::RowType) -> AResult<ShareLink>;
+
+    fn to_audit_log(row: Self::RowType) -> AResult<AuditLog>;
+
     fn to_kv(row: Self::RowType) -> AResult<KV>;
 }
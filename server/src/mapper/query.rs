@@ -0,0 +1,314 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone};
+use serde::Serialize;
+
+use crate::model::db::chnot::ChnotKind;
+
+/// The query string of `chnot_query`.
+///
+/// ```text
+/// tag:rust ns:work before:2025-01-01 pinned:true kind:mdwt "exact phrase" -excluded
+/// ```
+///
+/// Terms are separated by spaces and all of them must match. A leading `-`
+/// excludes the term, a quoted text keeps its spaces, a word with an unknown
+/// `key:` prefix is searched as plain text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChnotQuery {
+    /// Searches this namespace instead of the one in the request header.
+    pub namespace: Option<String>,
+    pub terms: Vec<QueryTerm>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTerm {
+    pub filter: QueryFilter,
+    pub exclude: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryFilter {
+    /// Case-insensitive substring of the content.
+    Text(String),
+    /// The tag or a tag nested in it.
+    Tag(String),
+    Kind(String),
+    Pinned(bool),
+    /// The chnot is created before this time.
+    Before(DateTime<FixedOffset>),
+    /// The chnot is created at or after this time.
+    After(DateTime<FixedOffset>),
+}
+
+/// `position` is the char index in the query string where the error starts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueryParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for QueryParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid query at position {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for QueryParseError {}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error<T>(position: usize, message: impl Into<String>) -> Result<T, QueryParseError> {
+        Err(QueryParseError {
+            position,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Read a quoted text or a bare word, returns the text and whether it is
+    /// quoted.
+    fn read_text(&mut self) -> Result<(String, bool), QueryParseError> {
+        let start = self.pos;
+        if self.peek() == Some('"') {
+            self.pos += 1;
+            let from = self.pos;
+            while self.peek().is_some_and(|c| c != '"') {
+                self.pos += 1;
+            }
+            if self.peek().is_none() {
+                return Self::error(start, "unterminated quote");
+            }
+            let text: String = self.chars[from..self.pos].iter().collect();
+            self.pos += 1;
+            Ok((text, true))
+        } else {
+            while let Some(c) = self.peek() {
+                // stop before the quoted value of `key:"..."`
+                if c.is_whitespace()
+                    || (c == '"' && self.pos > start && self.chars[self.pos - 1] == ':')
+                {
+                    break;
+                }
+                self.pos += 1;
+            }
+            Ok((self.chars[start..self.pos].iter().collect(), false))
+        }
+    }
+
+    fn parse(mut self) -> Result<ChnotQuery, QueryParseError> {
+        let mut query = ChnotQuery::default();
+
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            let Some(c) = self.peek() else {
+                break;
+            };
+
+            let exclude = c == '-'
+                && self
+                    .chars
+                    .get(self.pos + 1)
+                    .is_some_and(|c| !c.is_whitespace());
+            if exclude {
+                self.pos += 1;
+            }
+
+            let (text, quoted) = self.read_text()?;
+            if quoted {
+                if text.is_empty() {
+                    return Self::error(start, "empty phrase");
+                }
+                query.terms.push(QueryTerm {
+                    filter: QueryFilter::Text(text),
+                    exclude,
+                });
+                continue;
+            }
+
+            let filter = match text.split_once(':') {
+                Some((key, value)) if is_key(key) => {
+                    let value_start = start + exclude as usize + key.chars().count() + 1;
+                    // the value can be quoted too, like `tag:"a b"`
+                    let value = if value.is_empty() && self.peek() == Some('"') {
+                        self.read_text()?.0
+                    } else {
+                        value.to_owned()
+                    };
+                    if value.is_empty() {
+                        return Self::error(value_start, format!("missing value of {}:", key));
+                    }
+
+                    if key == "ns" {
+                        if exclude {
+                            return Self::error(start, "ns: can not be excluded");
+                        }
+                        if query.namespace.is_some() {
+                            return Self::error(start, "ns: is given more than once");
+                        }
+                        query.namespace = Some(value);
+                        continue;
+                    }
+
+                    parse_filter(key, &value).map_err(|message| QueryParseError {
+                        position: value_start,
+                        message,
+                    })?
+                }
+                _ => QueryFilter::Text(text),
+            };
+
+            query.terms.push(QueryTerm { filter, exclude });
+        }
+
+        Ok(query)
+    }
+}
+
+fn is_key(key: &str) -> bool {
+    matches!(key, "tag" | "ns" | "kind" | "pinned" | "before" | "after")
+}
+
+fn parse_filter(key: &str, value: &str) -> Result<QueryFilter, String> {
+    let filter = match key {
+        "tag" => QueryFilter::Tag(value.trim_start_matches('#').to_owned()),
+        "kind" => QueryFilter::Kind(
+            ChnotKind::from_str(value)
+                .map_err(|_| format!("unknown kind {}", value))?
+                .to_string(),
+        ),
+        "pinned" => QueryFilter::Pinned(
+            value
+                .parse::<bool>()
+                .map_err(|_| format!("pinned: expects true or false, got {}", value))?,
+        ),
+        "before" => QueryFilter::Before(parse_time(value)?),
+        "after" => QueryFilter::After(parse_time(value)?),
+        _ => unreachable!("{} is not a query key", key),
+    };
+    Ok(filter)
+}
+
+/// A date is the local midnight of that day, or an RFC 3339 time.
+fn parse_time(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time);
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+        .map(|time| time.fixed_offset())
+        .ok_or_else(|| format!("{} is not a date like 2025-01-01", value))
+}
+
+impl FromStr for ChnotQuery {
+    type Err = QueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        }
+        .parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::{ChnotQuery, QueryFilter, QueryTerm};
+
+    fn term(filter: QueryFilter, exclude: bool) -> QueryTerm {
+        QueryTerm { filter, exclude }
+    }
+
+    #[test]
+    fn full_query() {
+        let query: ChnotQuery =
+            r#"tag:rust ns:work pinned:true kind:mdwt "exact phrase" -excluded"#
+                .parse()
+                .unwrap();
+
+        assert_eq!(query.namespace.as_deref(), Some("work"));
+        assert_eq!(
+            query.terms,
+            vec![
+                term(QueryFilter::Tag("rust".to_owned()), false),
+                term(QueryFilter::Pinned(true), false),
+                term(QueryFilter::Kind("mdwt".to_owned()), false),
+                term(QueryFilter::Text("exact phrase".to_owned()), false),
+                term(QueryFilter::Text("excluded".to_owned()), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn times() {
+        let query: ChnotQuery = "before:2025-01-01T00:00:00+08:00 -after:2024-06-01"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            query.terms[0],
+            term(
+                QueryFilter::Before(
+                    DateTime::parse_from_rfc3339("2025-01-01T00:00:00+08:00").unwrap()
+                ),
+                false
+            )
+        );
+        assert!(matches!(query.terms[1].filter, QueryFilter::After(_)));
+        assert!(query.terms[1].exclude);
+    }
+
+    #[test]
+    fn plain_text() {
+        let query: ChnotQuery = "https://example.com - a:b tag:\"two words\""
+            .parse()
+            .unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                term(QueryFilter::Text("https://example.com".to_owned()), false),
+                term(QueryFilter::Text("-".to_owned()), false),
+                term(QueryFilter::Text("a:b".to_owned()), false),
+                term(QueryFilter::Tag("two words".to_owned()), false),
+            ]
+        );
+        assert_eq!("  ".parse::<ChnotQuery>().unwrap(), ChnotQuery::default());
+    }
+
+    #[test]
+    fn errors() {
+        let position = |s: &str| s.parse::<ChnotQuery>().unwrap_err().position;
+
+        assert_eq!(position("abc \"open"), 4);
+        assert_eq!(position("abc pinned:yes"), 11);
+        assert_eq!(position("abc -kind:unknown"), 10);
+        assert_eq!(position("before:2025-13-01"), 7);
+        assert_eq!(position("abc tag:"), 8);
+        assert_eq!(position("a -ns:work"), 2);
+        assert_eq!(position("ns:a ns:b"), 5);
+        assert_eq!(position("\"\""), 0);
+    }
+}
@@ -0,0 +1,132 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+
+use super::DeserializeMapper;
+use crate::{
+    mapper::AclMapper,
+    model::db::acl::{AclSubjectKind, NamespaceAcl, UserGroup, UserGroupMember},
+};
+
+use super::{sqltype::Timestamptz, Sqlite};
+
+impl AclMapper for Sqlite {
+    async fn namespace_acl_list(&self, namespace: &str) -> AResult<Vec<NamespaceAcl>> {
+        self.query_rows(
+            "select * from namespace_acl where namespace = ?1 order by insert_time".to_owned(),
+            vec![namespace.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .map(Self::to_namespace_acl)
+        .collect()
+    }
+
+    async fn namespace_acl_of_user(&self, user_id: &str) -> AResult<Vec<NamespaceAcl>> {
+        self.query_rows(
+            "select * from namespace_acl where (subject_kind = 'user' and subject_id = ?1) \
+             or (subject_kind = 'group' and subject_id in (select group_id from user_group_member where user_id = ?1))"
+                .to_owned(),
+            vec![user_id.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .map(Self::to_namespace_acl)
+        .collect()
+    }
+
+    async fn namespace_acl_overwrite(&self, acl: &NamespaceAcl) -> EResult {
+        self.execute(
+            "insert into namespace_acl(id, namespace, subject_kind, subject_id, role, insert_time) values(?1, ?2, ?3, ?4, ?5, ?6) \
+             on conflict (namespace, subject_kind, subject_id) do update set role = excluded.role",
+            vec![
+                acl.id.clone().into(),
+                acl.namespace.clone().into(),
+                acl.subject_kind.to_string().into(),
+                acl.subject_id.clone().into(),
+                acl.role.to_string().into(),
+                Timestamptz::from(acl.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn namespace_acl_delete(
+        &self,
+        namespace: &str,
+        subject_kind: AclSubjectKind,
+        subject_id: &str,
+    ) -> AResult<bool> {
+        let count = self
+            .execute(
+                "delete from namespace_acl where namespace = ?1 and subject_kind = ?2 and subject_id = ?3",
+                vec![
+                    namespace.to_owned().into(),
+                    subject_kind.to_string().into(),
+                    subject_id.to_owned().into(),
+                ],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn user_group_insert(&self, group: &UserGroup) -> AResult<bool> {
+        let count = self
+            .execute(
+                "insert into user_group(id, name, insert_time) values(?1, ?2, ?3) on conflict do nothing",
+                vec![
+                    group.id.clone().into(),
+                    group.name.clone().into(),
+                    Timestamptz::from(group.insert_time).into(),
+                ],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn user_group_list(&self) -> AResult<Vec<UserGroup>> {
+        self.query_rows("select * from user_group order by name".to_owned(), vec![])
+            .await?
+            .into_iter()
+            .map(Self::to_user_group)
+            .collect()
+    }
+
+    async fn user_group_member_list(&self) -> AResult<Vec<UserGroupMember>> {
+        self.query_rows(
+            "select * from user_group_member order by insert_time".to_owned(),
+            vec![],
+        )
+        .await?
+        .into_iter()
+        .map(Self::to_user_group_member)
+        .collect()
+    }
+
+    async fn user_group_member_insert(&self, member: &UserGroupMember) -> EResult {
+        self.execute(
+            "insert into user_group_member(group_id, user_id, insert_time) values(?1, ?2, ?3) on conflict do nothing",
+            vec![
+                member.group_id.clone().into(),
+                member.user_id.clone().into(),
+                Timestamptz::from(member.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn user_group_member_delete(&self, group_id: &str, user_id: &str) -> AResult<bool> {
+        let count = self
+            .execute(
+                "delete from user_group_member where group_id = ?1 and user_id = ?2",
+                vec![group_id.to_owned().into(), user_id.to_owned().into()],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+}
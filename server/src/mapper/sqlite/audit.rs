@@ -0,0 +1,86 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use deadpool_sqlite::rusqlite::types::Value;
+
+use super::DeserializeMapper;
+use crate::{
+    mapper::AuditLogMapper,
+    model::{
+        db::audit::AuditLog,
+        dto::{
+            audit::{AuditLogQueryReq, AuditLogQueryRsp},
+            KReq,
+        },
+    },
+};
+
+use super::{sqltype::Timestamptz, Sqlite};
+
+impl AuditLogMapper for Sqlite {
+    async fn audit_log_insert(&self, log: &AuditLog) -> EResult {
+        self.execute(
+            "insert into audit_log(id, actor_id, actor_name, namespace, entity_type, entity_id, action, payload, insert_time) values(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            vec![
+                log.id.clone().into(),
+                log.actor_id.clone().into(),
+                log.actor_name.clone().into(),
+                log.namespace.clone().into(),
+                log.entity_type.to_string().into(),
+                log.entity_id.clone().into(),
+                log.action.to_string().into(),
+                log.payload.to_string().into(),
+                Timestamptz::from(log.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn audit_log_query(&self, req: KReq<AuditLogQueryReq>) -> AResult<AuditLogQueryRsp> {
+        let mut wheres: Vec<&str> = vec!["namespace = ?"];
+        let mut values: Vec<Value> = vec![req.namespace.clone().into()];
+
+        if let Some(actor_id) = req.actor_id.as_ref() {
+            wheres.push("actor_id = ?");
+            values.push(actor_id.clone().into());
+        }
+        if let Some(entity_type) = req.entity_type {
+            wheres.push("entity_type = ?");
+            values.push(entity_type.to_string().into());
+        }
+        if let Some(entity_id) = req.entity_id.as_ref() {
+            wheres.push("entity_id = ?");
+            values.push(entity_id.clone().into());
+        }
+        if let Some(action) = req.action {
+            wheres.push("action = ?");
+            values.push(action.to_string().into());
+        }
+        if let Some(after) = req.after {
+            wheres.push("insert_time >= ?");
+            values.push(Timestamptz::from(after).into());
+        }
+        if let Some(before) = req.before {
+            wheres.push("insert_time < ?");
+            values.push(Timestamptz::from(before).into());
+        }
+        values.push((req.page_size as i64).into());
+        values.push((req.start_index as i64).into());
+
+        let sql = format!(
+            "select * from audit_log where {} order by insert_time desc, id limit ? offset ?",
+            wheres.join(" and ")
+        );
+        let logs = self
+            .query_rows(sql, values)
+            .await?
+            .into_iter()
+            .map(Self::to_audit_log)
+            .collect::<AResult<Vec<AuditLog>>>()?;
+
+        Ok(AuditLogQueryRsp {
+            logs,
+            start_index: req.start_index,
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Context;
+use chin_tools::{
+    sql::PlaceHolderType,
+    wrapper::anyhow::{AResult, EResult},
+};
+use serde::Serialize;
+
+use crate::mapper::{
+    dump::{tabledumpsql::TableDumpSql, DumpWrapper, TableRowCallback, TableRowCallbackEnum},
+    DeserializeMapper, DumpMapper,
+};
+
+use super::{Sqlite, SqliteRow};
+
+impl DumpMapper for Sqlite {
+    type RowType = SqliteRow;
+
+    async fn dump_and_callback(&self, callback: &TableRowCallbackEnum) -> EResult {
+        let s = |name: &'static str| {
+            TableDumpSql::new(name.to_owned(), None, None, PlaceHolderType::QustionMark)
+        };
+
+        self.read_iterator(s("chnot_record"), Self::to_chnot_record, &callback)
+            .await?;
+        self.read_iterator(s("chnot_metadata"), Self::to_chnot_meta, &callback)
+            .await?;
+        self.read_iterator(s("namespace_record"), Self::to_namespace_record, &callback)
+            .await?;
+        self.read_iterator(
+            s("namespace_relation"),
+            Self::to_namespace_relation,
+            &callback,
+        )
+        .await?;
+        self.read_iterator(s("resources"), Self::to_resource, &callback)
+            .await?;
+        self.read_iterator(s("llm_chat_bot"), Self::to_llmchat_bot, &callback)
+            .await?;
+        self.read_iterator(s("llm_chat_record"), Self::to_llmchat_record, &callback)
+            .await?;
+        self.read_iterator(s("llm_chat_session"), Self::to_llmchat_session, &callback)
+            .await?;
+        self.read_iterator(s("llm_chat_template"), Self::to_llmchat_template, &callback)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn read_iterator<'a, F1, O: Serialize>(
+        &self,
+        sql_builder: TableDumpSql<'a>,
+        convert_row_to_obj: F1,
+        callback: &TableRowCallbackEnum,
+    ) -> EResult
+    where
+        F1: Fn(Self::RowType) -> AResult<O>,
+    {
+        let table_name = sql_builder.table_name.clone();
+
+        let seg = sql_builder.build().context("unable to build dump sql")?;
+        anyhow::ensure!(
+            seg.values.is_empty(),
+            "sqlite dump does not support ranged dump yet"
+        );
+
+        let rows = self.query_rows(seg.seg.to_string(), vec![]).await?;
+
+        for row in rows {
+            match convert_row_to_obj(row) {
+                Ok(obj) => {
+                    callback
+                        .callback(DumpWrapper::of(obj, 1, &table_name))
+                        .await?;
+                }
+                Err(err) => {
+                    tracing::error!("{} -- unable to convert {}", table_name, err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,682 @@
+use std::str::FromStr;
+
+use chin_tools::wrapper::anyhow::AResult;
+use chrono::{DateTime, FixedOffset, Local};
+use deadpool_sqlite::rusqlite::{params, types::Value, OptionalExtension};
+use tracing::{error, info};
+
+use crate::{
+    error::KError,
+    mapper::{
+        cursor::ChnotCursor,
+        query::{ChnotQuery, QueryFilter, QueryTerm},
+        versioning::VersionPolicy,
+        ChnotMapper, ChnotTrashMapper, DeserializeMapper,
+    },
+    model::{
+        db::chnot::{ChnotMetadata, ChnotMove, ChnotRecord},
+        dto::{chnot::*, KReq},
+    },
+    util::activity::{count_by_day, DayActivity},
+};
+
+use super::{sqltype::Timestamptz, Sqlite, SqliteRow};
+
+/// Escape the wildcards of a `like ... escape '\'` pattern.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// A row of `chnot_record r` joined with `chnot_metadata m`, the columns are
+/// aliased as in `chnot_query`.
+pub(super) fn row_to_chnot(row: &SqliteRow) -> AResult<Chnot> {
+    let record = ChnotRecord {
+        id: row.try_get("rid")?,
+        meta_id: row.try_get("mid")?,
+        content: row.try_get("content")?,
+        omit_time: row.try_get_time_opt("omit_time")?,
+        insert_time: row.try_get_time("version_time")?,
+    };
+    let meta = ChnotMetadata {
+        id: row.try_get("mid")?,
+        namespace: row.try_get("namespace")?,
+        kind: row.try_get("kind")?,
+        pin_time: row.try_get_time_opt("pin_time")?,
+        archive_time: row.try_get_time_opt("archive_time")?,
+        delete_time: row.try_get_time_opt("delete_time")?,
+        update_time: row.try_get_time_opt("update_time")?,
+        insert_time: row.try_get_time("init_time")?,
+    };
+    Ok(Chnot { record, meta })
+}
+
+impl ChnotMapper for Sqlite {
+    async fn chnot_overwrite(
+        &self,
+        req: KReq<ChnotOverwriteReq>,
+        policy: &VersionPolicy,
+    ) -> AResult<ChnotOverwriteRsp> {
+        tracing::debug!("begin to overwrite chnot, {}", req.id);
+        let chnot = req.body.chnot.clone();
+        let namespace = req.namespace.clone();
+        let kind = req.kind.to_string();
+        let checkpoint = req.checkpoint;
+        let policy = policy.clone();
+
+        let (meta_insert_time, new_version, record_id) = self
+            .interact(move |conn| {
+                let transaction = conn.transaction()?;
+
+                let meta = transaction
+                    .query_row(
+                        "select namespace, insert_time from chnot_metadata where id = ?1",
+                        params![&chnot.meta_id],
+                        |row| {
+                            Ok((
+                                row.get::<_, String>("namespace")?,
+                                row.get::<_, Timestamptz>("insert_time")?,
+                            ))
+                        },
+                    )
+                    .optional()?;
+                let meta_insert_time = match meta {
+                    Some((meta_namespace, _)) if meta_namespace != namespace => {
+                        return Err(KError::NotFound(format!(
+                            "unable to find chnot {}",
+                            chnot.meta_id
+                        ))
+                        .into());
+                    }
+                    meta => meta.map(|(_, insert_time)| insert_time),
+                };
+
+                let old_record = transaction
+                    .query_row(
+                        "select id, content, insert_time from chnot_record where meta_id = ?1 and omit_time is null",
+                        params![&chnot.meta_id],
+                        |row| {
+                            Ok((
+                                row.get::<_, String>("id")?,
+                                row.get::<_, String>("content")?,
+                                row.get::<_, Timestamptz>("insert_time")?,
+                            ))
+                        },
+                    )
+                    .optional()?;
+
+                let insert_time = Timestamptz::from(chnot.insert_time);
+
+                transaction.execute(
+                    "insert into chnot_metadata(id, insert_time, namespace, kind) values(?1, ?2, ?3, ?4) on CONFLICT (id) DO UPDATE SET update_time = ?2",
+                    params![&chnot.meta_id, &insert_time, &namespace, &kind],
+                )?;
+
+                let new_version = policy.is_new_version(
+                    old_record
+                        .as_ref()
+                        .map(|(_, content, insert_time)| (content.as_str(), &insert_time.value)),
+                    &chnot.content,
+                    &chnot.insert_time,
+                    checkpoint,
+                );
+
+                // a merged edit keeps the id of the record, the ids handed
+                // out by the history stay valid
+                let id = match old_record.as_ref() {
+                    Some((old_id, _, _)) if !new_version => old_id,
+                    _ => &chnot.id,
+                };
+
+                if let Some((old_id, _, _)) = old_record.as_ref() {
+                    if &chnot.id == id {
+                        transaction.execute(
+                            "update chnot_record set omit_time = ?1 where id = ?2",
+                            params![&insert_time, old_id],
+                        )?;
+                    }
+                }
+
+                transaction.execute(
+                    "insert into chnot_record(id, meta_id, content, insert_time) values(?1, ?2, ?3, ?4) on CONFLICT (id) DO UPDATE SET content=?3,insert_time=?4",
+                    params![id, &chnot.meta_id, &chnot.content, &insert_time],
+                )?;
+                let record_id = id.clone();
+
+                transaction.commit()?;
+
+                Ok((meta_insert_time.map(|e| e.value), new_version, record_id))
+            })
+            .await?;
+
+        Ok(ChnotOverwriteRsp {
+            chnot: Chnot {
+                meta: ChnotMetadata {
+                    id: req.chnot.meta_id.clone(),
+                    namespace: req.namespace.clone(),
+                    kind: req.kind.to_string(),
+                    pin_time: None,
+                    archive_time: None,
+                    delete_time: None,
+                    update_time: None,
+                    insert_time: meta_insert_time.unwrap_or(req.insert_time.clone()),
+                },
+                record: ChnotRecord {
+                    id: record_id,
+                    ..req.chnot.clone()
+                },
+            },
+            new_version,
+        })
+    }
+
+    async fn chnot_delete(&self, req: KReq<ChnotDeletionReq>) -> AResult<ChnotDeletionRsp> {
+        if !req.logic {
+            self.query_rows(
+                "select id from chnot_metadata where id = ?1 and namespace = ?2".to_owned(),
+                vec![req.chnot_id.clone().into(), req.namespace.clone().into()],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| KError::NotFound(format!("unable to find chnot {}", req.chnot_id)))?;
+            self.chnot_purge(&[req.chnot_id.clone()]).await?;
+            return Ok(ChnotDeletionRsp {});
+        }
+
+        let count = self
+            .execute(
+                "update chnot_metadata set delete_time = ?1 where id = ?2 and namespace = ?3",
+                vec![
+                    Timestamptz::from(Local::now().fixed_offset()).into(),
+                    req.chnot_id.clone().into(),
+                    req.namespace.clone().into(),
+                ],
+            )
+            .await?;
+        if count == 0 {
+            return Err(KError::NotFound(format!("unable to find chnot {}", req.chnot_id)).into());
+        }
+
+        Ok(ChnotDeletionRsp {})
+    }
+
+    async fn chnot_query(&self, req: KReq<ChnotQueryReq>) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
+        let mut wheres: Vec<&str> = vec![];
+        let mut values: Vec<Value> = vec![];
+
+        // default without deleted chnot
+        if !req.with_deleted.unwrap_or(false) {
+            wheres.push("m.delete_time is null");
+        }
+        // default without omit chnot record
+        if !req.with_omitted.unwrap_or(false) {
+            wheres.push("r.omit_time is null");
+        }
+        // without the tsvector of postgres the full text search is a
+        // substring match of the whole query, its syntax is not parsed
+        let query = match (req.search_mode, req.query.as_deref()) {
+            (ChnotSearchMode::FullText, Some(query)) => ChnotQuery {
+                namespace: None,
+                terms: vec![QueryTerm {
+                    filter: QueryFilter::Text(query.to_owned()),
+                    exclude: false,
+                }],
+            },
+            (_, query) => query
+                .map(ChnotQuery::from_str)
+                .transpose()?
+                .unwrap_or_default(),
+        };
+        match req.archived {
+            ChnotArchiveFilter::Exclude => wheres.push("m.archive_time is null"),
+            ChnotArchiveFilter::Include => {}
+            ChnotArchiveFilter::Only => wheres.push("m.archive_time is not null"),
+        }
+        let namespace_clause = format!(
+            "m.namespace in ({})",
+            vec!["?"; req.descendants.len() + 1].join(", ")
+        );
+        wheres.push(&namespace_clause);
+        values.push(
+            query
+                .namespace
+                .clone()
+                .unwrap_or(req.namespace.clone())
+                .into(),
+        );
+        values.extend(req.descendants.iter().map(|e| Value::from(e.clone())));
+        for term in query.terms.iter() {
+            let (clause, params): (&str, Vec<Value>) = match (&term.filter, term.exclude) {
+                (QueryFilter::Text(text), false) => (
+                    "r.content like ? escape '\\'",
+                    vec![format!("%{}%", escape_like(text)).into()],
+                ),
+                (QueryFilter::Text(text), true) => (
+                    "r.content not like ? escape '\\'",
+                    vec![format!("%{}%", escape_like(text)).into()],
+                ),
+                (QueryFilter::Tag(tag), exclude) => (
+                    if exclude {
+                        "m.id not in (select meta_id from chnot_tag where tag = ? or tag like ? escape '\\')"
+                    } else {
+                        "m.id in (select meta_id from chnot_tag where tag = ? or tag like ? escape '\\')"
+                    },
+                    vec![tag.clone().into(), format!("{}/%", escape_like(tag)).into()],
+                ),
+                (QueryFilter::Kind(kind), false) => ("m.kind = ?", vec![kind.clone().into()]),
+                (QueryFilter::Kind(kind), true) => ("m.kind != ?", vec![kind.clone().into()]),
+                (QueryFilter::Pinned(pinned), exclude) if *pinned != exclude => {
+                    ("m.pin_time is not null", vec![])
+                }
+                (QueryFilter::Pinned(_), _) => ("m.pin_time is null", vec![]),
+                (QueryFilter::Before(time), false) | (QueryFilter::After(time), true) => {
+                    ("m.insert_time < ?", vec![Timestamptz::from(*time).into()])
+                }
+                (QueryFilter::After(time), false) | (QueryFilter::Before(time), true) => {
+                    ("m.insert_time >= ?", vec![Timestamptz::from(*time).into()])
+                }
+            };
+            wheres.push(clause);
+            values.extend(params);
+        }
+        let range = &req.time_range;
+        for (time, clause) in [
+            (range.inserted_after, "m.insert_time >= ?"),
+            (range.inserted_before, "m.insert_time < ?"),
+            (range.updated_after, "m.update_time >= ?"),
+            (range.updated_before, "m.update_time < ?"),
+            (range.version_after, "r.insert_time >= ?"),
+            (range.version_before, "r.insert_time < ?"),
+        ] {
+            if let Some(time) = time {
+                wheres.push(clause);
+                values.push(Timestamptz::from(time).into());
+            }
+        }
+        if let Some(id) = req.record_id.as_ref() {
+            wheres.push("r.id = ?");
+            values.push(id.clone().into());
+        }
+        if let Some(id) = req.meta_id.as_ref() {
+            wheres.push("r.meta_id = ?");
+            values.push(id.clone().into());
+        }
+        let cursor = req
+            .cursor
+            .as_deref()
+            .map(ChnotCursor::from_str)
+            .transpose()?;
+        if let Some(cursor) = cursor.as_ref() {
+            let time: Value = Timestamptz::from(cursor.insert_time).into();
+            let id: Value = cursor.id.clone().into();
+            match (req.sort, cursor.pin_time) {
+                (ChnotSort::Created, _) => {
+                    wheres.push("(m.insert_time < ? or (m.insert_time = ? and r.id < ?))");
+                    values.extend([time.clone(), time, id]);
+                }
+                (ChnotSort::Edited, _) => {
+                    wheres.push("(r.insert_time < ? or (r.insert_time = ? and r.id < ?))");
+                    values.extend([time.clone(), time, id]);
+                }
+                // the not pinned ones are after all pinned ones
+                (ChnotSort::PinnedFirst, Some(pin_time)) => {
+                    let pin_time: Value = Timestamptz::from(pin_time).into();
+                    wheres.push(
+                        "(m.pin_time is null or m.pin_time < ? or (m.pin_time = ? and \
+                         (r.insert_time < ? or (r.insert_time = ? and r.id < ?))))",
+                    );
+                    values.extend([pin_time.clone(), pin_time, time.clone(), time, id]);
+                }
+                (ChnotSort::PinnedFirst, None) => {
+                    wheres.push(
+                        "(m.pin_time is null and \
+                         (r.insert_time < ? or (r.insert_time = ? and r.id < ?)))",
+                    );
+                    values.extend([time.clone(), time, id]);
+                }
+            }
+        }
+        // sqlite puts nulls last for desc
+        let order_by = match req.sort {
+            ChnotSort::PinnedFirst => "m.pin_time DESC, r.insert_time DESC, r.id DESC",
+            ChnotSort::Created => "m.insert_time DESC, r.id DESC",
+            ChnotSort::Edited => "r.insert_time DESC, r.id DESC",
+        };
+
+        let chnot_sql = format!(
+            "SELECT r.id as rid, r.content, r.omit_time, r.insert_time as version_time, \
+             m.id as mid, m.namespace, m.kind, m.pin_time, m.archive_time, m.delete_time, m.update_time, m.insert_time as init_time \
+             FROM chnot_record r LEFT JOIN chnot_metadata m ON r.meta_id = m.id \
+             WHERE {} \
+             ORDER BY {} LIMIT ? OFFSET ?",
+            wheres.join(" and "),
+            order_by
+        );
+        values.push((req.page_size as i64).into());
+        let offset = if cursor.is_some() { 0 } else { req.start_index };
+        values.push((offset as i64).into());
+        info!("sql is {}", chnot_sql);
+
+        let cs: Vec<Chnot> = self
+            .query_rows(chnot_sql, values)
+            .await?
+            .into_iter()
+            .map(|row| row_to_chnot(&row))
+            .filter_map(|e: AResult<Chnot>| {
+                if e.is_err() {
+                    error!("unable to remap: {:?}", e.err());
+                    None
+                } else {
+                    e.ok()
+                }
+            })
+            .collect();
+
+        Ok(ChnotQueryRsp {
+            next_cursor: ChnotCursor::next_of(&cs, req.sort, req.page_size),
+            data: cs,
+            start_index: req.start_index,
+            highlights: vec![],
+        })
+    }
+
+    async fn chnot_update(&self, req: KReq<ChnotUpdateReq>) -> AResult<ChnotMetadata> {
+        let now = Timestamptz::from(Local::now().fixed_offset());
+
+        let mut sets: Vec<&str> = vec![];
+        let mut values: Vec<Value> = vec![];
+
+        if let Some(pinned) = req.pinned {
+            sets.push("pin_time = ?");
+            values.push(if pinned {
+                now.clone().into()
+            } else {
+                Value::Null
+            });
+        }
+        if let Some(archive) = req.archive {
+            sets.push("archive_time = ?");
+            values.push(if archive {
+                now.clone().into()
+            } else {
+                Value::Null
+            });
+        }
+        if req.update_time {
+            sets.push("update_time = ?");
+            values.push(now.into());
+        }
+
+        if !sets.is_empty() {
+            values.push(req.meta_id.clone().into());
+            values.push(req.namespace.clone().into());
+            let sql = format!(
+                "update chnot_metadata set {} where id = ? and namespace = ?",
+                sets.join(", ")
+            );
+            self.execute(&sql, values).await?;
+        }
+
+        let meta = self
+            .query_rows(
+                "select * from chnot_metadata where id = ?1 and namespace = ?2".to_owned(),
+                vec![req.meta_id.clone().into(), req.namespace.clone().into()],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| KError::NotFound(format!("unable to find chnot {}", req.meta_id)))?;
+        Self::to_chnot_meta(meta)
+    }
+
+    async fn chnot_move(
+        &self,
+        items: &[ChnotMoveItem],
+        target: &str,
+    ) -> AResult<Vec<ChnotMoveResult>> {
+        let items = items.to_vec();
+        let target = target.to_owned();
+        let now = Timestamptz::from(Local::now().fixed_offset());
+
+        self.interact(move |conn| {
+            let transaction = conn.transaction()?;
+
+            let mut results = Vec::with_capacity(items.len());
+            for item in items {
+                let moved = transaction.execute(
+                    "update chnot_metadata set namespace = ?1 where id = ?2 and namespace = ?3",
+                    params![&target, &item.meta_id, &item.namespace],
+                )?;
+                if moved == 0 {
+                    results.push(ChnotMoveResult {
+                        meta_id: item.meta_id,
+                        status: ChnotMoveStatus::NotFound,
+                        resources: vec![],
+                    });
+                    continue;
+                }
+
+                let mut resources = vec![];
+                for id in item.resource_ids {
+                    let moved = transaction.execute(
+                        "update resources set namespace = ?1 where id = ?2 and namespace = ?3",
+                        params![&target, &id, &item.namespace],
+                    )?;
+                    if moved > 0 {
+                        resources.push(id);
+                    }
+                }
+                transaction.execute(
+                    "insert into chnot_move(id, meta_id, from_namespace, to_namespace, insert_time) values(?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        uuid::Uuid::new_v4().to_string(),
+                        &item.meta_id,
+                        &item.namespace,
+                        &target,
+                        &now
+                    ],
+                )?;
+
+                results.push(ChnotMoveResult {
+                    meta_id: item.meta_id,
+                    status: ChnotMoveStatus::Moved,
+                    resources,
+                });
+            }
+
+            transaction.commit()?;
+            Ok(results)
+        })
+        .await
+    }
+
+    async fn chnot_history(&self, req: KReq<ChnotHistoryReq>) -> AResult<ChnotHistoryRsp> {
+        let meta = self
+            .query_rows(
+                "select * from chnot_metadata where id = ?1 and namespace = ?2".to_owned(),
+                vec![req.meta_id.clone().into(), req.namespace.clone().into()],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| KError::NotFound(format!("unable to find chnot {}", req.meta_id)))?;
+        let meta = Self::to_chnot_meta(meta)?;
+
+        let records = self
+            .query_rows(
+                "select * from chnot_record where meta_id = ?1 order by insert_time asc".to_owned(),
+                vec![req.meta_id.clone().into()],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_chnot_record)
+            .collect::<AResult<Vec<ChnotRecord>>>()?;
+
+        let moves = self
+            .query_rows(
+                "select * from chnot_move where meta_id = ?1 order by insert_time asc".to_owned(),
+                vec![req.meta_id.clone().into()],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_chnot_move)
+            .collect::<AResult<Vec<ChnotMove>>>()?;
+
+        Ok(ChnotHistoryRsp {
+            meta,
+            records,
+            moves,
+        })
+    }
+
+    async fn chnot_record_by_id(&self, namespace: &str, record_id: &str) -> AResult<ChnotRecord> {
+        let row = self
+            .query_rows(
+                "select r.* from chnot_record r join chnot_metadata m on r.meta_id = m.id where r.id = ?1 and m.namespace = ?2"
+                    .to_owned(),
+                vec![record_id.to_owned().into(), namespace.to_owned().into()],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| KError::NotFound(format!("unable to find chnot record {}", record_id)))?;
+
+        Self::to_chnot_record(row)
+    }
+
+    async fn chnot_namespaces(&self) -> AResult<Vec<String>> {
+        self.query_rows(
+            "select distinct namespace from chnot_metadata".to_owned(),
+            vec![],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.try_get("namespace"))
+        .collect()
+    }
+
+    async fn chnot_activity_days(
+        &self,
+        namespace: &str,
+        after: DateTime<FixedOffset>,
+        before: DateTime<FixedOffset>,
+        offset: FixedOffset,
+    ) -> AResult<Vec<DayActivity>> {
+        // The times are stored encoded, sqlite can not take their dates, so
+        // they are counted here. The caller bounds the range.
+        let values: Vec<Value> = vec![
+            namespace.to_owned().into(),
+            Timestamptz::from(after).into(),
+            Timestamptz::from(before).into(),
+        ];
+
+        let created: Vec<DateTime<FixedOffset>> = self
+            .query_rows(
+                "select insert_time from chnot_metadata \
+                 where namespace = ?1 and delete_time is null and insert_time >= ?2 and insert_time < ?3"
+                    .to_owned(),
+                values.clone(),
+            )
+            .await?
+            .iter()
+            .map(|row| row.try_get_time("insert_time"))
+            .collect::<AResult<_>>()?;
+        let edited: Vec<DateTime<FixedOffset>> = self
+            .query_rows(
+                "select r.insert_time from chnot_record r join chnot_metadata m on r.meta_id = m.id \
+                 where m.namespace = ?1 and m.delete_time is null and r.insert_time >= ?2 and r.insert_time < ?3"
+                    .to_owned(),
+                values,
+            )
+            .await?
+            .iter()
+            .map(|row| row.try_get_time("insert_time"))
+            .collect::<AResult<_>>()?;
+
+        Ok(count_by_day(&created, &edited, offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeDelta};
+
+    use super::escape_like;
+    use crate::{
+        mapper::{
+            migration::pending_migrations,
+            sqlite::{Sqlite, SqliteConfig},
+            versioning::VersionPolicy,
+            ChnotMapper, MigrationMapper,
+        },
+        model::{
+            db::chnot::{ChnotKind, ChnotRecord},
+            dto::{chnot::ChnotOverwriteReq, KReq},
+        },
+    };
+
+    #[tokio::test]
+    async fn merged_record_keeps_id() {
+        let filepath = std::env::temp_dir().join(format!("chnots-{}.sqlite", uuid::Uuid::new_v4()));
+        let db = Sqlite::new(SqliteConfig {
+            filepath: filepath.to_string_lossy().to_string(),
+        })
+        .unwrap();
+        db.ensure_table_schema_version().await.unwrap();
+        for migration in pending_migrations(0) {
+            db.apply_migration(migration).await.unwrap();
+        }
+
+        let now = Local::now().fixed_offset();
+        let overwrite = |id: &str, content: &str, seconds: i64| KReq {
+            body: ChnotOverwriteReq {
+                chnot: ChnotRecord {
+                    id: id.to_owned(),
+                    meta_id: "meta".to_owned(),
+                    content: content.to_owned(),
+                    omit_time: None,
+                    insert_time: now + TimeDelta::seconds(seconds),
+                },
+                kind: ChnotKind::MarkdownWithToent,
+                checkpoint: false,
+            },
+            namespace: "default".to_owned(),
+        };
+        let policy = VersionPolicy::default();
+
+        let first = db
+            .chnot_overwrite(overwrite("r1", "hello", 0), &policy)
+            .await
+            .unwrap();
+        assert_eq!(first.chnot.record.id, "r1");
+
+        // a small and recent edit is merged into the current record
+        let merged = db
+            .chnot_overwrite(overwrite("r2", "hello!", 1), &policy)
+            .await
+            .unwrap();
+        assert!(!merged.new_version);
+        assert_eq!(merged.chnot.record.id, "r1");
+
+        let ids: Vec<String> = db
+            .query_rows(
+                "select id from chnot_record where meta_id = 'meta'".to_owned(),
+                vec![],
+            )
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.try_get("id").unwrap())
+            .collect();
+        assert_eq!(ids, vec!["r1".to_owned()]);
+
+        let _ = std::fs::remove_file(filepath);
+    }
+
+    #[test]
+    fn like_wildcards_escaped() {
+        assert_eq!(escape_like(r"50%_off\"), r"50\%\_off\\");
+    }
+}
@@ -0,0 +1,54 @@
+use chin_tools::wrapper::anyhow::AResult;
+use chrono::Local;
+
+use crate::{
+    mapper::KVMapper,
+    model::dto::{
+        kv::{KVDeleteReq, KVDeleteRsp, KVOverwriteReq, KVOverwriteRsp, KVQueryReq, KVQueryRsp},
+        KReq,
+    },
+};
+
+use super::{sqltype::Timestamptz, DeserializeMapper, Sqlite};
+
+impl KVMapper for Sqlite {
+    async fn kv_overwrite(&self, req: KReq<KVOverwriteReq>) -> AResult<KVOverwriteRsp> {
+        self.execute(
+            "insert into kv(key, value, insert_time) values (?1, ?2, ?3) on conflict(key) do update set value = ?2, update_time = ?4",
+            vec![
+                req.kv.key.to_string().into(),
+                req.kv.value.to_string().into(),
+                Timestamptz::from(req.kv.insert_time).into(),
+                Timestamptz::from(Local::now().fixed_offset()).into(),
+            ],
+        )
+        .await?;
+
+        Ok(KVOverwriteRsp {})
+    }
+
+    async fn kv_query(&self, req: KReq<KVQueryReq>) -> AResult<KVQueryRsp> {
+        let kv = self
+            .query_rows(
+                "select * from kv where key = ?1".to_owned(),
+                vec![req.key.to_string().into()],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .map(Self::to_kv)
+            .transpose()?;
+
+        Ok(KVQueryRsp { kv })
+    }
+
+    async fn kv_delete(&self, req: KReq<KVDeleteReq>) -> AResult<KVDeleteRsp> {
+        self.execute(
+            "delete from kv where key = ?1",
+            vec![req.key.to_string().into()],
+        )
+        .await?;
+
+        Ok(KVDeleteRsp {})
+    }
+}
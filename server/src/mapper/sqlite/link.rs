@@ -0,0 +1,64 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use deadpool_sqlite::rusqlite::{params, types::Value};
+
+use crate::{mapper::ChnotLinkMapper, model::db::chnot::ChnotLink};
+
+use super::Sqlite;
+
+impl ChnotLinkMapper for Sqlite {
+    async fn chnot_link_overwrite(&self, meta_id: &str, targets: &[String]) -> EResult {
+        let meta_id = meta_id.to_owned();
+        let targets = targets.to_vec();
+
+        self.interact(move |conn| {
+            let transaction = conn.transaction()?;
+
+            transaction.execute(
+                "delete from chnot_link where meta_id = ?1",
+                params![&meta_id],
+            )?;
+            for target in targets.iter() {
+                transaction.execute(
+                    "insert into chnot_link(meta_id, target) values(?1, ?2)",
+                    params![&meta_id, target],
+                )?;
+            }
+
+            transaction.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn chnot_link_list(
+        &self,
+        namespace: &str,
+        targets: Option<&[String]>,
+    ) -> AResult<Vec<ChnotLink>> {
+        let mut sql = "select l.meta_id, l.target from chnot_link l join chnot_metadata m on l.meta_id = m.id \
+                       where m.namespace = ? and m.delete_time is null"
+            .to_owned();
+        let mut values: Vec<Value> = vec![namespace.to_owned().into()];
+        if let Some(targets) = targets {
+            if targets.is_empty() {
+                return Ok(vec![]);
+            }
+            sql.push_str(&format!(
+                " and l.target in ({})",
+                vec!["?"; targets.len()].join(", ")
+            ));
+            values.extend(targets.iter().map(|e| Value::from(e.clone())));
+        }
+
+        self.query_rows(sql, values)
+            .await?
+            .into_iter()
+            .map(|row| {
+                Ok(ChnotLink {
+                    meta_id: row.try_get("meta_id")?,
+                    target: row.try_get("target")?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,338 @@
+use chin_tools::{
+    utils::sort_util::sort_by_prev,
+    wrapper::anyhow::{AResult, EResult},
+};
+use chrono::Local;
+use deadpool_sqlite::rusqlite::types::Value;
+
+use crate::{
+    error::KError,
+    mapper::LLMChatMapper,
+    model::{
+        db::llmchat::{LLMChatBot, LLMChatRecord, LLMChatSession, LLMChatTemplate},
+        dto::{llmchat::*, KReq},
+    },
+};
+
+use super::{sqltype::Timestamptz, DeserializeMapper, Sqlite};
+
+fn now() -> Value {
+    Timestamptz::from(Local::now().fixed_offset()).into()
+}
+
+fn session_not_found(session_id: &str) -> anyhow::Error {
+    KError::NotFound(format!("unable to find llm chat session {}", session_id)).into()
+}
+
+impl Sqlite {
+    /// The session ids come from the body, only the namespace of the header
+    /// is checked against the acl.
+    async fn llm_chat_check_session(&self, namespace: &str, session_id: &str) -> EResult {
+        let rows = self
+            .query_rows(
+                "select id from llm_chat_session where id = ?1 and namespace = ?2".to_owned(),
+                vec![session_id.to_owned().into(), namespace.to_owned().into()],
+            )
+            .await?;
+        if rows.is_empty() {
+            return Err(session_not_found(session_id));
+        }
+
+        Ok(())
+    }
+}
+
+impl LLMChatMapper for Sqlite {
+    async fn llm_chat_overwrite_bot(
+        &self,
+        req: KReq<LLMChatOverwriteBotReq>,
+    ) -> AResult<LLMChatOverwriteBotRsp> {
+        self.execute(
+            "insert into llm_chat_bot(id, name, body, svg_logo, insert_time) values(?1, ?2, ?3, ?4, ?5) on CONFLICT (id) DO UPDATE SET name = ?2, body = ?3, svg_logo = ?4",
+            vec![
+                req.bot.id.clone().into(),
+                req.bot.name.clone().into(),
+                req.bot.body.clone().into(),
+                req.bot.svg_logo.clone().into(),
+                Timestamptz::from(req.bot.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(LLMChatOverwriteBotRsp {})
+    }
+
+    async fn llm_chat_overwrite_template(
+        &self,
+        req: KReq<LLMChatOverwriteTemplateReq>,
+    ) -> AResult<LLMChatOverwriteTemplateRsp> {
+        self.execute(
+            "insert into llm_chat_template(id, name, prompt, svg_logo, insert_time) values(?1, ?2, ?3, ?4, ?5) on CONFLICT (id) DO UPDATE SET update_time = ?6, name = ?2, prompt = ?3, svg_logo = ?4",
+            vec![
+                req.template.id.clone().into(),
+                req.template.name.clone().into(),
+                req.template.prompt.clone().into(),
+                req.template.svg_logo.clone().into(),
+                Timestamptz::from(req.template.insert_time).into(),
+                now(),
+            ],
+        )
+        .await?;
+
+        Ok(LLMChatOverwriteTemplateRsp {})
+    }
+
+    async fn llm_chat_insert_session(
+        &self,
+        req: KReq<LLMChatInsertSessionReq>,
+    ) -> AResult<LLMChatInsertSessionRsp> {
+        let title: String = req.session.title.chars().into_iter().take(300).collect();
+        self.execute(
+            "insert into llm_chat_session(id, bot_id, template_id, title, namespace, insert_time) values(?1, ?2, ?3, ?4, ?5, ?6)",
+            vec![
+                req.session.id.clone().into(),
+                req.session.bot_id.clone().into(),
+                req.session.template_id.clone().into(),
+                title.into(),
+                req.namespace.clone().into(),
+                Timestamptz::from(req.session.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(LLMChatInsertSessionRsp {})
+    }
+
+    async fn llm_chat_insert_record(
+        &self,
+        req: KReq<LLMChatInsertRecordReq>,
+    ) -> AResult<LLMChatInsertRecordRsp> {
+        self.llm_chat_check_session(&req.namespace, &req.record.session_id)
+            .await?;
+        self.execute(
+            "insert into llm_chat_record(id, session_id, pre_record_id, content, role, role_id, insert_time) values(?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            vec![
+                req.record.id.clone().into(),
+                req.record.session_id.clone().into(),
+                req.record.pre_record_id.clone().into(),
+                req.record.content.clone().into(),
+                req.record.role.clone().into(),
+                req.record.role_id.clone().into(),
+                Timestamptz::from(req.record.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(LLMChatInsertRecordRsp {})
+    }
+
+    async fn llm_chat_list_bots(&self, _req: KReq<LLMChatListBotReq>) -> AResult<LLMChatListBotRsp> {
+        let bots: AResult<Vec<LLMChatBot>> = self
+            .query_rows(
+                "select * from llm_chat_bot where delete_time is null order by insert_time desc"
+                    .to_owned(),
+                vec![],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_llmchat_bot)
+            .collect();
+
+        Ok(LLMChatListBotRsp { bots: bots? })
+    }
+
+    async fn llm_chat_list_templates(
+        &self,
+        _req: KReq<LLMChatListTemplateReq>,
+    ) -> AResult<LLMChatListTemplateRsp> {
+        let templates: AResult<Vec<LLMChatTemplate>> = self
+            .query_rows(
+                "select * from llm_chat_template where delete_time is null order by insert_time desc"
+                    .to_owned(),
+                vec![],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_llmchat_template)
+            .collect();
+
+        Ok(LLMChatListTemplateRsp {
+            templates: templates?,
+        })
+    }
+
+    async fn llm_chat_list_sessions(
+        &self,
+        req: KReq<LLMChatListSessionReq>,
+    ) -> AResult<LLMChatListSessionRsp> {
+        let sql = format!(
+            "select * from llm_chat_session where delete_time is null and namespace in ({}) order by insert_time desc",
+            vec!["?"; req.descendants.len() + 1].join(", ")
+        );
+        let mut values: Vec<Value> = vec![req.namespace.clone().into()];
+        values.extend(req.descendants.iter().map(|e| Value::from(e.clone())));
+
+        let sessions: AResult<Vec<LLMChatSession>> = self
+            .query_rows(sql, values)
+            .await?
+            .into_iter()
+            .map(Self::to_llmchat_session)
+            .collect();
+
+        Ok(LLMChatListSessionRsp {
+            sessions: sessions?,
+        })
+    }
+
+    async fn llm_chat_session_detail(
+        &self,
+        req: KReq<LLMChatSessionDetialReq>,
+    ) -> AResult<LLMChatSessionDetailRsp> {
+        self.llm_chat_check_session(&req.namespace, &req.session_id)
+            .await?;
+        let records: AResult<Vec<LLMChatRecord>> = self
+            .query_rows(
+                "select r.* from llm_chat_record r join llm_chat_session s on r.session_id = s.id where r.session_id = ?1 and s.namespace = ?2 and r.omit_time is null order by r.insert_time desc"
+                    .to_owned(),
+                vec![req.session_id.clone().into(), req.namespace.clone().into()],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_llmchat_record)
+            .collect();
+
+        Ok(LLMChatSessionDetailRsp { records: records? })
+    }
+
+    async fn llm_chat_delete_bot(
+        &self,
+        req: KReq<LLMChatDeleteBotReq>,
+    ) -> AResult<LLMChatDeleteBotRsp> {
+        self.execute(
+            "update llm_chat_bot set delete_time = ?1 where id = ?2",
+            vec![now(), req.bot_id.clone().into()],
+        )
+        .await?;
+
+        Ok(LLMChatDeleteBotRsp {})
+    }
+
+    async fn llm_chat_delete_template(
+        &self,
+        req: KReq<LLMChatDeleteTemplateReq>,
+    ) -> AResult<LLMChatDeleteTemplateRsp> {
+        self.execute(
+            "update llm_chat_template set delete_time = ?1 where id = ?2",
+            vec![now(), req.template_id.clone().into()],
+        )
+        .await?;
+
+        Ok(LLMChatDeleteTemplateRsp {})
+    }
+
+    async fn llm_chat_delete_session(
+        &self,
+        req: KReq<LLMChatDeleteSessionReq>,
+    ) -> AResult<LLMChatDeleteSessionRsp> {
+        let count = self
+            .execute(
+                "update llm_chat_session set delete_time = ?1 where id = ?2 and namespace = ?3",
+                vec![
+                    now(),
+                    req.session_id.clone().into(),
+                    req.namespace.clone().into(),
+                ],
+            )
+            .await?;
+        if count == 0 {
+            return Err(session_not_found(&req.session_id));
+        }
+
+        Ok(LLMChatDeleteSessionRsp {})
+    }
+
+    async fn llm_chat_update_session(
+        &self,
+        req: KReq<LLMChatUpdateSessionReq>,
+    ) -> AResult<LLMChatUpdateSessionRsp> {
+        let mut sets: Vec<&str> = vec![];
+        let mut values: Vec<Value> = vec![];
+
+        if let Some(title) = req.title.as_ref() {
+            sets.push("title = ?");
+            values.push(title.clone().into());
+        }
+        if let Some(delete) = req.delete {
+            sets.push("delete_time = ?");
+            values.push(if delete { now() } else { Value::Null });
+        }
+
+        if sets.is_empty() {
+            self.llm_chat_check_session(&req.namespace, &req.session_id)
+                .await?;
+        } else {
+            values.push(req.session_id.clone().into());
+            values.push(req.namespace.clone().into());
+            let sql = format!(
+                "update llm_chat_session set {} where id = ? and namespace = ?",
+                sets.join(", ")
+            );
+            if self.execute(&sql, values).await? == 0 {
+                return Err(session_not_found(&req.session_id));
+            }
+        }
+
+        Ok(LLMChatUpdateSessionRsp {})
+    }
+
+    async fn llm_chat_truncate_session(
+        &self,
+        req: KReq<LLMChatTruncateSessionReq>,
+    ) -> AResult<LLMChatTruncateSessionRsp> {
+        let mut records = self
+            .llm_chat_session_detail(KReq {
+                body: LLMChatSessionDetialReq {
+                    session_id: req.session_id.clone(),
+                },
+                namespace: req.namespace.clone(),
+            })
+            .await?
+            .records;
+
+        sort_by_prev(
+            &mut records,
+            false,
+            |r| &r.id,
+            |r| &r.pre_record_id,
+            |r| &r.insert_time,
+        );
+
+        let mut to_omit_ids = vec![];
+        let mut remove_flag = false;
+
+        for r in records {
+            if r.id == req.remove_rid_included {
+                remove_flag = true;
+            }
+            if remove_flag {
+                to_omit_ids.push(r.id);
+            }
+        }
+
+        if to_omit_ids.is_empty() {
+            return Ok(LLMChatTruncateSessionRsp {});
+        }
+
+        let placeholders = vec!["?"; to_omit_ids.len()].join(", ");
+        let mut values: Vec<Value> = vec![now()];
+        values.extend(to_omit_ids.into_iter().map(Value::from));
+
+        let sql = format!(
+            "update llm_chat_record set omit_time = ? where id in ({})",
+            placeholders
+        );
+        self.execute(&sql, values).await?;
+
+        Ok(LLMChatTruncateSessionRsp {})
+    }
+}
@@ -0,0 +1,63 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::Local;
+use deadpool_sqlite::rusqlite::params;
+
+use crate::mapper::{migration::Migration, MigrationMapper};
+
+use super::{sqltype::Timestamptz, Sqlite};
+
+impl MigrationMapper for Sqlite {
+    async fn ensure_table_schema_version(&self) -> EResult {
+        self.create_table(
+            "create table IF NOT EXISTS schema_version (
+    version INTEGER NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    insert_time INTEGER NOT NULL,
+    primary key (version)
+)",
+        )
+        .await
+    }
+
+    async fn read_schema_version(&self) -> AResult<i64> {
+        self.interact(|conn| {
+            let existed: i64 = conn.query_row(
+                "select count(*) from sqlite_master where type = 'table' and name = 'schema_version'",
+                [],
+                |row| row.get(0),
+            )?;
+            if existed == 0 {
+                return Ok(0);
+            }
+
+            let version: Option<i64> =
+                conn.query_row("select max(version) from schema_version", [], |row| {
+                    row.get(0)
+                })?;
+
+            Ok(version.unwrap_or(0))
+        })
+        .await
+    }
+
+    async fn apply_migration(&self, migration: &'static Migration) -> EResult {
+        self.interact(move |conn| {
+            let transaction = conn.transaction()?;
+
+            transaction.execute_batch(migration.sqlite)?;
+            transaction.execute(
+                "insert into schema_version(version, name, insert_time) values(?1, ?2, ?3)",
+                params![
+                    migration.version,
+                    migration.name,
+                    Timestamptz::from(Local::now().fixed_offset())
+                ],
+            )?;
+
+            transaction.commit()?;
+
+            Ok(())
+        })
+        .await
+    }
+}
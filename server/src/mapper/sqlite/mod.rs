@@ -1 +1,385 @@
-pub mod sqltype;
\ No newline at end of file
+pub mod acl;
+pub mod audit;
+pub mod backup;
+pub mod chnot;
+pub mod kv;
+pub mod link;
+pub mod llmchat;
+pub mod migration;
+pub mod namespace;
+pub mod resource;
+pub mod share;
+pub mod sqltype;
+pub mod tag;
+pub mod trash;
+pub mod user;
+
+use std::str::FromStr;
+
+use anyhow::Context;
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{DateTime, FixedOffset, Utc};
+use deadpool_sqlite::{
+    rusqlite::{
+        types::{FromSql, Value, ValueRef},
+        Connection, Row,
+    },
+    Pool, Runtime,
+};
+use serde::Deserialize;
+use sqltype::Timestamptz;
+
+use crate::model::db::{
+    acl::{AclSubjectKind, NamespaceAcl, NamespaceRole, UserGroup, UserGroupMember},
+    audit::{AuditAction, AuditEntityType, AuditLog},
+    chnot::*,
+    kv::KV,
+    llmchat::*,
+    namespace::*,
+    resource::Resource,
+    share::{ShareLink, ShareTargetKind},
+    user::{ApiToken, ApiTokenAccess, User},
+};
+
+use super::DeserializeMapper;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SqliteConfig {
+    filepath: String,
+}
+
+pub struct Sqlite {
+    pub pool: Pool,
+}
+
+impl Sqlite {
+    pub fn new(config: SqliteConfig) -> AResult<Sqlite> {
+        let pool = deadpool_sqlite::Config::new(config.filepath).create_pool(Runtime::Tokio1)?;
+
+        Ok(Sqlite { pool })
+    }
+
+    /// Run `f` on a pooled connection, sqlite calls are blocking so they are
+    /// executed on the blocking thread pool by deadpool.
+    async fn interact<F, R>(&self, f: F) -> AResult<R>
+    where
+        F: FnOnce(&mut Connection) -> AResult<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.pool
+            .get()
+            .await?
+            .interact(f)
+            .await
+            .map_err(|e| anyhow::anyhow!("unable to interact with sqlite: {}", e))?
+    }
+
+    async fn query_rows(&self, sql: String, values: Vec<Value>) -> AResult<Vec<SqliteRow>> {
+        self.interact(move |conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(
+                    deadpool_sqlite::rusqlite::params_from_iter(values),
+                    SqliteRow::from_row,
+                )?
+                .collect::<Result<Vec<SqliteRow>, _>>()?;
+            Ok(rows)
+        })
+        .await
+    }
+
+    async fn execute(&self, sql: &str, values: Vec<Value>) -> AResult<usize> {
+        let sql = sql.to_owned();
+        self.interact(move |conn| {
+            Ok(conn.execute(&sql, deadpool_sqlite::rusqlite::params_from_iter(values))?)
+        })
+        .await
+    }
+
+    async fn create_table(&self, create_sql: &'static str) -> EResult {
+        self.interact(move |conn| Ok(conn.execute_batch(create_sql)?))
+            .await
+    }
+}
+
+/// An owned copy of a sqlite row.
+///
+/// `rusqlite::Row` borrows its statement, which makes it unable to leave the
+/// `interact` closure, so the values are copied out and read by column name
+/// just like `tokio_postgres::Row`.
+pub struct SqliteRow {
+    columns: Vec<(String, Value)>,
+}
+
+impl SqliteRow {
+    fn from_row(row: &Row) -> deadpool_sqlite::rusqlite::Result<SqliteRow> {
+        let columns = row
+            .as_ref()
+            .column_names()
+            .into_iter()
+            .map(|e| e.to_owned())
+            .collect::<Vec<String>>();
+
+        let mut values = Vec::with_capacity(columns.len());
+        for (idx, name) in columns.into_iter().enumerate() {
+            values.push((name, Value::from(row.get_ref(idx)?)));
+        }
+
+        Ok(SqliteRow { columns: values })
+    }
+
+    pub fn try_get<T: FromSql>(&self, name: &str) -> AResult<T> {
+        let (_, value) = self
+            .columns
+            .iter()
+            .find(|(n, _)| n == name)
+            .context(format!("unable to find column {}", name))?;
+
+        T::column_result(ValueRef::from(value))
+            .map_err(|e| anyhow::anyhow!("unable to convert column {}: {}", name, e))
+    }
+
+    pub fn try_get_time(&self, name: &str) -> AResult<DateTime<FixedOffset>> {
+        Ok(self.try_get::<Timestamptz>(name)?.value)
+    }
+
+    pub fn try_get_time_opt(&self, name: &str) -> AResult<Option<DateTime<FixedOffset>>> {
+        Ok(self
+            .try_get::<Option<Timestamptz>>(name)?
+            .map(|e| e.value))
+    }
+
+    pub fn try_get_utc(&self, name: &str) -> AResult<DateTime<Utc>> {
+        Ok(self.try_get_time(name)?.to_utc())
+    }
+
+    pub fn try_get_utc_opt(&self, name: &str) -> AResult<Option<DateTime<Utc>>> {
+        Ok(self.try_get_time_opt(name)?.map(|e| e.to_utc()))
+    }
+}
+
+impl DeserializeMapper for Sqlite {
+    type RowType = SqliteRow;
+
+    fn to_chnot_meta(row: Self::RowType) -> AResult<ChnotMetadata> {
+        let chnot = ChnotMetadata {
+            id: row.try_get("id")?,
+            namespace: row.try_get("namespace")?,
+            kind: row.try_get("kind")?,
+            pin_time: row.try_get_time_opt("pin_time")?,
+            archive_time: row.try_get_time_opt("archive_time")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            update_time: row.try_get_time_opt("update_time")?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(chnot)
+    }
+
+    fn to_chnot_record(row: Self::RowType) -> AResult<ChnotRecord> {
+        let chnot = ChnotRecord {
+            id: row.try_get("id")?,
+            meta_id: row.try_get("meta_id")?,
+            content: row.try_get("content")?,
+            omit_time: row.try_get_time_opt("omit_time")?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(chnot)
+    }
+
+    fn to_chnot_move(row: Self::RowType) -> AResult<ChnotMove> {
+        let obj = ChnotMove {
+            id: row.try_get("id")?,
+            meta_id: row.try_get("meta_id")?,
+            from_namespace: row.try_get("from_namespace")?,
+            to_namespace: row.try_get("to_namespace")?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_llmchat_bot(row: Self::RowType) -> AResult<LLMChatBot> {
+        let obj = LLMChatBot {
+            id: row.try_get("id")?,
+            insert_time: row.try_get_time("insert_time")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            name: row.try_get("name")?,
+            body: row.try_get("body")?,
+            update_time: row.try_get_time_opt("update_time")?,
+            svg_logo: row.try_get("svg_logo")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_llmchat_template(row: Self::RowType) -> AResult<LLMChatTemplate> {
+        let obj = LLMChatTemplate {
+            id: row.try_get("id")?,
+            insert_time: row.try_get_time("insert_time")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            update_time: row.try_get_time_opt("update_time")?,
+            name: row.try_get("name")?,
+            prompt: row.try_get("prompt")?,
+            svg_logo: row.try_get("svg_logo")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_llmchat_session(row: Self::RowType) -> AResult<LLMChatSession> {
+        let obj = LLMChatSession {
+            id: row.try_get("id")?,
+            insert_time: row.try_get_time("insert_time")?,
+            bot_id: row.try_get("bot_id")?,
+            template_id: row.try_get("template_id")?,
+            title: row.try_get("title")?,
+            namespace: row.try_get("namespace")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            update_time: row.try_get_time_opt("update_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_llmchat_record(row: Self::RowType) -> AResult<LLMChatRecord> {
+        let obj = LLMChatRecord {
+            id: row.try_get("id")?,
+            insert_time: row.try_get_time("insert_time")?,
+            session_id: row.try_get("session_id")?,
+            pre_record_id: row.try_get("pre_record_id")?,
+            content: row.try_get("content")?,
+            role: row.try_get("role")?,
+            role_id: row.try_get("role_id")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_namespace_record(row: Self::RowType) -> AResult<NamespaceRecord> {
+        let obj = NamespaceRecord {
+            id: row.try_get("id")?,
+            insert_time: row.try_get_time("insert_time")?,
+            name: row.try_get("name")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            update_time: row.try_get_time_opt("update_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_namespace_relation(row: Self::RowType) -> AResult<NamespaceRelation> {
+        let obj = NamespaceRelation {
+            id: row.try_get("id")?,
+            insert_time: row.try_get_time("insert_time")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            update_time: row.try_get_time_opt("update_time")?,
+            sub_id: row.try_get("sub_id")?,
+            parent_id: row.try_get("parent_id")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_resource(row: Self::RowType) -> AResult<Resource> {
+        let obj = Resource {
+            id: row.try_get("id")?,
+            insert_time: row.try_get_utc("insert_time")?,
+            delete_time: row.try_get_utc_opt("delete_time")?,
+            namespace: row.try_get("namespace")?,
+            ori_filename: row.try_get("ori_filename")?,
+            content_type: row.try_get("content_type")?,
+            sha256: row.try_get("sha256")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_user(row: Self::RowType) -> AResult<User> {
+        let obj = User {
+            id: row.try_get("id")?,
+            username: row.try_get("username")?,
+            password_hash: row.try_get("password_hash")?,
+            admin: row.try_get("admin")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            update_time: row.try_get_time_opt("update_time")?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_api_token(row: Self::RowType) -> AResult<ApiToken> {
+        let obj = ApiToken {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            name: row.try_get("name")?,
+            token_hash: row.try_get("token_hash")?,
+            namespaces: serde_json::from_str(&row.try_get::<String>("namespaces")?)?,
+            access: ApiTokenAccess::from_str(&row.try_get::<String>("access")?)?,
+            last_used_time: row.try_get_time_opt("last_used_time")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_namespace_acl(row: Self::RowType) -> AResult<NamespaceAcl> {
+        let obj = NamespaceAcl {
+            id: row.try_get("id")?,
+            namespace: row.try_get("namespace")?,
+            subject_kind: AclSubjectKind::from_str(&row.try_get::<String>("subject_kind")?)?,
+            subject_id: row.try_get("subject_id")?,
+            role: NamespaceRole::from_str(&row.try_get::<String>("role")?)?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_user_group(row: Self::RowType) -> AResult<UserGroup> {
+        let obj = UserGroup {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_user_group_member(row: Self::RowType) -> AResult<UserGroupMember> {
+        let obj = UserGroupMember {
+            group_id: row.try_get("group_id")?,
+            user_id: row.try_get("user_id")?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_share_link(row: Self::RowType) -> AResult<ShareLink> {
+        let obj = ShareLink {
+            id: row.try_get("id")?,
+            namespace: row.try_get("namespace")?,
+            target_kind: ShareTargetKind::from_str(&row.try_get::<String>("target_kind")?)?,
+            target_id: row.try_get("target_id")?,
+            user_id: row.try_get("user_id")?,
+            expire_time: row.try_get_time("expire_time")?,
+            delete_time: row.try_get_time_opt("delete_time")?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_audit_log(row: Self::RowType) -> AResult<AuditLog> {
+        let obj = AuditLog {
+            id: row.try_get("id")?,
+            actor_id: row.try_get("actor_id")?,
+            actor_name: row.try_get("actor_name")?,
+            namespace: row.try_get("namespace")?,
+            entity_type: AuditEntityType::from_str(&row.try_get::<String>("entity_type")?)?,
+            entity_id: row.try_get("entity_id")?,
+            action: AuditAction::from_str(&row.try_get::<String>("action")?)?,
+            payload: serde_json::from_str(&row.try_get::<String>("payload")?)?,
+            insert_time: row.try_get_time("insert_time")?,
+        };
+        Ok(obj)
+    }
+
+    fn to_kv(row: Self::RowType) -> AResult<KV> {
+        let obj = KV {
+            insert_time: row.try_get_time("insert_time")?,
+            key: row.try_get::<String>("key")?.into(),
+            value: row.try_get::<String>("value")?.into(),
+            update_time: row.try_get_time_opt("update_time")?,
+        };
+        Ok(obj)
+    }
+}
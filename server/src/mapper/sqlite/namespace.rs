@@ -0,0 +1,141 @@
+use anyhow::Context;
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::Local;
+use deadpool_sqlite::rusqlite::params;
+
+use super::DeserializeMapper;
+use crate::{
+    error::KError,
+    mapper::NamespaceMapper,
+    model::db::namespace::{NamespaceRecord, NamespaceRelation},
+};
+
+use super::{sqltype::Timestamptz, Sqlite};
+
+impl NamespaceMapper for Sqlite {
+    async fn read_all_namespaces(&self) -> AResult<Vec<NamespaceRecord>> {
+        let nrs = self
+            .query_rows(
+                "select * from namespace_record where delete_time is null".to_owned(),
+                vec![],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_namespace_record)
+            .filter_map(|e: AResult<NamespaceRecord>| e.ok())
+            .collect();
+
+        Ok(nrs)
+    }
+
+    async fn read_all_namespace_relations(&self) -> AResult<Vec<NamespaceRelation>> {
+        let nrs = self
+            .query_rows(
+                "select * from namespace_relation where delete_time is null order by insert_time"
+                    .to_owned(),
+                vec![],
+            )
+            .await?
+            .into_iter()
+            .map(Self::to_namespace_relation)
+            .filter_map(|e: AResult<NamespaceRelation>| e.ok())
+            .collect();
+
+        Ok(nrs)
+    }
+
+    async fn namespace_insert(&self, record: &NamespaceRecord) -> AResult<bool> {
+        let count = self
+            .execute(
+                "insert into namespace_record(id, name, insert_time) values(?1, ?2, ?3) on conflict do nothing",
+                vec![
+                    record.id.clone().into(),
+                    record.name.clone().into(),
+                    Timestamptz::from(record.insert_time).into(),
+                ],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn namespace_rename(&self, id: &str, name: &str) -> AResult<NamespaceRecord> {
+        let row = self
+            .query_rows(
+                "update namespace_record set name = ?2, update_time = ?3 \
+                 where id = ?1 and delete_time is null returning *"
+                    .to_owned(),
+                vec![
+                    id.to_owned().into(),
+                    name.to_owned().into(),
+                    Timestamptz::from(Local::now().fixed_offset()).into(),
+                ],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| KError::NotFound(format!("unable to find namespace {}", id)))?;
+
+        Self::to_namespace_record(row)
+    }
+
+    async fn namespace_delete(&self, id: &str) -> EResult {
+        let id = id.to_owned();
+        let now = Timestamptz::from(Local::now().fixed_offset());
+
+        self.interact(move |conn| {
+            let transaction = conn.transaction()?;
+
+            transaction.execute(
+                "update namespace_relation set delete_time = ?2 \
+                 where (sub_id = ?1 or parent_id = ?1) and delete_time is null",
+                params![&id, &now],
+            )?;
+            transaction.execute(
+                "update namespace_record set delete_time = ?2 where id = ?1 and delete_time is null",
+                params![&id, &now],
+            )?;
+
+            transaction.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn namespace_set_parent(&self, id: &str, parent_id: Option<&str>) -> EResult {
+        let id = id.to_owned();
+        let parent_id = parent_id.map(|e| e.to_owned());
+        let now = Timestamptz::from(Local::now().fixed_offset());
+
+        self.interact(move |conn| {
+            let transaction = conn.transaction()?;
+
+            transaction.execute(
+                "update namespace_relation set delete_time = ?2 where sub_id = ?1 and delete_time is null",
+                params![&id, &now],
+            )?;
+            if let Some(parent_id) = parent_id {
+                transaction.execute(
+                    "insert into namespace_relation(id, sub_id, parent_id, insert_time) values(?1, ?2, ?3, ?4)",
+                    params![uuid::Uuid::new_v4().to_string(), &id, &parent_id, &now],
+                )?;
+            }
+
+            transaction.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn namespace_chnot_count(&self, id: &str) -> AResult<i64> {
+        self.query_rows(
+            "select count(*) as count from chnot_metadata where namespace = ?1".to_owned(),
+            vec![id.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .context("unable to count chnots")?
+        .try_get("count")
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::Context;
+use chin_tools::wrapper::anyhow::AResult;
+use deadpool_sqlite::rusqlite::types::Value;
+
+use super::DeserializeMapper;
+use crate::{
+    error::KError,
+    mapper::ResourceMapper,
+    model::{
+        db::resource::{InlineResource, Resource},
+        dto::{
+            InsertInlineResourceReq, InsertInlineResourceRsp, KReq, QueryInlineResourceReq,
+            QueryInlineResourceRsp, ResourceListReq, ResourceListRsp,
+        },
+    },
+};
+
+use super::{sqltype::Timestamptz, Sqlite};
+
+impl ResourceMapper for Sqlite {
+    async fn insert_resource(&self, res: &Resource) -> AResult<Resource> {
+        let insert_time = chrono::Utc::now().to_owned();
+
+        self.execute(
+            "insert into resources(id, namespace, ori_filename, content_type, sha256, insert_time) values (?1, ?2, ?3, ?4, ?5, ?6)",
+            vec![
+                res.id.clone().into(),
+                res.namespace.clone().into(),
+                res.ori_filename.clone().into(),
+                res.content_type.clone().into(),
+                res.sha256.clone().into(),
+                Timestamptz::from(insert_time.fixed_offset()).into(),
+            ],
+        )
+        .await?;
+
+        Ok(Resource {
+            insert_time,
+            delete_time: None,
+            ..res.clone()
+        })
+    }
+
+    async fn query_resource_by_id(&self, id: &str) -> AResult<Resource> {
+        let row = self
+            .query_rows(
+                "select * from resources where id = ?1".to_owned(),
+                vec![id.to_owned().into()],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| KError::NotFound(format!("unable to find resource {}", id)))?;
+
+        Self::to_resource(row)
+    }
+
+    async fn query_resources(&self, req: KReq<ResourceListReq>) -> AResult<ResourceListRsp> {
+        let sql = format!(
+            "select * from resources where delete_time is null and namespace in ({}) order by insert_time desc",
+            vec!["?"; req.descendants.len() + 1].join(", ")
+        );
+        let mut values: Vec<Value> = vec![req.namespace.clone().into()];
+        values.extend(req.descendants.iter().map(|e| Value::from(e.clone())));
+
+        let resources = self
+            .query_rows(sql, values)
+            .await?
+            .into_iter()
+            .map(Self::to_resource)
+            .collect::<AResult<Vec<Resource>>>()?;
+
+        Ok(ResourceListRsp { resources })
+    }
+
+    async fn delete_resource(&self, namespace: &str, id: &str) -> AResult<Resource> {
+        let row = self
+            .query_rows(
+                "delete from resources where id = ?1 and namespace = ?2 returning *".to_owned(),
+                vec![id.to_owned().into(), namespace.to_owned().into()],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| KError::NotFound(format!("unable to find resource {}", id)))?;
+
+        Self::to_resource(row)
+    }
+
+    async fn count_resources_by_sha256(&self, sha256: &str) -> AResult<i64> {
+        self.query_rows(
+            "select count(*) as count from resources where sha256 = ?1".to_owned(),
+            vec![sha256.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .context("unable to count resources")?
+        .try_get("count")
+    }
+
+    async fn insert_inline_resource(
+        &self,
+        req: &KReq<InsertInlineResourceReq>,
+    ) -> AResult<InsertInlineResourceRsp> {
+        self.execute(
+            "insert into inline_resource(id, name, content, content_type, insert_time) values (?1, ?2, ?3, ?4, ?5)",
+            vec![
+                req.res.id.clone().into(),
+                req.res.name.clone().into(),
+                req.res.content.to_string().into(),
+                req.res.content_type.clone().into(),
+                Timestamptz::from(req.res.insert_time.fixed_offset()).into(),
+            ],
+        )
+        .await?;
+
+        Ok(InsertInlineResourceRsp {})
+    }
+
+    async fn query_inline_resource(
+        &self,
+        req: KReq<QueryInlineResourceReq>,
+    ) -> AResult<QueryInlineResourceRsp> {
+        let mut wheres: Vec<&str> = vec!["delete_time is null"];
+        let mut values: Vec<Value> = vec![];
+
+        if let Some(content_type) = req.content_type.as_ref() {
+            wheres.push("content_type = ?");
+            values.push(content_type.to_string().into());
+        }
+        if let Some(id) = req.id.as_ref() {
+            wheres.push("id = ?");
+            values.push(id.to_string().into());
+        }
+        if let Some(name) = req.name_like.as_ref() {
+            wheres.push("name like ?");
+            values.push(format!("%{}%", name).into());
+        }
+
+        let query = format!(
+            "select * from inline_resource where {} order by insert_time desc",
+            wheres.join(" and ")
+        );
+
+        let res: AResult<Vec<InlineResource>> = self
+            .query_rows(query, values)
+            .await?
+            .into_iter()
+            .map(|t| {
+                let r = InlineResource {
+                    id: t.try_get("id")?,
+                    name: t.try_get("name")?,
+                    delete_time: t.try_get_utc_opt("delete_time")?,
+                    insert_time: t.try_get_utc("insert_time")?,
+                    content: t.try_get::<String>("content")?.into(),
+                    content_type: t.try_get("content_type")?,
+                };
+                Ok(r)
+            })
+            .collect();
+
+        Ok(QueryInlineResourceRsp { res: res? })
+    }
+}
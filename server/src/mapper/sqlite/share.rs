@@ -0,0 +1,71 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{DateTime, FixedOffset};
+
+use super::DeserializeMapper;
+use crate::{mapper::ShareLinkMapper, model::db::share::ShareLink};
+
+use super::{sqltype::Timestamptz, Sqlite};
+
+impl ShareLinkMapper for Sqlite {
+    async fn share_link_insert(&self, link: &ShareLink) -> EResult {
+        self.execute(
+            "insert into share_link(id, namespace, target_kind, target_id, user_id, expire_time, insert_time) values(?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            vec![
+                link.id.clone().into(),
+                link.namespace.clone().into(),
+                link.target_kind.to_string().into(),
+                link.target_id.clone().into(),
+                link.user_id.clone().into(),
+                Timestamptz::from(link.expire_time).into(),
+                Timestamptz::from(link.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn share_link_list(&self, namespace: &str) -> AResult<Vec<ShareLink>> {
+        self.query_rows(
+            "select * from share_link where namespace = ?1 and delete_time is null order by insert_time desc"
+                .to_owned(),
+            vec![namespace.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .map(Self::to_share_link)
+        .collect()
+    }
+
+    async fn share_link_by_id(&self, id: &str) -> AResult<Option<ShareLink>> {
+        self.query_rows(
+            "select * from share_link where id = ?1".to_owned(),
+            vec![id.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .map(Self::to_share_link)
+        .transpose()
+    }
+
+    async fn share_link_revoke(
+        &self,
+        namespace: &str,
+        id: &str,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<bool> {
+        let count = self
+            .execute(
+                "update share_link set delete_time = ?3 where id = ?1 and namespace = ?2 and delete_time is null",
+                vec![
+                    id.to_owned().into(),
+                    namespace.to_owned().into(),
+                    Timestamptz::from(time).into(),
+                ],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+}
@@ -4,6 +4,10 @@ use anyhow::Context;
 use chrono::{
     DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
 };
+use deadpool_sqlite::rusqlite::{
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Value, ValueRef},
+    ToSql,
+};
 
 // {yyyy,4}{oridinal,3}{sincemidnightseconds,5,0~86400}{millsesonds,3}{timezone,4,5000+-1400}
 #[derive(Clone)]
@@ -22,12 +26,8 @@ impl TryFrom<i64> for Timestamptz {
 
     fn try_from(value: i64) -> Result<Self, Self::Error> {
         let raw_tz = (value % 10000 - 5000) as i32;
-        let tz = if raw_tz > 0 {
-            FixedOffset::east_opt(raw_tz * 60)
-        } else {
-            FixedOffset::west_opt(raw_tz * 60)
-        };
-        let tz = tz.context(format!("unable to extract tz from {} -- {}", raw_tz, value))?;
+        let tz = FixedOffset::east_opt(raw_tz * 60)
+            .context(format!("unable to extract tz from {} -- {}", raw_tz, value))?;
 
         let raw_time = value / 10000;
         let mills = (raw_time % 1000) as i32;
@@ -89,6 +89,26 @@ impl DerefMut for Timestamptz {
     }
 }
 
+impl From<Timestamptz> for Value {
+    fn from(value: Timestamptz) -> Self {
+        Value::Integer(value.into())
+    }
+}
+
+impl ToSql for Timestamptz {
+    fn to_sql(&self) -> deadpool_sqlite::rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Owned(self.clone().into()))
+    }
+}
+
+impl FromSql for Timestamptz {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_i64()
+            .and_then(|v| Timestamptz::try_from(v).map_err(|e| FromSqlError::Other(e.into())))
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use anyhow::Context;
@@ -0,0 +1,59 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use deadpool_sqlite::rusqlite::params;
+
+use crate::{
+    mapper::ChnotTagMapper,
+    model::dto::{
+        chnot::{ChnotTagCount, ChnotTagListReq, ChnotTagListRsp},
+        KReq,
+    },
+};
+
+use super::Sqlite;
+
+impl ChnotTagMapper for Sqlite {
+    async fn chnot_tag_overwrite(&self, meta_id: &str, tags: &[String]) -> EResult {
+        let meta_id = meta_id.to_owned();
+        let tags = tags.to_vec();
+
+        self.interact(move |conn| {
+            let transaction = conn.transaction()?;
+
+            transaction.execute(
+                "delete from chnot_tag where meta_id = ?1",
+                params![&meta_id],
+            )?;
+            for tag in tags.iter() {
+                transaction.execute(
+                    "insert into chnot_tag(meta_id, tag) values(?1, ?2)",
+                    params![&meta_id, tag],
+                )?;
+            }
+
+            transaction.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn chnot_tag_list(&self, req: KReq<ChnotTagListReq>) -> AResult<ChnotTagListRsp> {
+        let tags = self
+            .query_rows(
+                "select t.tag, count(*) as count from chnot_tag t join chnot_metadata m on t.meta_id = m.id \
+                 where m.namespace = ?1 and m.delete_time is null group by t.tag order by count desc, t.tag"
+                    .to_owned(),
+                vec![req.namespace.clone().into()],
+            )
+            .await?
+            .into_iter()
+            .map(|row| {
+                Ok(ChnotTagCount {
+                    tag: row.try_get("tag")?,
+                    count: row.try_get("count")?,
+                })
+            })
+            .collect::<AResult<Vec<ChnotTagCount>>>()?;
+
+        Ok(ChnotTagListRsp { tags })
+    }
+}
@@ -0,0 +1,105 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{DateTime, FixedOffset};
+use deadpool_sqlite::rusqlite::params;
+
+use crate::{
+    error::KError,
+    mapper::{ChnotTrashMapper, DeserializeMapper},
+    model::{
+        db::chnot::ChnotMetadata,
+        dto::{
+            chnot::{Chnot, ChnotQueryRsp, ChnotTrashListReq, ChnotTrashRestoreReq},
+            KReq,
+        },
+    },
+};
+
+use super::{chnot::row_to_chnot, sqltype::Timestamptz, Sqlite};
+
+impl ChnotTrashMapper for Sqlite {
+    async fn chnot_trash_list(
+        &self,
+        req: KReq<ChnotTrashListReq>,
+    ) -> AResult<ChnotQueryRsp<Vec<Chnot>>> {
+        let data = self
+            .query_rows(
+                "SELECT r.id as rid, r.content, r.omit_time, r.insert_time as version_time, \
+                 m.id as mid, m.namespace, m.kind, m.pin_time, m.archive_time, m.delete_time, m.update_time, m.insert_time as init_time \
+                 FROM chnot_record r JOIN chnot_metadata m ON r.meta_id = m.id \
+                 WHERE m.namespace = ?1 AND m.delete_time is not null AND r.omit_time is null \
+                 ORDER BY m.delete_time DESC, m.id LIMIT ?2 OFFSET ?3"
+                    .to_owned(),
+                vec![
+                    req.namespace.clone().into(),
+                    (req.page_size as i64).into(),
+                    (req.start_index as i64).into(),
+                ],
+            )
+            .await?
+            .iter()
+            .map(row_to_chnot)
+            .collect::<AResult<_>>()?;
+
+        Ok(ChnotQueryRsp {
+            data,
+            start_index: req.start_index,
+            highlights: vec![],
+            next_cursor: None,
+        })
+    }
+
+    async fn chnot_trash_restore(&self, req: KReq<ChnotTrashRestoreReq>) -> AResult<ChnotMetadata> {
+        let meta = self
+            .query_rows(
+                "update chnot_metadata set delete_time = null \
+                 where id = ?1 and namespace = ?2 and delete_time is not null returning *"
+                    .to_owned(),
+                vec![req.chnot_id.clone().into(), req.namespace.clone().into()],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                KError::NotFound(format!("unable to find chnot {} in trash", req.chnot_id))
+            })?;
+
+        Self::to_chnot_meta(meta)
+    }
+
+    async fn chnot_trash_expired(
+        &self,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<Vec<ChnotMetadata>> {
+        self.query_rows(
+            "select * from chnot_metadata where delete_time < ?1".to_owned(),
+            vec![Timestamptz::from(time).into()],
+        )
+        .await?
+        .into_iter()
+        .map(Self::to_chnot_meta)
+        .collect()
+    }
+
+    async fn chnot_purge(&self, meta_ids: &[String]) -> EResult {
+        let meta_ids = meta_ids.to_vec();
+
+        self.interact(move |conn| {
+            let transaction = conn.transaction()?;
+
+            for meta_id in meta_ids.iter() {
+                for table in ["chnot_tag", "chnot_link", "chnot_move", "chnot_record"] {
+                    transaction.execute(
+                        &format!("delete from {} where meta_id = ?1", table),
+                        params![meta_id],
+                    )?;
+                }
+                transaction
+                    .execute("delete from chnot_metadata where id = ?1", params![meta_id])?;
+            }
+
+            transaction.commit()?;
+            Ok(())
+        })
+        .await
+    }
+}
@@ -0,0 +1,191 @@
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use chrono::{DateTime, FixedOffset};
+
+use super::DeserializeMapper;
+use crate::{
+    mapper::{ApiTokenMapper, UserMapper},
+    model::db::user::{ApiToken, User, UserSession},
+};
+
+use super::{sqltype::Timestamptz, Sqlite};
+
+impl UserMapper for Sqlite {
+    async fn user_insert(&self, user: &User) -> AResult<bool> {
+        let count = self
+            .execute(
+                "insert into user_account(id, username, password_hash, admin, insert_time) values(?1, ?2, ?3, ?4, ?5) on conflict do nothing",
+                vec![
+                    user.id.clone().into(),
+                    user.username.clone().into(),
+                    user.password_hash.clone().into(),
+                    user.admin.into(),
+                    Timestamptz::from(user.insert_time).into(),
+                ],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn user_by_name(&self, username: &str) -> AResult<Option<User>> {
+        self.query_rows(
+            "select * from user_account where username = ?1 and delete_time is null".to_owned(),
+            vec![username.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .map(Self::to_user)
+        .transpose()
+    }
+
+    async fn user_by_id(&self, id: &str) -> AResult<Option<User>> {
+        self.query_rows(
+            "select * from user_account where id = ?1 and delete_time is null".to_owned(),
+            vec![id.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .map(Self::to_user)
+        .transpose()
+    }
+
+    async fn user_list(&self) -> AResult<Vec<User>> {
+        self.query_rows(
+            "select * from user_account where delete_time is null order by username".to_owned(),
+            vec![],
+        )
+        .await?
+        .into_iter()
+        .map(Self::to_user)
+        .collect()
+    }
+
+    async fn user_session_insert(&self, session: &UserSession) -> EResult {
+        self.execute(
+            "insert into user_session(id, user_id, expire_time, insert_time) values(?1, ?2, ?3, ?4)",
+            vec![
+                session.id.clone().into(),
+                session.user_id.clone().into(),
+                Timestamptz::from(session.expire_time).into(),
+                Timestamptz::from(session.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn user_by_session(
+        &self,
+        session_id: &str,
+        now: DateTime<FixedOffset>,
+    ) -> AResult<Option<User>> {
+        self.query_rows(
+            "select u.* from user_account u join user_session s on s.user_id = u.id \
+             where s.id = ?1 and s.expire_time > ?2 and u.delete_time is null"
+                .to_owned(),
+            vec![session_id.to_owned().into(), Timestamptz::from(now).into()],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .map(Self::to_user)
+        .transpose()
+    }
+
+    async fn user_session_delete(&self, session_id: &str) -> EResult {
+        self.execute(
+            "delete from user_session where id = ?1",
+            vec![session_id.to_owned().into()],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn user_session_purge(&self, now: DateTime<FixedOffset>) -> EResult {
+        self.execute(
+            "delete from user_session where expire_time <= ?1",
+            vec![Timestamptz::from(now).into()],
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl ApiTokenMapper for Sqlite {
+    async fn api_token_insert(&self, token: &ApiToken) -> EResult {
+        self.execute(
+            "insert into api_token(id, user_id, name, token_hash, namespaces, access, insert_time) values(?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            vec![
+                token.id.clone().into(),
+                token.user_id.clone().into(),
+                token.name.clone().into(),
+                token.token_hash.clone().into(),
+                serde_json::to_string(&token.namespaces)?.into(),
+                token.access.to_string().into(),
+                Timestamptz::from(token.insert_time).into(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn api_token_list(&self, user_id: &str) -> AResult<Vec<ApiToken>> {
+        self.query_rows(
+            "select * from api_token where user_id = ?1 and delete_time is null order by insert_time desc"
+                .to_owned(),
+            vec![user_id.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .map(Self::to_api_token)
+        .collect()
+    }
+
+    async fn api_token_by_hash(&self, token_hash: &str) -> AResult<Option<ApiToken>> {
+        self.query_rows(
+            "select * from api_token where token_hash = ?1 and delete_time is null".to_owned(),
+            vec![token_hash.to_owned().into()],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .map(Self::to_api_token)
+        .transpose()
+    }
+
+    async fn api_token_touch(&self, id: &str, time: DateTime<FixedOffset>) -> EResult {
+        self.execute(
+            "update api_token set last_used_time = ?2 where id = ?1",
+            vec![id.to_owned().into(), Timestamptz::from(time).into()],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn api_token_revoke(
+        &self,
+        user_id: &str,
+        id: &str,
+        time: DateTime<FixedOffset>,
+    ) -> AResult<bool> {
+        let count = self
+            .execute(
+                "update api_token set delete_time = ?3 where id = ?1 and user_id = ?2 and delete_time is null",
+                vec![
+                    id.to_owned().into(),
+                    user_id.to_owned().into(),
+                    Timestamptz::from(time).into(),
+                ],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use serde::Deserialize;
+
+/// Decides whether `chnot_overwrite` overwrites the current record or starts
+/// a new version.
+///
+/// ```toml
+/// [versioning.default]
+/// strategy = "distance"
+/// metric = "sift4"
+/// threshold = 50
+/// window_secs = 3600
+///
+/// [versioning.namespaces.work]
+/// strategy = "always_new"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VersioningConfig {
+    #[serde(default)]
+    pub default: VersionPolicy,
+    #[serde(default)]
+    pub namespaces: HashMap<String, VersionPolicy>,
+}
+
+impl VersioningConfig {
+    pub fn policy_of(&self, namespace: &str) -> &VersionPolicy {
+        self.namespaces.get(namespace).unwrap_or(&self.default)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    #[default]
+    Sift4,
+    Sift4Common,
+    Levenshtein,
+    DamerauLevenshtein,
+}
+
+impl DistanceMetric {
+    pub fn distance(&self, s1: &str, s2: &str) -> usize {
+        match self {
+            DistanceMetric::Sift4 => textdistance::str::sift4_simple(s1, s2),
+            DistanceMetric::Sift4Common => textdistance::str::sift4_common(s1, s2),
+            DistanceMetric::Levenshtein => textdistance::str::levenshtein(s1, s2),
+            DistanceMetric::DamerauLevenshtein => {
+                textdistance::str::damerau_levenshtein(s1, s2)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum VersionPolicy {
+    /// Overwrite the current record while the edit is small and recent.
+    Distance {
+        #[serde(default)]
+        metric: DistanceMetric,
+        #[serde(default = "default_threshold")]
+        threshold: usize,
+        #[serde(default = "default_window_secs")]
+        window_secs: i64,
+    },
+    /// Every save is a new version.
+    AlwaysNew,
+    /// Only a save marked as checkpoint is a new version.
+    Checkpoint,
+}
+
+fn default_threshold() -> usize {
+    50
+}
+
+fn default_window_secs() -> i64 {
+    3600
+}
+
+impl Default for VersionPolicy {
+    fn default() -> Self {
+        VersionPolicy::Distance {
+            metric: DistanceMetric::default(),
+            threshold: default_threshold(),
+            window_secs: default_window_secs(),
+        }
+    }
+}
+
+impl VersionPolicy {
+    /// `old` is the content and insert time of the current record.
+    pub fn is_new_version(
+        &self,
+        old: Option<(&str, &DateTime<FixedOffset>)>,
+        content: &str,
+        insert_time: &DateTime<FixedOffset>,
+        checkpoint: bool,
+    ) -> bool {
+        let Some((old_content, old_insert_time)) = old else {
+            return true;
+        };
+
+        if checkpoint {
+            return true;
+        }
+
+        match self {
+            VersionPolicy::Distance {
+                metric,
+                threshold,
+                window_secs,
+            } => {
+                metric.distance(old_content, content) > *threshold
+                    || insert_time.signed_duration_since(old_insert_time)
+                        >= TimeDelta::seconds(*window_secs)
+            }
+            VersionPolicy::AlwaysNew => true,
+            VersionPolicy::Checkpoint => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset, TimeDelta};
+
+    use super::{DistanceMetric, VersionPolicy, VersioningConfig};
+
+    fn t(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    #[test]
+    fn default_policy() {
+        let p = VersionPolicy::default();
+        let old_time = t("2025-01-01T10:00:00+08:00");
+
+        assert!(p.is_new_version(None, "a", &old_time, false));
+        assert!(!p.is_new_version(
+            Some(("abc", &old_time)),
+            "abcd",
+            &(old_time + TimeDelta::minutes(10)),
+            false
+        ));
+        assert!(p.is_new_version(
+            Some(("abc", &old_time)),
+            "abcd",
+            &(old_time + TimeDelta::hours(2)),
+            false
+        ));
+        assert!(p.is_new_version(
+            Some(("abc", &old_time)),
+            &"x".repeat(100),
+            &(old_time + TimeDelta::minutes(10)),
+            false
+        ));
+        assert!(p.is_new_version(
+            Some(("abc", &old_time)),
+            "abcd",
+            &(old_time + TimeDelta::minutes(10)),
+            true
+        ));
+    }
+
+    #[test]
+    fn checkpoint_only() {
+        let old_time = t("2025-01-01T10:00:00+08:00");
+        let later = old_time + TimeDelta::days(3);
+        let p = VersionPolicy::Checkpoint;
+
+        assert!(!p.is_new_version(Some(("abc", &old_time)), "xyz", &later, false));
+        assert!(p.is_new_version(Some(("abc", &old_time)), "xyz", &later, true));
+        assert!(VersionPolicy::AlwaysNew.is_new_version(
+            Some(("abc", &old_time)),
+            "abc",
+            &old_time,
+            false
+        ));
+    }
+
+    #[test]
+    fn deserialize() {
+        let config: VersioningConfig = toml::from_str(
+            r#"
+            [default]
+            strategy = "distance"
+            metric = "levenshtein"
+            threshold = 10
+
+            [namespaces.work]
+            strategy = "always_new"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.policy_of("private"),
+            &VersionPolicy::Distance {
+                metric: DistanceMetric::Levenshtein,
+                threshold: 10,
+                window_secs: 3600,
+            }
+        );
+        assert_eq!(config.policy_of("work"), &VersionPolicy::AlwaysNew);
+    }
+}
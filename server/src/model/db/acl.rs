@@ -0,0 +1,58 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use strum_macros::EnumString;
+
+/// Each role can do everything the lower ones can.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, EnumString, Display,
+)]
+pub enum NamespaceRole {
+    /// Read the chnots.
+    #[strum(serialize = "viewer")]
+    #[serde(rename = "viewer")]
+    Viewer,
+    /// Write the chnots.
+    #[strum(serialize = "editor")]
+    #[serde(rename = "editor")]
+    Editor,
+    /// Manage the namespace and its acl.
+    #[strum(serialize = "owner")]
+    #[serde(rename = "owner")]
+    Owner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+pub enum AclSubjectKind {
+    #[strum(serialize = "user")]
+    #[serde(rename = "user")]
+    User,
+    #[strum(serialize = "group")]
+    #[serde(rename = "group")]
+    Group,
+}
+
+/// A role of a user or a group on the namespace and its descendants.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceAcl {
+    pub id: String,
+    pub namespace: String,
+    pub subject_kind: AclSubjectKind,
+    pub subject_id: String,
+    pub role: NamespaceRole,
+    pub insert_time: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserGroup {
+    pub id: String,
+    pub name: String,
+    pub insert_time: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserGroupMember {
+    pub group_id: String,
+    pub user_id: String,
+    pub insert_time: DateTime<FixedOffset>,
+}
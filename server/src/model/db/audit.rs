@@ -0,0 +1,116 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use strum_macros::EnumString;
+
+/// The actor of the changes the server makes on its own, like the purge of
+/// the trash.
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// The namespace of the changes outside of any namespace, users, groups and
+/// api tokens.
+pub const GLOBAL_NAMESPACE: &str = "";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+pub enum AuditEntityType {
+    #[strum(serialize = "chnot")]
+    #[serde(rename = "chnot")]
+    Chnot,
+    #[strum(serialize = "resource")]
+    #[serde(rename = "resource")]
+    Resource,
+    #[strum(serialize = "llmchat_bot")]
+    #[serde(rename = "llmchat_bot")]
+    LLMChatBot,
+    #[strum(serialize = "llmchat_template")]
+    #[serde(rename = "llmchat_template")]
+    LLMChatTemplate,
+    #[strum(serialize = "llmchat_session")]
+    #[serde(rename = "llmchat_session")]
+    LLMChatSession,
+    #[strum(serialize = "llmchat_record")]
+    #[serde(rename = "llmchat_record")]
+    LLMChatRecord,
+    #[strum(serialize = "kv")]
+    #[serde(rename = "kv")]
+    KV,
+    #[strum(serialize = "chnot_tag")]
+    #[serde(rename = "chnot_tag")]
+    ChnotTag,
+    #[strum(serialize = "namespace")]
+    #[serde(rename = "namespace")]
+    Namespace,
+    #[strum(serialize = "namespace_acl")]
+    #[serde(rename = "namespace_acl")]
+    NamespaceAcl,
+    #[strum(serialize = "user")]
+    #[serde(rename = "user")]
+    User,
+    #[strum(serialize = "user_group")]
+    #[serde(rename = "user_group")]
+    UserGroup,
+    #[strum(serialize = "user_group_member")]
+    #[serde(rename = "user_group_member")]
+    UserGroupMember,
+    #[strum(serialize = "api_token")]
+    #[serde(rename = "api_token")]
+    ApiToken,
+    #[strum(serialize = "share_link")]
+    #[serde(rename = "share_link")]
+    ShareLink,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+pub enum AuditAction {
+    /// Created or replaced.
+    #[strum(serialize = "overwrite")]
+    #[serde(rename = "overwrite")]
+    Overwrite,
+    #[strum(serialize = "insert")]
+    #[serde(rename = "insert")]
+    Insert,
+    #[strum(serialize = "update")]
+    #[serde(rename = "update")]
+    Update,
+    #[strum(serialize = "delete")]
+    #[serde(rename = "delete")]
+    Delete,
+    #[strum(serialize = "truncate")]
+    #[serde(rename = "truncate")]
+    Truncate,
+    #[strum(serialize = "upload")]
+    #[serde(rename = "upload")]
+    Upload,
+    /// Moved to another namespace.
+    #[strum(serialize = "move")]
+    #[serde(rename = "move")]
+    Move,
+    /// Restored from the trash or an older version.
+    #[strum(serialize = "restore")]
+    #[serde(rename = "restore")]
+    Restore,
+    #[strum(serialize = "rename")]
+    #[serde(rename = "rename")]
+    Rename,
+    /// Removed physically by the trash retention.
+    #[strum(serialize = "purge")]
+    #[serde(rename = "purge")]
+    Purge,
+    #[strum(serialize = "revoke")]
+    #[serde(rename = "revoke")]
+    Revoke,
+}
+
+/// A successful mutation of the api, `payload` is the json of its request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLog {
+    pub id: String,
+    pub actor_id: String,
+    pub actor_name: String,
+    pub namespace: String,
+    pub entity_type: AuditEntityType,
+    pub entity_id: String,
+    pub action: AuditAction,
+    pub payload: serde_json::Value,
+    pub insert_time: DateTime<FixedOffset>,
+}
@@ -24,6 +24,7 @@ pub struct ChnotMetadata {
     pub namespace: String,
     pub kind: String,
     pub pin_time: Option<DateTime<FixedOffset>>,
+    pub archive_time: Option<DateTime<FixedOffset>>,
     pub delete_time: Option<DateTime<FixedOffset>>,
     pub update_time: Option<DateTime<FixedOffset>>,
     pub insert_time: DateTime<FixedOffset>,
@@ -35,3 +36,20 @@ pub enum ChnotKind {
     #[serde(rename = "mdwt")]
     MarkdownWithToent,
 }
+
+/// A move of the chnot from one namespace to another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotMove {
+    pub id: String,
+    pub meta_id: String,
+    pub from_namespace: String,
+    pub to_namespace: String,
+    pub insert_time: DateTime<FixedOffset>,
+}
+
+/// A `[[target]]` in the current record of the chnot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotLink {
+    pub meta_id: String,
+    pub target: String,
+}
@@ -1,6 +1,10 @@
+pub mod acl;
+pub mod audit;
 pub mod chnot;
 pub mod kv;
 pub mod llmchat;
 pub mod namespace;
 pub mod resource;
+pub mod share;
 pub mod toent;
+pub mod user;
@@ -9,6 +9,9 @@ pub struct Resource {
     pub namespace: String,
     pub ori_filename: String,
     pub content_type: String,
+    /// Resources with the same sha256 share one file, none for the ones
+    /// uploaded before, which have a file of their own under the id.
+    pub sha256: Option<String>,
     pub delete_time: Option<DateTime<Utc>>,
     pub insert_time: DateTime<Utc>,
 }
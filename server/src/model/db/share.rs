@@ -0,0 +1,31 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use strum_macros::EnumString;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+pub enum ShareTargetKind {
+    /// A `ChnotMetadata.id`, the current record is shown.
+    #[strum(serialize = "chnot")]
+    #[serde(rename = "chnot")]
+    Chnot,
+    /// A `LLMChatSession.id`.
+    #[strum(serialize = "llmchat_session")]
+    #[serde(rename = "llmchat_session")]
+    LLMChatSession,
+}
+
+/// A public read-only link to a chnot or a llm chat session of the
+/// namespace, revoked by setting `delete_time`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLink {
+    pub id: String,
+    pub namespace: String,
+    pub target_kind: ShareTargetKind,
+    pub target_id: String,
+    /// The user who created the link.
+    pub user_id: String,
+    pub expire_time: DateTime<FixedOffset>,
+    pub delete_time: Option<DateTime<FixedOffset>>,
+    pub insert_time: DateTime<FixedOffset>,
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use strum_macros::EnumString;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct User {
+    pub id: String,
+    pub username: String,
+    /// The argon2 phc string, it never leaves the server.
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    pub admin: bool,
+    pub delete_time: Option<DateTime<FixedOffset>>,
+    pub update_time: Option<DateTime<FixedOffset>>,
+    pub insert_time: DateTime<FixedOffset>,
+}
+
+/// A login session, the id is the sha256 of the token given to the client.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UserSession {
+    pub id: String,
+    pub user_id: String,
+    pub expire_time: DateTime<FixedOffset>,
+    pub insert_time: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+pub enum ApiTokenAccess {
+    #[strum(serialize = "read")]
+    #[serde(rename = "read")]
+    Read,
+    #[strum(serialize = "write")]
+    #[serde(rename = "write")]
+    Write,
+}
+
+/// A long-lived token for scripts, it acts as its user within its scope.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    /// The token is limited to these namespaces, empty for every namespace.
+    pub namespaces: Vec<String>,
+    pub access: ApiTokenAccess,
+    pub last_used_time: Option<DateTime<FixedOffset>>,
+    pub delete_time: Option<DateTime<FixedOffset>>,
+    pub insert_time: DateTime<FixedOffset>,
+}
+
+impl ApiToken {
+    pub fn allows_namespace(&self, namespace: &str) -> bool {
+        self.namespaces.is_empty() || self.namespaces.iter().any(|e| e == namespace)
+    }
+}
@@ -0,0 +1,29 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::model::db::audit::{AuditAction, AuditEntityType, AuditLog};
+
+/// The logs of the namespace matching every given filter, the latest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogQueryReq {
+    pub actor_id: Option<String>,
+    pub entity_type: Option<AuditEntityType>,
+    pub entity_id: Option<String>,
+    pub action: Option<AuditAction>,
+    /// Inclusive.
+    pub after: Option<DateTime<FixedOffset>>,
+    /// Exclusive.
+    pub before: Option<DateTime<FixedOffset>>,
+    /// The logs of users, groups and api tokens instead, only for admins.
+    #[serde(default)]
+    pub global: bool,
+
+    pub start_index: u64,
+    pub page_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogQueryRsp {
+    pub logs: Vec<AuditLog>,
+    pub start_index: u64,
+}
@@ -1,9 +1,13 @@
 use std::ops::Deref;
 
 use crate::toent::PossibleToent;
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
-use crate::model::db::chnot::{ChnotKind, ChnotMetadata, ChnotRecord};
+use crate::{
+    model::db::chnot::{ChnotKind, ChnotMetadata, ChnotMove, ChnotRecord},
+    util::{activity::DayActivity, diff::DiffLine},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chnot {
@@ -11,25 +15,24 @@ pub struct Chnot {
     pub meta: ChnotMetadata,
 }
 
+/// Moves between namespaces go through `chnot_move`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChnotUpdateReq {
     pub meta_id: String,
 
-    pub namespace: Option<String>,
-
     pub update_time: bool,
 
     pub pinned: Option<bool>,
     pub archive: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ChnotUpdateRsp {}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChnotOverwriteReq {
     pub chnot: ChnotRecord,
     pub kind: ChnotKind,
+    /// Explicit checkpoint, always saved as a new version.
+    #[serde(default)]
+    pub checkpoint: bool,
 }
 
 impl Deref for ChnotOverwriteReq {
@@ -43,20 +46,88 @@ impl Deref for ChnotOverwriteReq {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChnotOverwriteRsp {
     pub chnot: Chnot,
+    /// Whether a new version was created or the current one was overwritten.
+    pub new_version: bool,
+}
+
+/// Move chnots with the resources they embed to `target_namespace`, the ones
+/// in `meta_ids` and the ones matched by `filter`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotMoveReq {
+    pub target_namespace: String,
+    #[serde(default)]
+    pub meta_ids: Vec<String>,
+    pub filter: Option<ChnotMoveFilter>,
+}
+
+/// The filter of `chnot_query` without paging.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotMoveFilter {
+    pub query: Option<String>,
+    #[serde(default)]
+    pub archived: ChnotArchiveFilter,
+    #[serde(default)]
+    pub include_descendants: bool,
+    #[serde(default, flatten)]
+    pub time_range: ChnotTimeRange,
+}
+
+/// A chnot to move, `namespace` is the one it is in.
+#[derive(Debug, Clone)]
+pub struct ChnotMoveItem {
+    pub meta_id: String,
+    pub namespace: String,
+    pub resource_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChnotMoveStatus {
+    Moved,
+    /// It is in the target namespace already.
+    Unchanged,
+    NotFound,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotMoveResult {
+    pub meta_id: String,
+    pub status: ChnotMoveStatus,
+    /// Ids of the moved resources.
+    pub resources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotMoveRsp {
+    pub results: Vec<ChnotMoveResult>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChnotDeletionReq {
     pub chnot_id: String,
-    /// logic or physical deletion
+    /// logic or physical deletion, a logic deleted chnot is moved to the
+    /// trash, a physical deleted one is removed with all its records.
     pub logic: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChnotDeletionRsp {}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotTrashListReq {
+    pub start_index: u64,
+    pub page_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotTrashRestoreReq {
+    pub chnot_id: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChnotQueryReq {
+    /// The syntax is described in `mapper::query::ChnotQuery`, the full text
+    /// search uses it as it is.
     pub query: Option<String>,
     pub meta_id: Option<String>,
     pub record_id: Option<String>,
@@ -64,15 +135,154 @@ pub struct ChnotQueryReq {
     pub with_deleted: Option<bool>,
     pub with_omitted: Option<bool>,
 
+    /// How `query` is matched.
+    #[serde(default)]
+    pub search_mode: ChnotSearchMode,
+
+    #[serde(default)]
+    pub archived: ChnotArchiveFilter,
+
+    /// Also return the chnots of the descendant namespaces.
+    #[serde(default)]
+    pub include_descendants: bool,
+    /// The descendant namespaces, filled by `AppState` from the namespace
+    /// tree if `include_descendants` is set.
+    #[serde(skip)]
+    pub descendants: Vec<String>,
+
+    #[serde(default, flatten)]
+    pub time_range: ChnotTimeRange,
+
+    #[serde(default)]
+    pub sort: ChnotSort,
+    /// The `next_cursor` of the previous page, `start_index` is ignored if
+    /// it is given.
+    #[serde(default)]
+    pub cursor: Option<String>,
+
+    // Paging
+    pub start_index: u64,
+    pub page_size: u64,
+}
+
+/// Search through the embedded search index, the results are ordered by score.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotSearchReq {
+    pub query: String,
+
     // Paging
     pub start_index: u64,
     pub page_size: u64,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChnotSearchMode {
+    /// Case-insensitive substring match, results are ordered by time.
+    #[default]
+    Substring,
+    /// Ranked full text search over the current records of the namespace,
+    /// the time range and the archive filter apply, `sort` and `cursor` do
+    /// not.
+    FullText,
+}
+
+/// Time bounds of `chnot_query`, an `after` bound is inclusive and a
+/// `before` bound is exclusive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChnotTimeRange {
+    /// The creation time of the chnot.
+    pub inserted_after: Option<DateTime<FixedOffset>>,
+    pub inserted_before: Option<DateTime<FixedOffset>>,
+    /// The last update time of the chnot.
+    pub updated_after: Option<DateTime<FixedOffset>>,
+    pub updated_before: Option<DateTime<FixedOffset>>,
+    /// The time of the record, a version of the chnot.
+    pub version_after: Option<DateTime<FixedOffset>>,
+    pub version_before: Option<DateTime<FixedOffset>>,
+}
+
+/// The order of `chnot_query`, always the latest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChnotSort {
+    /// Pinned chnots first, then by the last edit.
+    #[default]
+    PinnedFirst,
+    Created,
+    /// The time of the current record.
+    Edited,
+}
+
+/// Which chnots to return by their archive state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChnotArchiveFilter {
+    #[default]
+    Exclude,
+    Include,
+    Only,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChnotQueryRsp<T> {
     pub data: T,
     pub start_index: u64,
+    /// Only filled by the full text search.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<ChnotHighlight>,
+    /// Only filled by `chnot_query` when there may be a next page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotHighlight {
+    pub record_id: String,
+    pub rank: f32,
+    /// Matched fragments as html, the content is escaped and the hit words
+    /// are wrapped with `<mark>`.
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotHistoryReq {
+    pub meta_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotHistoryRsp {
+    pub meta: ChnotMetadata,
+    /// All versions of the chnot, the oldest first.
+    pub records: Vec<ChnotRecord>,
+    /// Moves between namespaces, the oldest first.
+    pub moves: Vec<ChnotMove>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotDiffReq {
+    pub from_record_id: String,
+    pub to_record_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotDiffRsp {
+    pub from: ChnotRecord,
+    pub to: ChnotRecord,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotRestoreReq {
+    /// Filled from the path.
+    #[serde(default)]
+    pub meta_id: String,
+    pub record_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotRestoreRsp {
+    pub chnot: Chnot,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -84,3 +294,81 @@ pub struct ToentGuessReq {
 pub struct ToentGuessRsp {
     pub toents: Vec<PossibleToent>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotTagListReq {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotTagCount {
+    pub tag: String,
+    /// Count of not deleted chnots with this tag.
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotTagListRsp {
+    pub tags: Vec<ChnotTagCount>,
+}
+
+/// Rename `from` and its nested tags to `to`, they are merged when `to` is in
+/// use already. Every changed chnot gets a new version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotTagRenameReq {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotTagRenameRsp {
+    /// Count of rewritten chnots.
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotBacklinksReq {
+    pub meta_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotBacklinksRsp {
+    /// Chnots linking to the chnot by its id or title.
+    pub chnots: Vec<Chnot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotGraphReq {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotGraphNode {
+    pub meta_id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ChnotGraphEdge {
+    pub source: String,
+    pub target: String,
+}
+
+/// Every not deleted chnot of the namespace and the links between them,
+/// links to nothing are left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotGraphRsp {
+    pub nodes: Vec<ChnotGraphNode>,
+    pub edges: Vec<ChnotGraphEdge>,
+}
+
+/// Per-day activity between `after` (inclusive) and `before` (exclusive),
+/// the last year by default. The days are split at `utc_offset_minutes`, the
+/// offset of the server by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotActivityReq {
+    pub after: Option<DateTime<FixedOffset>>,
+    pub before: Option<DateTime<FixedOffset>>,
+    pub utc_offset_minutes: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChnotActivityRsp {
+    pub days: Vec<DayActivity>,
+}
@@ -13,7 +13,7 @@ pub struct KVQueryRsp {
     pub kv: Option<KV>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KVOverwriteReq {
     pub kv: KV,
 }
@@ -22,7 +22,7 @@ pub struct KVOverwriteReq {
 pub struct KVOverwriteRsp {}
 
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KVDeleteReq {
     pub key: SharedStr
 }
@@ -17,6 +17,7 @@ pub struct LLMChatOverwriteTemplateReq {
 pub struct LLMChatOverwriteTemplateRsp {}
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMChatInsertSessionReq {
+    /// The namespace of the session is replaced by the one of the request.
     pub session: LLMChatSession,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,7 +41,14 @@ pub struct LLMChatListTemplateRsp {
     pub(crate) templates: Vec<LLMChatTemplate>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LLMChatListSessionReq {}
+pub struct LLMChatListSessionReq {
+    /// Also list the sessions of the descendant namespaces.
+    #[serde(default)]
+    pub include_descendants: bool,
+    /// Filled by `AppState` if `include_descendants` is set.
+    #[serde(skip)]
+    pub descendants: Vec<String>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMChatListSessionRsp {
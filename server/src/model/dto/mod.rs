@@ -1,13 +1,17 @@
+pub mod audit;
 pub mod chnot;
 pub mod kv;
 pub mod llmchat;
+pub mod namespace;
+pub mod share;
+pub mod user;
 
 /// DTO: Data Transfer Object
 ///
 /// All dtos should be put into this file.
 use std::{fmt::Debug, ops::Deref};
 
-use axum::{extract::Multipart, http::HeaderMap};
+use axum::extract::Multipart;
 
 use chin_tools::shared_str::SharedStr;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -22,20 +26,6 @@ pub struct KReq<E: Debug + Clone + DeserializeOwned> {
     pub namespace: String,
 }
 
-pub fn read_namespace_from_header(headers: &HeaderMap) -> String {
-    headers
-        .get("K-namespace")
-        .and_then(|v| v.to_str().ok().map(|e| e.to_string()))
-        .unwrap()
-}
-
-pub fn kreq<E: Debug + Clone + DeserializeOwned>(headers: HeaderMap, body: E) -> KReq<E> {
-    KReq {
-        body,
-        namespace: read_namespace_from_header(&headers),
-    }
-}
-
 impl<E: Debug + Clone + DeserializeOwned> Deref for KReq<E> {
     type Target = E;
 
@@ -57,6 +47,34 @@ pub struct ResourceUploadRsp {
     pub(crate) resources: Vec<Resource>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceListReq {
+    /// Also list the resources of the descendant namespaces.
+    #[serde(default)]
+    pub include_descendants: bool,
+    /// Filled by `AppState` if `include_descendants` is set.
+    #[serde(skip)]
+    pub descendants: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceListRsp {
+    /// Not deleted resources, the newest first.
+    pub resources: Vec<Resource>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceDeleteReq {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceDeleteRsp {
+    /// Whether the file was removed too, it is kept while other resources
+    /// share it.
+    pub file_removed: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InsertInlineResourceReq {
     pub res: InlineResource,
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    model::db::acl::{AclSubjectKind, NamespaceAcl, NamespaceRole},
+    util::namespace::NamespaceNode,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceTreeRsp {
+    pub roots: Vec<NamespaceNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceCreateReq {
+    /// The id is what chnots and the `K-namespace` header refer to, it is
+    /// the name by default and can not be changed later.
+    pub id: Option<String>,
+    pub name: String,
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceRenameReq {
+    pub id: String,
+    pub name: String,
+}
+
+/// Only an empty namespace without children can be deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceDeleteReq {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceMoveReq {
+    pub id: String,
+    /// The namespace becomes a root without a parent.
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceDeleteRsp {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceAclListReq {
+    pub namespace: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceAclListRsp {
+    pub acls: Vec<NamespaceAcl>,
+}
+
+/// Grant the role, it replaces the former role of the subject.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceAclOverwriteReq {
+    pub namespace: String,
+    pub subject_kind: AclSubjectKind,
+    pub subject_id: String,
+    pub role: NamespaceRole,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceAclDeleteReq {
+    pub namespace: String,
+    pub subject_kind: AclSubjectKind,
+    pub subject_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceAclDeleteRsp {}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::db::share::{ShareLink, ShareTargetKind};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkCreateReq {
+    pub target_kind: ShareTargetKind,
+    pub target_id: String,
+    /// `share.default_ttl_hours` of the config if missing.
+    pub ttl_hours: Option<u32>,
+}
+
+/// The page of the link is at `path`, relative to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkCreateRsp {
+    pub token: String,
+    pub path: String,
+    pub share_link: ShareLink,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkListRsp {
+    pub share_links: Vec<ShareLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkRevokeReq {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkRevokeRsp {}
+
+/// What `/s/{token}` shows, a chnot has a single record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareContent {
+    pub title: String,
+    pub records: Vec<ShareRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareRecord {
+    /// The role of a llm chat record, like `user` or `assistant`.
+    pub role: Option<String>,
+    /// Markdown.
+    pub content: String,
+}
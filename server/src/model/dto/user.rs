@@ -0,0 +1,87 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::model::db::{
+    acl::{UserGroup, UserGroupMember},
+    user::{ApiToken, ApiTokenAccess, User},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserLoginReq {
+    pub username: String,
+    pub password: String,
+}
+
+/// The token is sent back in the `Authorization: Bearer` header, the
+/// browser gets it as a cookie too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserLoginRsp {
+    pub token: String,
+    pub expire_time: DateTime<FixedOffset>,
+    pub user: User,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserLogoutRsp {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenCreateReq {
+    pub name: String,
+    /// Empty for every namespace.
+    #[serde(default)]
+    pub namespaces: Vec<String>,
+    pub access: ApiTokenAccess,
+}
+
+/// The token is only shown here, the server keeps its hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenCreateRsp {
+    pub token: String,
+    pub api_token: ApiToken,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenListRsp {
+    pub api_tokens: Vec<ApiToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenRevokeReq {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenRevokeRsp {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserCreateReq {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub admin: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserListRsp {
+    pub users: Vec<User>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserGroupCreateReq {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserGroupListRsp {
+    pub groups: Vec<UserGroup>,
+    pub members: Vec<UserGroupMember>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserGroupMemberReq {
+    pub group_id: String,
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserGroupMemberRsp {}
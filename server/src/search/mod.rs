@@ -0,0 +1,231 @@
+pub mod tokenizer;
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use chin_tools::wrapper::anyhow::{AResult, EResult};
+use serde::Deserialize;
+use tantivy::{
+    collector::TopDocs,
+    directory::MmapDirectory,
+    query::{BooleanQuery, Occur, Query, QueryParser, TermQuery},
+    schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, STORED, STRING,
+    },
+    snippet::SnippetGenerator,
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term,
+};
+use tokenizer::{CjkTokenizer, CJK_TOKENIZER};
+
+use crate::model::dto::chnot::Chnot;
+
+/// `start_index + page_size` of a search, the collector holds every hit up
+/// to it in memory.
+pub const MAX_SEARCH_HITS: u64 = 10_000;
+
+/// ```toml
+/// [search]
+/// index_dir = "/home/chin/chnots-dev/search"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchConfig {
+    pub index_dir: String,
+    #[serde(default = "default_writer_memory_mb")]
+    pub writer_memory_mb: usize,
+}
+
+fn default_writer_memory_mb() -> usize {
+    50
+}
+
+#[derive(Clone, Copy)]
+struct SearchFields {
+    meta_id: Field,
+    record_id: Field,
+    namespace: Field,
+    content: Field,
+}
+
+/// One matched chnot, `snippet` wraps the hit words with `<mark>`.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub meta_id: String,
+    pub record_id: String,
+    pub score: f32,
+    pub snippet: String,
+}
+
+/// An embedded full text index of the current record of every chnot.
+///
+/// The database is still the source of truth, the index only keeps what is
+/// needed to find a chnot and can be rebuilt from the database at any time.
+#[derive(Clone)]
+pub struct SearchIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Arc<Mutex<IndexWriter>>,
+    fields: SearchFields,
+}
+
+impl SearchIndex {
+    pub fn open(config: &SearchConfig) -> AResult<SearchIndex> {
+        let mut builder = Schema::builder();
+        let content_options = TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(CJK_TOKENIZER)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            )
+            .set_stored();
+        let fields = SearchFields {
+            meta_id: builder.add_text_field("meta_id", STRING | STORED),
+            record_id: builder.add_text_field("record_id", STRING | STORED),
+            namespace: builder.add_text_field("namespace", STRING),
+            content: builder.add_text_field("content", content_options),
+        };
+
+        std::fs::create_dir_all(&config.index_dir)
+            .context(format!("unable to create index dir {}", config.index_dir))?;
+        let index = Index::open_or_create(MmapDirectory::open(&config.index_dir)?, builder.build())?;
+        index
+            .tokenizers()
+            .register(CJK_TOKENIZER, CjkTokenizer::default());
+
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let writer = index.writer(config.writer_memory_mb * 1024 * 1024)?;
+
+        Ok(SearchIndex {
+            index,
+            reader,
+            writer: Arc::new(Mutex::new(writer)),
+            fields,
+        })
+    }
+
+    fn to_document(&self, chnot: &Chnot) -> TantivyDocument {
+        let mut doc = TantivyDocument::default();
+        doc.add_text(self.fields.meta_id, &chnot.meta.id);
+        doc.add_text(self.fields.record_id, &chnot.record.id);
+        doc.add_text(self.fields.namespace, &chnot.meta.namespace);
+        doc.add_text(self.fields.content, &chnot.record.content);
+        doc
+    }
+
+    /// Run `f` with the writer on the blocking pool, then commit and reload
+    /// the reader so the change is visible to the next search.
+    async fn write<F>(&self, f: F) -> EResult
+    where
+        F: FnOnce(&SearchIndex, &mut IndexWriter) -> EResult + Send + 'static,
+    {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut writer = this
+                .writer
+                .lock()
+                .map_err(|e| anyhow::anyhow!("search index writer is poisoned: {}", e))?;
+            f(&this, &mut writer)?;
+            writer.commit()?;
+            this.reader.reload()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Index the current record of the chnot, the old one is replaced.
+    pub async fn upsert(&self, chnot: &Chnot) -> EResult {
+        let chnot = chnot.clone();
+        self.write(move |this, writer| {
+            writer.delete_term(Term::from_field_text(this.fields.meta_id, &chnot.meta.id));
+            writer.add_document(this.to_document(&chnot))?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn delete(&self, meta_id: &str) -> EResult {
+        let meta_id = meta_id.to_owned();
+        self.write(move |this, writer| {
+            writer.delete_term(Term::from_field_text(this.fields.meta_id, &meta_id));
+            Ok(())
+        })
+        .await
+    }
+
+    /// Drop every document and index `chnots` instead.
+    pub async fn rebuild(&self, chnots: Vec<Chnot>) -> EResult {
+        self.write(move |this, writer| {
+            writer.delete_all_documents()?;
+            for chnot in chnots.iter() {
+                writer.add_document(this.to_document(chnot))?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Search `query` in the namespace, the query uses tantivy's syntax, the
+    /// invalid parts are ignored.
+    pub async fn search(
+        &self,
+        namespace: &str,
+        query: &str,
+        start_index: usize,
+        page_size: usize,
+    ) -> AResult<Vec<SearchHit>> {
+        // TopDocs asserts a limit of at least 1
+        if page_size == 0 {
+            return Ok(vec![]);
+        }
+
+        let this = self.clone();
+        let namespace = namespace.to_owned();
+        let query = query.to_owned();
+
+        tokio::task::spawn_blocking(move || {
+            let parser = QueryParser::for_index(&this.index, vec![this.fields.content]);
+            let (content_query, _) = parser.parse_query_lenient(&query);
+            let namespace_query = TermQuery::new(
+                Term::from_field_text(this.fields.namespace, &namespace),
+                IndexRecordOption::Basic,
+            );
+            let query = BooleanQuery::new(vec![
+                (Occur::Must, Box::new(namespace_query) as Box<dyn Query>),
+                (Occur::Must, content_query.box_clone()),
+            ]);
+
+            let searcher = this.reader.searcher();
+            let top_docs = searcher.search(
+                &query,
+                &TopDocs::with_limit(page_size).and_offset(start_index),
+            )?;
+            let snippet_generator =
+                SnippetGenerator::create(&searcher, &*content_query, this.fields.content)?;
+
+            let mut hits = Vec::with_capacity(top_docs.len());
+            for (score, address) in top_docs {
+                let doc: TantivyDocument = searcher.doc(address)?;
+                let text_of = |field: Field| {
+                    doc.get_first(field)
+                        .and_then(|e| e.as_str())
+                        .map(|e| e.to_owned())
+                        .unwrap_or_default()
+                };
+                let mut snippet = snippet_generator.snippet_from_doc(&doc);
+                snippet.set_snippet_prefix_postfix("<mark>", "</mark>");
+
+                hits.push(SearchHit {
+                    meta_id: text_of(this.fields.meta_id),
+                    record_id: text_of(this.fields.record_id),
+                    score,
+                    snippet: snippet.to_html(),
+                });
+            }
+
+            Ok(hits)
+        })
+        .await?
+    }
+}
@@ -0,0 +1,166 @@
+use tantivy::tokenizer::{Token, TokenStream, Tokenizer};
+
+pub const CJK_TOKENIZER: &str = "chnot_cjk";
+
+/// Splits latin text into lowercased words and CJK text into unigrams plus
+/// bigrams.
+///
+/// A unigram and the bigram starting from it share one position, so a
+/// phrase query made by this tokenizer matches both a single character and a
+/// longer CJK word without a dictionary.
+#[derive(Clone, Default)]
+pub struct CjkTokenizer {
+    tokens: Vec<Token>,
+}
+
+pub struct CjkTokenStream<'a> {
+    tokens: &'a mut [Token],
+    index: usize,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{2E80}'..='\u{2FDF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{3100}'..='\u{312F}'
+        | '\u{3190}'..='\u{31FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}')
+}
+
+fn tokenize(text: &str, tokens: &mut Vec<Token>) {
+    let mut position = 0;
+    let mut push = |tokens: &mut Vec<Token>, from: usize, to: usize, text: String, next: bool| {
+        tokens.push(Token {
+            offset_from: from,
+            offset_to: to,
+            position,
+            text,
+            position_length: 1,
+        });
+        if next {
+            position += 1;
+        }
+    };
+
+    let mut chars = text.char_indices().peekable();
+    while let Some((from, c)) = chars.next() {
+        if is_cjk(c) {
+            let to = from + c.len_utf8();
+            match chars.peek() {
+                Some(&(_, n)) if is_cjk(n) => {
+                    push(tokens, from, to, c.to_string(), false);
+                    push(tokens, from, to + n.len_utf8(), format!("{}{}", c, n), true);
+                }
+                _ => push(tokens, from, to, c.to_string(), true),
+            }
+        } else if c.is_alphanumeric() {
+            let mut to = from + c.len_utf8();
+            while let Some(&(i, n)) = chars.peek() {
+                if !n.is_alphanumeric() || is_cjk(n) {
+                    break;
+                }
+                to = i + n.len_utf8();
+                chars.next();
+            }
+            push(tokens, from, to, text[from..to].to_lowercase(), true);
+        }
+    }
+}
+
+impl Tokenizer for CjkTokenizer {
+    type TokenStream<'a> = CjkTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        self.tokens.clear();
+        tokenize(text, &mut self.tokens);
+        CjkTokenStream {
+            tokens: &mut self.tokens,
+            index: 0,
+        }
+    }
+}
+
+impl TokenStream for CjkTokenStream<'_> {
+    fn advance(&mut self) -> bool {
+        self.index += 1;
+        self.index <= self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::tokenizer::{TokenStream, Tokenizer};
+
+    use super::CjkTokenizer;
+
+    fn tokens(text: &str) -> Vec<(String, usize)> {
+        let mut tokenizer = CjkTokenizer::default();
+        let mut stream = tokenizer.token_stream(text);
+        let mut result = vec![];
+        while stream.advance() {
+            let token = stream.token();
+            assert_eq!(
+                text[token.offset_from..token.offset_to].to_lowercase(),
+                token.text
+            );
+            result.push((token.text.clone(), token.position));
+        }
+        result
+    }
+
+    #[test]
+    fn latin() {
+        assert_eq!(
+            tokens("Hello, World-42"),
+            vec![
+                ("hello".to_owned(), 0),
+                ("world".to_owned(), 1),
+                ("42".to_owned(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn cjk() {
+        assert_eq!(
+            tokens("农历闰月"),
+            vec![
+                ("农".to_owned(), 0),
+                ("农历".to_owned(), 0),
+                ("历".to_owned(), 1),
+                ("历闰".to_owned(), 1),
+                ("闰".to_owned(), 2),
+                ("闰月".to_owned(), 2),
+                ("月".to_owned(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn mixed() {
+        assert_eq!(
+            tokens("用Rust写笔记"),
+            vec![
+                ("用".to_owned(), 0),
+                ("rust".to_owned(), 1),
+                ("写".to_owned(), 2),
+                ("写笔".to_owned(), 2),
+                ("笔".to_owned(), 3),
+                ("笔记".to_owned(), 3),
+                ("记".to_owned(), 4),
+            ]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use axum::{
+    extract::{FromRequestParts, MatchedPath, Request, State},
+    http::{
+        header::{AUTHORIZATION, COOKIE},
+        request::Parts,
+        HeaderMap,
+    },
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    app::ShareAppState,
+    error::KError,
+    model::db::user::{ApiToken, ApiTokenAccess, User},
+    server::controller::extract::header_namespace,
+    util::auth::{is_cross_namespace_route, is_read_route, API_TOKEN_PREFIX},
+};
+
+/// Browsers can not set the `Authorization` header on `<img>` and links, so
+/// the login sets the token in this cookie as well.
+pub const SESSION_COOKIE: &str = "k_session";
+
+/// The user of the request, set by `require_user`.
+#[derive(Debug, Clone)]
+pub struct KUser {
+    pub user: User,
+    /// The api token of the request, none for a login session.
+    pub api_token: Option<ApiToken>,
+}
+
+impl KUser {
+    /// Managing api tokens needs a login session, a token must not be able
+    /// to mint wider ones.
+    pub fn session_user(self) -> Result<User, KError> {
+        match self.api_token {
+            None => Ok(self.user),
+            Some(_) => Err(KError::Forbidden(
+                "api tokens are not allowed here, login first".to_owned(),
+            )),
+        }
+    }
+
+    /// Managing users and groups needs an admin login session.
+    pub fn admin_user(self) -> Result<User, KError> {
+        let user = self.session_user()?;
+        if !user.admin {
+            return Err(KError::Forbidden(format!(
+                "the user {} is not an admin",
+                user.username
+            )));
+        }
+        Ok(user)
+    }
+
+    /// A scoped api token only reaches the namespaces of its scope.
+    /// `require_user` checks the header namespace, the other namespaces a
+    /// request reads, like `ns:` or the descendants, are checked by this.
+    pub fn require_token_scope<'a>(
+        &self,
+        namespaces: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), KError> {
+        let Some(api_token) = self.api_token.as_ref() else {
+            return Ok(());
+        };
+        for namespace in namespaces {
+            if !api_token.allows_namespace(namespace) {
+                return Err(KError::Forbidden(format!(
+                    "the api token {} has no access to namespace {}",
+                    api_token.name, namespace
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromRequestParts<ShareAppState> for KUser {
+    type Rejection = KError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &ShareAppState,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<KUser>()
+            .cloned()
+            .ok_or_else(|| KError::Unauthorized("login required".to_owned()))
+    }
+}
+
+/// The session token from `Authorization: Bearer <token>`, or from the
+/// session cookie.
+pub fn session_token(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|e| e.to_str().ok())
+        .and_then(|e| e.strip_prefix("Bearer "))
+        .map(|e| e.trim());
+    if bearer.is_some() {
+        return bearer;
+    }
+
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|e| e.to_str().ok())
+        .flat_map(|e| e.split(';'))
+        .filter_map(|e| e.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value)
+}
+
+/// An api token only reaches the namespaces of its scope, a read-only one
+/// only the read routes.
+fn check_api_token(
+    api_token: &ApiToken,
+    req: &Request,
+    state: &ShareAppState,
+) -> Result<(), KError> {
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(req.uri().path(), |e| e.as_str());
+
+    if api_token.access == ApiTokenAccess::Read && !is_read_route(req.method(), path) {
+        return Err(KError::Forbidden(format!(
+            "the api token {} is read only",
+            api_token.name
+        )));
+    }
+
+    if !api_token.namespaces.is_empty() {
+        if is_cross_namespace_route(path) {
+            return Err(KError::Forbidden(format!(
+                "the api token {} is limited to namespaces {}",
+                api_token.name,
+                api_token.namespaces.join(", ")
+            )));
+        }
+        let namespace = header_namespace(req.headers(), &state.config)?;
+        if !api_token.allows_namespace(&namespace) {
+            return Err(KError::Forbidden(format!(
+                "the api token {} has no access to namespace {}",
+                api_token.name, namespace
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reject requests without a valid session or api token, the user is put
+/// into the request extensions for `KUser`.
+pub async fn require_user(
+    State(state): State<ShareAppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, KError> {
+    let token = session_token(req.headers())
+        .filter(|e| !e.is_empty())
+        .ok_or_else(|| KError::Unauthorized("login required".to_owned()))?
+        .to_owned();
+
+    let user = if token.starts_with(API_TOKEN_PREFIX) {
+        let (user, api_token) = state.user_by_api_token(&token).await?;
+        check_api_token(&api_token, &req, &state)?;
+        KUser {
+            user,
+            api_token: Some(api_token),
+        }
+    } else {
+        KUser {
+            user: state.user_by_token(&token).await?,
+            api_token: None,
+        }
+    };
+
+    req.extensions_mut().insert(user);
+
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,93 @@
+use std::fmt::Debug;
+
+use axum::{
+    extract::{FromRequestParts, MatchedPath},
+    http::{request::Parts, HeaderMap},
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    app::ShareAppState,
+    config::Config,
+    error::KError,
+    model::{db::acl::NamespaceRole, dto::KReq},
+    server::controller::auth::KUser,
+    util::auth::is_read_route,
+};
+
+pub const NAMESPACE_HEADER: &str = "K-namespace";
+
+/// The namespace of the request, read from the `K-namespace` header. A
+/// request without it uses the `default_namespace` of the config.
+///
+/// The namespace must be a known one of `namespace_record`, and the user
+/// needs the viewer role on it for the read routes, the editor role for
+/// the others.
+#[derive(Debug, Clone)]
+pub struct KNamespace(pub String);
+
+impl KNamespace {
+    pub fn req<E: Debug + Clone + DeserializeOwned>(self, body: E) -> KReq<E> {
+        KReq {
+            body,
+            namespace: self.0,
+        }
+    }
+}
+
+/// The namespace of the header or the default one, it may be unknown.
+pub fn header_namespace(headers: &HeaderMap, config: &Config) -> Result<String, KError> {
+    match headers.get(NAMESPACE_HEADER) {
+        Some(value) => Ok(value
+            .to_str()
+            .ok()
+            .map(|e| e.trim())
+            .filter(|e| !e.is_empty())
+            .ok_or_else(|| {
+                KError::Validation(format!(
+                    "header {} is not a valid namespace",
+                    NAMESPACE_HEADER
+                ))
+            })?
+            .to_owned()),
+        None => config
+            .default_namespace
+            .clone()
+            .ok_or_else(|| KError::Validation(format!("header {} is required", NAMESPACE_HEADER))),
+    }
+}
+
+impl FromRequestParts<ShareAppState> for KNamespace {
+    type Rejection = KError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &ShareAppState,
+    ) -> Result<Self, Self::Rejection> {
+        let namespace = header_namespace(&parts.headers, &state.config)?;
+
+        if !state.namespace_tree().contains(&namespace) {
+            return Err(KError::NotFound(format!(
+                "unable to find namespace {}",
+                namespace
+            )));
+        }
+
+        let user = parts
+            .extensions
+            .get::<KUser>()
+            .ok_or_else(|| KError::Unauthorized("login required".to_owned()))?;
+        let path = parts
+            .extensions
+            .get::<MatchedPath>()
+            .map_or(parts.uri.path(), |e| e.as_str());
+        let role = if is_read_route(&parts.method, path) {
+            NamespaceRole::Viewer
+        } else {
+            NamespaceRole::Editor
+        };
+        state.require_role(&user.user, &namespace, role).await?;
+
+        Ok(KNamespace(namespace))
+    }
+}
@@ -0,0 +1,86 @@
+use std::{net::SocketAddr, time::Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{app::ShareAppState, error::KError, server::controller::auth::KUser};
+
+const FORWARDED_FOR: &str = "X-Forwarded-For";
+
+/// The first address of `X-Forwarded-For` if it is trusted, or the peer.
+fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trust_forwarded_for: bool) -> String {
+    let forwarded = headers
+        .get(FORWARDED_FOR)
+        .and_then(|e| e.to_str().ok())
+        .and_then(|e| e.split(',').next())
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty());
+
+    match (forwarded, peer) {
+        (Some(ip), _) if trust_forwarded_for => ip.to_owned(),
+        (_, Some(peer)) => peer.ip().to_string(),
+        _ => "unknown".to_owned(),
+    }
+}
+
+fn acquire(state: &ShareAppState, key: &str) -> Result<(), KError> {
+    let Some(limiter) = &state.rate_limiter else {
+        return Ok(());
+    };
+
+    limiter
+        .acquire(key, Instant::now())
+        .map_err(|wait| KError::TooManyRequests {
+            retry_after_secs: wait.as_secs_f64().ceil() as u64,
+        })
+}
+
+/// Take a token of the client ip.
+///
+/// It runs before `require_user`, so the requests with a missing or wrong
+/// session or api token are throttled as well.
+pub async fn rate_limit(
+    State(state): State<ShareAppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, KError> {
+    let Some(config) = &state.config.limit.rate else {
+        return Ok(next.run(req).await);
+    };
+
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|e| e.0);
+    let key = format!(
+        "ip:{}",
+        client_ip(req.headers(), peer, config.trust_forwarded_for)
+    );
+    acquire(&state, &key)?;
+
+    Ok(next.run(req).await)
+}
+
+/// Take a token of the api token of the request too.
+///
+/// It runs after `require_user`, only verified api tokens get a bucket of
+/// their own, the sessions are limited by their ip alone.
+pub async fn token_rate_limit(
+    State(state): State<ShareAppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, KError> {
+    if let Some(KUser {
+        api_token: Some(api_token),
+        ..
+    }) = req.extensions().get::<KUser>()
+    {
+        acquire(&state, &format!("token:{}", api_token.id))?;
+    }
+
+    Ok(next.run(req).await)
+}
@@ -3,7 +3,6 @@ use std::{net::SocketAddr, path::PathBuf};
 use axum_server::tls_rustls::RustlsConfig;
 use chin_tools::wrapper::anyhow::{AResult, EResult};
 use serde::Serialize;
-use serde_json::json;
 
 use axum::{
     extract::DefaultBodyLimit,
@@ -14,6 +13,7 @@ use axum::{
         },
         HeaderValue,
     },
+    middleware,
     response::IntoResponse,
     Json, Router,
 };
@@ -25,11 +25,15 @@ use tower_http::{
     trace::{self, TraceLayer},
 };
 use tracing::{info, Level};
-use v1::{chnot, llmchat, resource, toent};
+use v1::{chnot, llmchat, namespace, resource, toent, user};
 
-use crate::app::ShareAppState;
+use crate::{app::ShareAppState, error::KError};
 
 mod asset;
+pub mod auth;
+pub mod extract;
+mod limit;
+mod share;
 pub mod v1;
 
 pub struct KResponse<E: Serialize>(AResult<E>);
@@ -48,15 +52,7 @@ impl<E: Serialize> IntoResponse for KResponse<E> {
                 *res.status_mut() = StatusCode::OK;
                 res
             }
-            Err(err) => {
-                tracing::error!("Error Occured: {}", err.to_string());
-                let mut res = Json(
-                    json!({"msg": err.to_string(), "backstrace": err.backtrace().to_string()}),
-                )
-                .into_response();
-                *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                res
-            }
+            Err(err) => KError::from(err).into_response(),
         }
     }
 }
@@ -73,12 +69,42 @@ pub async fn serve(app_state: ShareAppState) -> EResult {
         .on_response(trace::DefaultOnResponse::new().level(Level::DEBUG))
         .on_request(|_req: &_, _: &_| {});
 
-    let app = Router::new()
+    let limit_config = &app_state.config.limit;
+    let rate_limit = || middleware::from_fn_with_state(app_state.clone(), limit::rate_limit);
+
+    // every api needs a session but the login, the ip is limited before the
+    // session is checked and the api token after
+    let api = Router::new()
+        .merge(
+            resource::upload_routes().layer(DefaultBodyLimit::max(limit_config.upload_body_bytes)),
+        )
         .merge(resource::routes())
         .merge(chnot::routes())
-        .merge(asset::routes())
         .merge(toent::routes())
         .merge(llmchat::routes())
+        .merge(namespace::routes())
+        .merge(user::routes())
+        .merge(v1::share::routes())
+        .merge(v1::audit::routes())
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            limit::token_rate_limit,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            auth::require_user,
+        ))
+        .route_layer(rate_limit());
+
+    let public = Router::new()
+        .merge(user::public_routes())
+        .merge(share::routes())
+        .route_layer(rate_limit());
+
+    let app = Router::new()
+        .merge(api)
+        .merge(public)
+        .merge(asset::routes())
         .with_state(app_state.clone())
         .layer(CompressionLayer::new())
         .layer(SetResponseHeaderLayer::<_>::overriding(
@@ -93,7 +119,7 @@ pub async fn serve(app_state: ShareAppState) -> EResult {
             ACCESS_CONTROL_ALLOW_HEADERS,
             HeaderValue::from_static("*"),
         ))
-        .layer(DefaultBodyLimit::disable())
+        .layer(DefaultBodyLimit::max(limit_config.json_body_bytes))
         // https://stackoverflow.com/questions/73498537/axum-router-rejecting-cors-options-preflight-with-405-even-with-corslayer/
         .layer(cors_layer)
         .layer(trace_layer);
@@ -112,7 +138,7 @@ pub async fn serve(app_state: ShareAppState) -> EResult {
             ),
             tls_config,
         )
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await?;
     } else {
         let server_url = format!("{}:{}", "0.0.0.0", port);
@@ -120,7 +146,11 @@ pub async fn serve(app_state: ShareAppState) -> EResult {
         let listener = tokio::net::TcpListener::bind(&server_url).await?;
         info!("server: {}", server_url);
 
-        axum::serve(listener, app).await?;
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await?;
     }
     Ok(())
 }
@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Path, State},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+use crate::{
+    app::ShareAppState,
+    error::KError,
+    server::controller::v1::resource::resource_file,
+    util::share::{escape_html, render_markdown, share_page},
+};
+
+/// Public read-only pages of the share links, no login is needed.
+pub fn routes() -> Router<ShareAppState> {
+    Router::new()
+        .route("/s/{token}", get(share_view))
+        .route("/s/{token}/resource/{id}", get(share_resource))
+}
+
+/// A plain page instead of the json error of the api, it is read by people.
+fn error_page(err: KError) -> Response {
+    match err {
+        KError::Internal(_) => err.into_response(),
+        err => (
+            err.status(),
+            Html(share_page(
+                "chnots",
+                &format!("<p>{}</p>", escape_html(&err.to_string())),
+            )),
+        )
+            .into_response(),
+    }
+}
+
+async fn share_view(state: State<ShareAppState>, Path(token): Path<String>) -> Response {
+    let content = match state.share_content(&token).await {
+        Ok(content) => content,
+        Err(err) => return error_page(err.into()),
+    };
+
+    let resource_prefix = format!("/s/{}/resource", token);
+    let mut body = format!("<h1>{}</h1>\n", escape_html(&content.title));
+    for record in content.records {
+        body.push_str("<div class=\"record\">\n");
+        if let Some(role) = record.role {
+            body.push_str(&format!(
+                "<div class=\"role\">{}</div>\n",
+                escape_html(&role)
+            ));
+        }
+        body.push_str(&render_markdown(&record.content, &resource_prefix));
+        body.push_str("</div>\n");
+    }
+
+    Html(share_page(&content.title, &body)).into_response()
+}
+
+async fn share_resource(
+    state: State<ShareAppState>,
+    Path((token, id)): Path<(String, String)>,
+) -> Response {
+    let resource = match state.share_resource(&token, &id).await {
+        Ok(resource) => resource,
+        Err(err) => return error_page(err.into()),
+    };
+
+    match resource_file(&state.config.attachment, resource).await {
+        Ok(file) => file.into_response(),
+        Err(err) => error_page(err.into()),
+    }
+}
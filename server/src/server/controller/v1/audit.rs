@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Router,
+};
+
+use crate::{
+    app::ShareAppState,
+    error::KError,
+    model::{
+        db::{acl::NamespaceRole, audit::GLOBAL_NAMESPACE},
+        dto::{
+            audit::{AuditLogQueryReq, AuditLogQueryRsp},
+            KReq,
+        },
+    },
+    server::controller::{auth::KUser, extract::KNamespace, KResponse},
+};
+
+pub fn routes() -> Router<ShareAppState> {
+    Router::new().route("/api/v1/audit-logs", get(audit_log_query))
+}
+
+async fn audit_log_query(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Query(req): Query<AuditLogQueryReq>,
+) -> Result<KResponse<AuditLogQueryRsp>, KError> {
+    if req.global {
+        user.admin_user()?;
+        let req = KReq {
+            body: req,
+            namespace: GLOBAL_NAMESPACE.to_owned(),
+        };
+        return Ok(state.audit_log_query(req).await.into());
+    }
+
+    state
+        .require_role(&user.user, &namespace.0, NamespaceRole::Owner)
+        .await?;
+    Ok(state.audit_log_query(namespace.req(req)).await.into())
+}
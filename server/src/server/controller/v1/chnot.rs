@@ -1,18 +1,25 @@
+use std::str::FromStr;
+
 use crate::app::ShareAppState;
+use crate::model::db::acl::NamespaceRole;
+use crate::model::db::chnot::ChnotMetadata;
 use crate::model::dto::chnot::Chnot;
-use crate::model::dto::kreq;
 use crate::{
-    mapper::ChnotMapper,
+    error::KError,
+    mapper::{query::ChnotQuery, ChnotMapper, ChnotTagMapper, ChnotTrashMapper},
     model::dto::chnot::{
-        ChnotDeletionReq, ChnotDeletionRsp, ChnotOverwriteReq, ChnotOverwriteRsp, ChnotQueryReq,
-        ChnotQueryRsp, ChnotUpdateReq, ChnotUpdateRsp,
+        ChnotActivityReq, ChnotActivityRsp, ChnotBacklinksReq, ChnotBacklinksRsp, ChnotDeletionReq,
+        ChnotDeletionRsp, ChnotDiffReq, ChnotDiffRsp, ChnotGraphReq, ChnotGraphRsp,
+        ChnotHistoryReq, ChnotHistoryRsp, ChnotMoveReq, ChnotMoveRsp, ChnotOverwriteReq,
+        ChnotOverwriteRsp, ChnotQueryReq, ChnotQueryRsp, ChnotRestoreReq, ChnotRestoreRsp,
+        ChnotSearchMode, ChnotSearchReq, ChnotTagListReq, ChnotTagListRsp, ChnotTagRenameReq,
+        ChnotTagRenameRsp, ChnotTrashListReq, ChnotTrashRestoreReq, ChnotUpdateReq,
     },
-    server::controller::KResponse,
+    server::controller::{auth::KUser, extract::KNamespace, KResponse},
 };
 use axum::{
-    extract::State,
-    http::HeaderMap,
-    routing::{delete, post, put},
+    extract::{Path, Query, State},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 
@@ -21,41 +28,232 @@ pub fn routes() -> Router<ShareAppState> {
         .route("/api/v1/chnot", put(chnot_overwrite))
         .route("/api/v1/chnot", delete(chnot_deletetion))
         .route("/api/v1/chnot-query", post(chnot_query))
+        .route("/api/v1/chnot-search", post(chnot_search))
         .route("/api/v1/chnot-update", post(chnot_update))
+        .route("/api/v1/chnot-move", post(chnot_move))
+        .route("/api/v1/chnot-trash", get(chnot_trash_list))
+        .route("/api/v1/chnot-trash-restore", post(chnot_trash_restore))
+        .route("/api/v1/chnot/{meta_id}/history", get(chnot_history))
+        .route("/api/v1/chnot/{meta_id}/restore", post(chnot_restore))
+        .route("/api/v1/chnot-diff", get(chnot_diff))
+        .route("/api/v1/chnot-activity", get(chnot_activity))
+        .route("/api/v1/chnot/{meta_id}/backlinks", get(chnot_backlinks))
+        .route("/api/v1/chnot-graph", get(chnot_graph))
+        .route("/api/v1/chnot-tags", get(chnot_tag_list))
+        .route("/api/v1/chnot-tag-rename", post(chnot_tag_rename))
 }
 
 async fn chnot_overwrite(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<ChnotOverwriteReq>,
 ) -> KResponse<ChnotOverwriteRsp> {
-    state
-        .mapper
-        .chnot_overwrite(kreq(headers, req))
-        .await
-        .into()
+    let req = namespace.req(req);
+    state.chnot_overwrite(&user.user, req).await.into()
 }
 
 async fn chnot_deletetion(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<ChnotDeletionReq>,
 ) -> KResponse<ChnotDeletionRsp> {
-    state.mapper.chnot_delete(kreq(headers, req)).await.into()
+    let req = namespace.req(req);
+    state.chnot_delete(&user.user, req).await.into()
 }
 
 async fn chnot_update(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<ChnotUpdateReq>,
-) -> KResponse<ChnotUpdateRsp> {
-    state.mapper.chnot_update(kreq(headers, req)).await.into()
+) -> KResponse<ChnotMetadata> {
+    let req = namespace.req(req);
+    state.chnot_update(&user.user, req).await.into()
+}
+
+/// The namespaces a chnot query reads besides the header one, the one of
+/// `ns:` and the descendants of the namespace read.
+fn query_namespaces(
+    state: &ShareAppState,
+    namespace: &str,
+    query: Option<&str>,
+    include_descendants: bool,
+) -> Result<(Option<String>, Vec<String>), KError> {
+    let ns = query
+        .map(ChnotQuery::from_str)
+        .transpose()
+        .map_err(KError::QueryParse)?
+        .and_then(|e| e.namespace);
+    let descendants = if include_descendants {
+        state
+            .namespace_tree()
+            .descendants(ns.as_deref().unwrap_or(namespace))
+    } else {
+        vec![]
+    };
+    Ok((ns, descendants))
+}
+
+async fn chnot_move(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Json(req): Json<ChnotMoveReq>,
+) -> Result<KResponse<ChnotMoveRsp>, KError> {
+    state
+        .require_role(
+            &user.user,
+            req.target_namespace.trim(),
+            NamespaceRole::Editor,
+        )
+        .await?;
+    user.require_token_scope([req.target_namespace.trim()])?;
+    if let Some(filter) = req.filter.as_ref() {
+        let (ns, descendants) = query_namespaces(
+            &state,
+            &namespace.0,
+            filter.query.as_deref(),
+            filter.include_descendants,
+        )?;
+        user.require_token_scope(ns.iter().chain(descendants.iter()).map(String::as_str))?;
+    }
+    Ok(state
+        .chnot_move(&user.user, namespace.req(req))
+        .await
+        .into())
+}
+
+async fn chnot_trash_list(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Query(req): Query<ChnotTrashListReq>,
+) -> KResponse<ChnotQueryRsp<Vec<Chnot>>> {
+    state
+        .mapper
+        .chnot_trash_list(namespace.req(req))
+        .await
+        .into()
+}
+
+async fn chnot_trash_restore(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Json(req): Json<ChnotTrashRestoreReq>,
+) -> KResponse<ChnotMetadata> {
+    let req = namespace.req(req);
+    state.chnot_trash_restore(&user.user, req).await.into()
 }
 
 async fn chnot_query(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<ChnotQueryReq>,
+) -> Result<KResponse<ChnotQueryRsp<Vec<Chnot>>>, KError> {
+    // `ns:` in the query reads another namespace than the one of the header,
+    // the full text search uses the query as it is.
+    let query = match req.search_mode {
+        ChnotSearchMode::Substring => req.query.as_deref(),
+        ChnotSearchMode::FullText => None,
+    };
+    let (ns, descendants) = query_namespaces(&state, &namespace.0, query, req.include_descendants)?;
+    if let Some(ns) = ns.as_deref() {
+        state
+            .require_role(&user.user, ns, NamespaceRole::Viewer)
+            .await?;
+    }
+    user.require_token_scope(ns.iter().chain(descendants.iter()).map(String::as_str))?;
+    Ok(state.chnot_query(namespace.req(req)).await.into())
+}
+
+async fn chnot_search(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Json(req): Json<ChnotSearchReq>,
 ) -> KResponse<ChnotQueryRsp<Vec<Chnot>>> {
-    state.chnot_query(kreq(headers, req)).await.into()
+    state.chnot_search(namespace.req(req)).await.into()
+}
+
+async fn chnot_history(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Path(meta_id): Path<String>,
+) -> KResponse<ChnotHistoryRsp> {
+    state
+        .mapper
+        .chnot_history(namespace.req(ChnotHistoryReq { meta_id }))
+        .await
+        .into()
+}
+
+async fn chnot_diff(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Query(req): Query<ChnotDiffReq>,
+) -> KResponse<ChnotDiffRsp> {
+    state.chnot_diff(namespace.req(req)).await.into()
+}
+
+async fn chnot_activity(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Query(req): Query<ChnotActivityReq>,
+) -> KResponse<ChnotActivityRsp> {
+    state.chnot_activity(namespace.req(req)).await.into()
+}
+
+async fn chnot_restore(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Path(meta_id): Path<String>,
+    Json(req): Json<ChnotRestoreReq>,
+) -> KResponse<ChnotRestoreRsp> {
+    let req = namespace.req(ChnotRestoreReq { meta_id, ..req });
+    state.chnot_restore(&user.user, req).await.into()
+}
+
+async fn chnot_tag_list(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+) -> KResponse<ChnotTagListRsp> {
+    state
+        .mapper
+        .chnot_tag_list(namespace.req(ChnotTagListReq {}))
+        .await
+        .into()
+}
+
+async fn chnot_tag_rename(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Json(req): Json<ChnotTagRenameReq>,
+) -> KResponse<ChnotTagRenameRsp> {
+    let req = namespace.req(req);
+    state.chnot_tag_rename(&user.user, req).await.into()
+}
+
+async fn chnot_backlinks(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Path(meta_id): Path<String>,
+) -> KResponse<ChnotBacklinksRsp> {
+    state
+        .chnot_backlinks(namespace.req(ChnotBacklinksReq { meta_id }))
+        .await
+        .into()
+}
+
+async fn chnot_graph(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+) -> KResponse<ChnotGraphRsp> {
+    state
+        .chnot_graph(namespace.req(ChnotGraphReq {}))
+        .await
+        .into()
 }
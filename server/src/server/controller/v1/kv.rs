@@ -1,13 +1,11 @@
 use crate::app::ShareAppState;
 use crate::mapper::KVMapper;
-use crate::model::dto::kreq;
 use crate::model::dto::kv::{KVDeleteReq, KVDeleteRsp, KVOverwriteReq, KVOverwriteRsp, KVQueryReq, KVQueryRsp};
-use crate::server::controller::KResponse;
+use crate::server::controller::{auth::KUser, extract::KNamespace, KResponse};
 use axum::extract::Path;
 use axum::routing::get;
 use axum::{
     extract::State,
-    http::HeaderMap,
     routing::{delete, put},
     Json, Router,
 };
@@ -20,25 +18,29 @@ pub fn routes() -> Router<ShareAppState> {
 }
 
 async fn kv_overwrite(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<KVOverwriteReq>,
 ) -> KResponse<KVOverwriteRsp> {
-    state.mapper.kv_overwrite(kreq(headers, req)).await.into()
+    let req = namespace.req(req);
+    state.kv_overwrite(&user.user, req).await.into()
 }
 
 async fn kv_query(
-    headers: HeaderMap,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Path(req): Path<KVQueryReq>,
 ) -> KResponse<KVQueryRsp> {
-    state.mapper.kv_query(kreq(headers, req)).await.into()
+    state.mapper.kv_query(namespace.req(req)).await.into()
 }
 
 async fn kv_delete(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<KVDeleteReq>,
 ) -> KResponse<KVDeleteRsp> {
-    state.mapper.kv_delete(kreq(headers, req)).await.into()
+    let req = namespace.req(req);
+    state.kv_delete(&user.user, req).await.into()
 }
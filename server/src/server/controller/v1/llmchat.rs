@@ -1,15 +1,15 @@
 use axum::{
     extract::{Query, State},
-    http::HeaderMap,
     routing::{delete, get, post, put},
     Json, Router,
 };
 
 use crate::{
     app::ShareAppState,
+    error::KError,
     mapper::LLMChatMapper,
-    model::dto::{kreq, llmchat::*},
-    server::controller::KResponse,
+    model::dto::llmchat::*,
+    server::controller::{auth::KUser, extract::KNamespace, KResponse},
 };
 
 pub fn routes() -> Router<ShareAppState> {
@@ -30,156 +30,150 @@ pub fn routes() -> Router<ShareAppState> {
 }
 
 async fn bot_overwrite(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<LLMChatOverwriteBotReq>,
 ) -> KResponse<LLMChatOverwriteBotRsp> {
-    state
-        .mapper
-        .llm_chat_overwrite_bot(kreq(headers, req))
-        .await
-        .into()
+    let req = namespace.req(req);
+    state.llm_chat_overwrite_bot(&user.user, req).await.into()
 }
 
 async fn bot_deletetion(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<LLMChatDeleteBotReq>,
 ) -> KResponse<LLMChatDeleteBotRsp> {
-    state
-        .mapper
-        .llm_chat_delete_bot(kreq(headers, req))
-        .await
-        .into()
+    let req = namespace.req(req);
+    state.llm_chat_delete_bot(&user.user, req).await.into()
 }
 
 async fn bot_list(
-    headers: HeaderMap,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Query(req): Query<LLMChatListBotReq>,
 ) -> KResponse<LLMChatListBotRsp> {
     state
         .mapper
-        .llm_chat_list_bots(kreq(headers, req))
+        .llm_chat_list_bots(namespace.req(req))
         .await
         .into()
 }
 
 async fn template_deletetion(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<LLMChatDeleteTemplateReq>,
 ) -> KResponse<LLMChatDeleteTemplateRsp> {
-    state
-        .mapper
-        .llm_chat_delete_template(kreq(headers, req))
-        .await
-        .into()
+    let req = namespace.req(req);
+    state.llm_chat_delete_template(&user.user, req).await.into()
 }
 
 async fn template_overwrite(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<LLMChatOverwriteTemplateReq>,
 ) -> KResponse<LLMChatOverwriteTemplateRsp> {
+    let req = namespace.req(req);
     state
-        .mapper
-        .llm_chat_overwrite_template(kreq(headers, req))
+        .llm_chat_overwrite_template(&user.user, req)
         .await
         .into()
 }
 
 async fn template_list(
-    headers: HeaderMap,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Query(req): Query<LLMChatListTemplateReq>,
 ) -> KResponse<LLMChatListTemplateRsp> {
     state
         .mapper
-        .llm_chat_list_templates(kreq(headers, req))
+        .llm_chat_list_templates(namespace.req(req))
         .await
         .into()
 }
 
 async fn session_deletetion(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<LLMChatDeleteSessionReq>,
 ) -> KResponse<LLMChatDeleteSessionRsp> {
-    state
-        .mapper
-        .llm_chat_delete_session(kreq(headers, req))
-        .await
-        .into()
+    let req = namespace.req(req);
+    state.llm_chat_delete_session(&user.user, req).await.into()
 }
 
 async fn session_insertion(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
-    Json(req): Json<LLMChatInsertSessionReq>,
+    Json(mut req): Json<LLMChatInsertSessionReq>,
 ) -> KResponse<LLMChatInsertSessionRsp> {
-    state
-        .mapper
-        .llm_chat_insert_session(kreq(headers, req))
-        .await
-        .into()
+    // the session belongs to the checked namespace of the header
+    req.session.namespace = namespace.0.clone();
+    let req = namespace.req(req);
+    state.llm_chat_insert_session(&user.user, req).await.into()
 }
 async fn session_list(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Query(req): Query<LLMChatListSessionReq>,
-) -> KResponse<LLMChatListSessionRsp> {
-    state
-        .mapper
-        .llm_chat_list_sessions(kreq(headers, req))
+) -> Result<KResponse<LLMChatListSessionRsp>, KError> {
+    if req.include_descendants {
+        let descendants = state.namespace_tree().descendants(&namespace.0);
+        user.require_token_scope(descendants.iter().map(String::as_str))?;
+    }
+    Ok(state
+        .llm_chat_list_sessions(namespace.req(req))
         .await
-        .into()
+        .into())
 }
 
 async fn session_detail(
-    headers: HeaderMap,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Query(req): Query<LLMChatSessionDetialReq>,
 ) -> KResponse<LLMChatSessionDetailRsp> {
     state
         .mapper
-        .llm_chat_session_detail(kreq(headers, req))
+        .llm_chat_session_detail(namespace.req(req))
         .await
         .into()
 }
 
 async fn session_updation(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<LLMChatUpdateSessionReq>,
 ) -> KResponse<LLMChatUpdateSessionRsp> {
-    state
-        .mapper
-        .llm_chat_update_session(kreq(headers, req))
-        .await
-        .into()
+    let req = namespace.req(req);
+    state.llm_chat_update_session(&user.user, req).await.into()
 }
 
 async fn session_truncation(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<LLMChatTruncateSessionReq>,
 ) -> KResponse<LLMChatTruncateSessionRsp> {
+    let req = namespace.req(req);
     state
-        .mapper
-        .llm_chat_truncate_session(kreq(headers, req))
+        .llm_chat_truncate_session(&user.user, req)
         .await
         .into()
 }
 
 async fn record_insertion(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Json(req): Json<LLMChatInsertRecordReq>,
 ) -> KResponse<LLMChatInsertRecordRsp> {
-    state
-        .mapper
-        .llm_chat_insert_record(kreq(headers, req))
-        .await
-        .into()
+    let req = namespace.req(req);
+    state.llm_chat_insert_record(&user.user, req).await.into()
 }
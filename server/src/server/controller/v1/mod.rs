@@ -1,5 +1,9 @@
+pub mod audit;
 pub mod chnot;
 pub mod llmchat;
+pub mod namespace;
 pub mod resource;
+pub mod share;
 pub mod toent;
+pub mod user;
 pub mod kv;
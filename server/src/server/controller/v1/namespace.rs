@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Query, State},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+
+use crate::{
+    app::ShareAppState,
+    error::KError,
+    model::{
+        db::{
+            acl::{NamespaceAcl, NamespaceRole},
+            namespace::NamespaceRecord,
+        },
+        dto::namespace::*,
+    },
+    server::controller::{auth::KUser, KResponse},
+};
+
+pub fn routes() -> Router<ShareAppState> {
+    Router::new()
+        .route("/api/v1/namespace", put(namespace_creation))
+        .route("/api/v1/namespace", delete(namespace_deletion))
+        .route("/api/v1/namespace-tree", get(namespace_tree))
+        .route("/api/v1/namespace-rename", post(namespace_rename))
+        .route("/api/v1/namespace-move", post(namespace_move))
+        .route("/api/v1/namespace-acl", get(namespace_acl_list))
+        .route("/api/v1/namespace-acl", put(namespace_acl_overwrite))
+        .route("/api/v1/namespace-acl", delete(namespace_acl_deletion))
+}
+
+async fn namespace_tree(user: KUser, state: State<ShareAppState>) -> KResponse<NamespaceTreeRsp> {
+    state.namespace_tree_of(&user.user).await.into()
+}
+
+async fn namespace_creation(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<NamespaceCreateReq>,
+) -> Result<KResponse<NamespaceRecord>, KError> {
+    if let Some(parent_id) = req.parent_id.as_deref() {
+        state
+            .require_role(&user.user, parent_id, NamespaceRole::Owner)
+            .await?;
+    }
+    Ok(state.namespace_create(req, Some(&user.user)).await.into())
+}
+
+async fn namespace_rename(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<NamespaceRenameReq>,
+) -> Result<KResponse<NamespaceRecord>, KError> {
+    state
+        .require_role(&user.user, &req.id, NamespaceRole::Owner)
+        .await?;
+    Ok(state.namespace_rename(&user.user, req).await.into())
+}
+
+async fn namespace_deletion(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<NamespaceDeleteReq>,
+) -> Result<KResponse<NamespaceDeleteRsp>, KError> {
+    state
+        .require_role(&user.user, &req.id, NamespaceRole::Owner)
+        .await?;
+    Ok(state.namespace_delete(&user.user, req).await.into())
+}
+
+async fn namespace_move(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<NamespaceMoveReq>,
+) -> Result<KResponse<NamespaceTreeRsp>, KError> {
+    state
+        .require_role(&user.user, &req.id, NamespaceRole::Owner)
+        .await?;
+    if let Some(parent_id) = req.parent_id.as_deref() {
+        state
+            .require_role(&user.user, parent_id, NamespaceRole::Owner)
+            .await?;
+    }
+    Ok(state.namespace_move(&user.user, req).await.into())
+}
+
+async fn namespace_acl_list(
+    user: KUser,
+    state: State<ShareAppState>,
+    Query(req): Query<NamespaceAclListReq>,
+) -> Result<KResponse<NamespaceAclListRsp>, KError> {
+    state
+        .require_role(&user.user, &req.namespace, NamespaceRole::Owner)
+        .await?;
+    Ok(state.namespace_acl_list(req).await.into())
+}
+
+async fn namespace_acl_overwrite(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<NamespaceAclOverwriteReq>,
+) -> Result<KResponse<NamespaceAcl>, KError> {
+    state
+        .require_role(&user.user, &req.namespace, NamespaceRole::Owner)
+        .await?;
+    Ok(state.namespace_acl_overwrite(&user.user, req).await.into())
+}
+
+async fn namespace_acl_deletion(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<NamespaceAclDeleteReq>,
+) -> Result<KResponse<NamespaceAclDeleteRsp>, KError> {
+    state
+        .require_role(&user.user, &req.namespace, NamespaceRole::Owner)
+        .await?;
+    Ok(state.namespace_acl_delete(&user.user, req).await.into())
+}
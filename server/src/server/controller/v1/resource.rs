@@ -1,37 +1,48 @@
 use std::{ffi::OsStr, path::PathBuf};
 
 use axum::{
-    body::{self, Bytes},
-    extract::{Multipart, Path, Query, State},
-    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    body,
+    extract::{
+        multipart::{Field, MultipartError},
+        Multipart, Path, Query, State,
+    },
+    http::{header, HeaderName, StatusCode},
     response::{IntoResponse, Response},
-    routing::{get, put},
-    Router,
+    routing::{delete, get, put},
+    Json, Router,
 };
 use chin_tools::{utils::path_util::split_uuid_to_file_name, wrapper::anyhow::AResult};
 use chrono::Local;
-use futures::{Stream, TryStreamExt};
-
+use sha2::{Digest, Sha256};
 use tokio::{
     fs::File,
-    io::{self, BufWriter},
+    io::{self, AsyncWriteExt, BufWriter},
 };
 use tokio_util::io::ReaderStream;
-use tracing::info;
+use tracing::{error, info};
 
 use crate::{
     app::ShareAppState,
     config::AttachmentConfig,
+    error::KError,
     mapper::ResourceMapper,
     model::{
-        db::resource::Resource,
+        db::{
+            acl::NamespaceRole,
+            audit::{AuditAction, AuditEntityType},
+            resource::Resource,
+            user::User,
+        },
         dto::{
-            kreq, read_namespace_from_header, InsertInlineResourceReq, InsertInlineResourceRsp,
-            QueryInlineResourceReq, QueryInlineResourceRsp, ResourceUploadRsp,
+            InsertInlineResourceReq, InsertInlineResourceRsp, KReq, QueryInlineResourceReq,
+            QueryInlineResourceRsp, ResourceDeleteReq, ResourceDeleteRsp, ResourceListReq,
+            ResourceListRsp, ResourceUploadRsp,
         },
     },
     server::controller::{
         asset::{asset_to_response, ContentEnum},
+        auth::KUser,
+        extract::KNamespace,
         KResponse,
     },
 };
@@ -46,6 +57,23 @@ pub fn asset_path_by_uuid(config: &AttachmentConfig, id: &str) -> PathBuf {
     save_filepath
 }
 
+/// The file shared by the resources of the same content.
+pub fn asset_path_by_sha256(config: &AttachmentConfig, sha256: &str) -> PathBuf {
+    std::path::Path::new(&config.base_dir)
+        .join("sha256")
+        .join(&sha256[..2])
+        .join(&sha256[2..4])
+        .join(sha256)
+}
+
+/// The uploads before the sha256 have a file of their own under the id.
+pub fn resource_path(config: &AttachmentConfig, resource: &Resource) -> PathBuf {
+    match resource.sha256.as_deref() {
+        Some(sha256) => asset_path_by_sha256(config, sha256),
+        None => asset_path_by_uuid(config, &resource.id),
+    }
+}
+
 fn generate_resource_id(filename: &str) -> String {
     let base = uuid::Uuid::new_v4().to_string().replace("-", "");
 
@@ -55,153 +83,288 @@ fn generate_resource_id(filename: &str) -> String {
     }
 }
 
+async fn create_parent_dir(path: &std::path::Path) -> io::Result<()> {
+    let dir = path.parent().unwrap();
+    if !tokio::fs::metadata(&dir).await.is_ok() {
+        tokio::fs::create_dir_all(&dir).await?;
+    }
+    Ok(())
+}
+
+/// The client sent a broken or too large body, not an internal error.
+fn multipart_error(err: MultipartError) -> KError {
+    match err.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => KError::PayloadTooLarge(err.body_text()),
+        _ => KError::Validation(err.body_text()),
+    }
+}
+
 async fn upload(
-    headers: HeaderMap,
+    user: KUser,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     mut multipart: Multipart,
 ) -> AResult<ResourceUploadRsp> {
     let mut resources = vec![];
-    while let Some(field) = multipart.next_field().await.unwrap() {
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
         let filename = if let Some(filename) = field.file_name() {
             filename.to_string()
         } else {
             continue;
         };
 
-        let content_type = field.content_type().unwrap().to_string();
-
-        let mapper = &state.mapper;
+        let content_type = field
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string();
 
         let id = generate_resource_id(&filename);
 
-        let save_filepath = asset_path_by_uuid(&state.config.attachment, &id);
-        let save_dir = save_filepath.parent().unwrap();
-
-        if !tokio::fs::metadata(&save_dir).await.is_ok() {
-            tokio::fs::create_dir_all(&save_dir).await?;
-        }
-
-        stream_to_file(field, &save_filepath).await?;
+        // the file is moved to its sha256 once it is known
+        let tmp_filepath = std::path::Path::new(&state.config.attachment.base_dir)
+            .join("tmp")
+            .join(&id);
+        create_parent_dir(&tmp_filepath).await?;
+
+        let sha256 = match stream_to_file(field, &tmp_filepath).await {
+            Ok(sha256) => sha256,
+            Err(err) => {
+                let _ = tokio::fs::remove_file(&tmp_filepath).await;
+                return Err(err);
+            }
+        };
 
-        let res = mapper
-            .insert_resource(&Resource {
+        let res = insert_resource(
+            &state,
+            &user.user,
+            &tmp_filepath,
+            Resource {
                 id,
-                namespace: read_namespace_from_header(&headers),
+                namespace: namespace.0.clone(),
                 ori_filename: filename,
                 content_type,
+                sha256: Some(sha256),
                 delete_time: None,
                 insert_time: Local::now().into(),
-            })
-            .await?;
+            },
+        )
+        .await?;
         resources.push(res);
     }
 
     Ok(ResourceUploadRsp { resources })
 }
 
-async fn stream_to_file<S, E>(stream: S, save_file: &PathBuf) -> Result<(), std::io::Error>
-where
-    S: Stream<Item = Result<Bytes, E>>,
-    E: Into<axum::BoxError>,
-{
-    async {
-        let body_with_io_error =
-            stream.map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err));
-        let body_reader = tokio_util::io::StreamReader::new(body_with_io_error);
-        futures::pin_mut!(body_reader);
+/// Keep the uploaded file unless another resource has the same content
+/// already, then insert the row referencing it.
+async fn insert_resource(
+    state: &ShareAppState,
+    user: &User,
+    tmp_filepath: &std::path::Path,
+    resource: Resource,
+) -> AResult<Resource> {
+    let _files = state.resource_files.lock().await;
+
+    let save_filepath = resource_path(&state.config.attachment, &resource);
+    let shared = tokio::fs::try_exists(&save_filepath).await?;
+    if shared {
+        tokio::fs::remove_file(tmp_filepath).await?;
+    } else {
+        create_parent_dir(&save_filepath).await?;
+        tokio::fs::rename(tmp_filepath, &save_filepath).await?;
+    }
+
+    let resource = match state.mapper.insert_resource(&resource).await {
+        Ok(resource) => resource,
+        Err(err) => {
+            if !shared {
+                let _ = tokio::fs::remove_file(&save_filepath).await;
+            }
+            return Err(err);
+        }
+    };
+    state
+        .audit(
+            user,
+            &resource.namespace,
+            AuditEntityType::Resource,
+            &resource.id,
+            AuditAction::Upload,
+            &resource,
+        )
+        .await;
+
+    Ok(resource)
+}
+
+/// Write the field to the file, the sha256 of the content in hex.
+async fn stream_to_file(mut field: Field<'_>, save_file: &PathBuf) -> AResult<String> {
+    let mut file = BufWriter::new(File::create(save_file).await?);
+    let mut hasher = Sha256::new();
+
+    while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+async fn resource_deletion(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Json(req): Json<ResourceDeleteReq>,
+) -> KResponse<ResourceDeleteRsp> {
+    delete_resource(&state, &user.user, &namespace.req(req))
+        .await
+        .into()
+}
 
-        let mut file = BufWriter::new(File::create(save_file).await?);
+/// Remove the row, and the file too if no other resource references it.
+async fn delete_resource(
+    state: &ShareAppState,
+    user: &User,
+    req: &KReq<ResourceDeleteReq>,
+) -> AResult<ResourceDeleteRsp> {
+    let _files = state.resource_files.lock().await;
 
-        tokio::io::copy(&mut body_reader, &mut file).await?;
+    let resource = state
+        .mapper
+        .delete_resource(&req.namespace, &req.id)
+        .await?;
+    state
+        .audit(
+            user,
+            &req.namespace,
+            AuditEntityType::Resource,
+            &req.id,
+            AuditAction::Delete,
+            &req.body,
+        )
+        .await;
+    if let Some(sha256) = resource.sha256.as_deref() {
+        if state.mapper.count_resources_by_sha256(sha256).await? > 0 {
+            return Ok(ResourceDeleteRsp {
+                file_removed: false,
+            });
+        }
+    }
 
-        Ok::<_, io::Error>(())
+    let save_filepath = resource_path(&state.config.attachment, &resource);
+    if let Err(err) = tokio::fs::remove_file(&save_filepath).await {
+        // the row is gone already, a left file is only wasted space
+        error!("unable to remove file {:?}: {:?}", save_filepath, err);
+        return Ok(ResourceDeleteRsp {
+            file_removed: false,
+        });
     }
-    .await
+
+    Ok(ResourceDeleteRsp { file_removed: true })
 }
 
 // https://github.com/tokio-rs/axum/discussions/608
-pub async fn download(state: State<ShareAppState>, Path(id): Path<String>) -> impl IntoResponse {
+/// The file of the resource streamed as an attachment.
+pub async fn resource_file(
+    config: &AttachmentConfig,
+    resource: Resource,
+) -> AResult<([(HeaderName, String); 2], body::Body)> {
+    let save_filepath = resource_path(config, &resource);
+
+    let file = tokio::fs::File::open(&save_filepath).await?;
+
+    let stream = ReaderStream::new(file);
+    let body: body::Body = body::Body::from_stream(stream);
+
+    let headers = [
+        (header::CONTENT_TYPE, resource.content_type),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{:?}\"", &resource.ori_filename),
+        ),
+    ];
+    Ok((headers, body))
+}
+
+/// Loaded by `<img>` without the namespace header, the namespace of the
+/// resource is checked instead.
+pub async fn download(
+    user: KUser,
+    state: State<ShareAppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
     info!("download id: {}", id);
 
     async fn inner(
+        user: KUser,
         state: State<ShareAppState>,
         id: &str,
     ) -> AResult<([(HeaderName, String); 2], body::Body)> {
         let resource = state.mapper.query_resource_by_id(id).await?;
+        state
+            .require_role(&user.user, &resource.namespace, NamespaceRole::Viewer)
+            .await?;
+        user.require_token_scope([resource.namespace.as_str()])?;
 
-        let save_filepath = asset_path_by_uuid(&state.config.attachment, &id);
-
-        let file = tokio::fs::File::open(&save_filepath).await?;
-
-        let stream = ReaderStream::new(file);
-        let body: body::Body = body::Body::from_stream(stream);
-
-        let headers = [
-            (header::CONTENT_TYPE, resource.content_type),
-            (
-                header::CONTENT_DISPOSITION,
-                format!("attachment; filename=\"{:?}\"", &resource.ori_filename),
-            ),
-        ];
-        Ok((headers, body))
+        resource_file(&state.config.attachment, resource).await
     }
 
-    let res = inner(state, &id).await;
+    inner(user, state, &id).await.map_err(KError::from)
+}
 
-    match res {
-        Ok(res) => return Ok(res),
-        Err(err) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Unable to download: {:?}, {}", &err.to_string(), err),
-            ))
-        }
+async fn resource_list(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Query(req): Query<ResourceListReq>,
+) -> Result<KResponse<ResourceListRsp>, KError> {
+    if req.include_descendants {
+        let descendants = state.namespace_tree().descendants(&namespace.0);
+        user.require_token_scope(descendants.iter().map(String::as_str))?;
     }
+    Ok(state.resource_list(namespace.req(req)).await.into())
 }
 
 async fn query_inline_resource(
-    headers: HeaderMap,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Query(req): Query<QueryInlineResourceReq>,
 ) -> KResponse<QueryInlineResourceRsp> {
     state
         .mapper
-        .query_inline_resource(kreq(headers, req))
+        .query_inline_resource(namespace.req(req))
         .await
         .into()
 }
 
 async fn insert_inline_resource(
-    headers: HeaderMap,
+    namespace: KNamespace,
     state: State<ShareAppState>,
     Query(req): Query<InsertInlineResourceReq>,
 ) -> KResponse<InsertInlineResourceRsp> {
     state
         .mapper
-        .insert_inline_resource(&kreq(headers, req))
+        .insert_inline_resource(&namespace.req(req))
         .await
         .into()
 }
 
-async fn query_svg(
-    headers: HeaderMap,
-    state: State<ShareAppState>,
-    Path(id): Path<String>,
-) -> Response {
-    let mut headers = headers.clone();
-    headers.append("K-namespace", HeaderValue::from_str("default").unwrap());
-    let rsp = query_inline_resource(
-        headers,
-        state,
-        Query(QueryInlineResourceReq {
-            id: Some(id.into()),
-            content_type: Some("svg".into()),
-            name_like: None,
-        }),
-    )
-    .await
-    .0
-    .ok();
+async fn query_svg(state: State<ShareAppState>, Path(id): Path<String>) -> Response {
+    // loaded by `<img>` without the namespace header, inline resources are
+    // not namespaced anyway.
+    let rsp = state
+        .mapper
+        .query_inline_resource(KReq {
+            body: QueryInlineResourceReq {
+                id: Some(id.into()),
+                content_type: Some("svg".into()),
+                name_like: None,
+            },
+            namespace: "default".to_owned(),
+        })
+        .await
+        .ok();
 
     let res = rsp
         .and_then(|e| e.res.get(0).cloned())
@@ -210,16 +373,22 @@ async fn query_svg(
     asset_to_response(res)
 }
 
+/// Apart from `routes` for the larger body limit.
+pub fn upload_routes() -> Router<ShareAppState> {
+    Router::new().route(
+        "/api/v1/resource",
+        put(|user, namespace, state, mp| async {
+            let rsp: KResponse<ResourceUploadRsp> = upload(user, namespace, state, mp).await.into();
+            rsp
+        }),
+    )
+}
+
 pub fn routes() -> Router<ShareAppState> {
     Router::new()
-        .route(
-            "/api/v1/resource",
-            put(|headers, state, mp| async {
-                let rsp: KResponse<ResourceUploadRsp> = upload(headers, state, mp).await.into();
-                rsp
-            }),
-        )
+        .route("/api/v1/resource", delete(resource_deletion))
         .route("/api/v1/resource/{id}", get(download))
+        .route("/api/v1/resources", get(resource_list))
         .route("/api/v1/inline-resource", put(insert_inline_resource))
         .route("/api/v1/inline-resource", get(query_inline_resource))
         .route("/api/v1/inline-svg/{id}", get(query_svg))
@@ -0,0 +1,45 @@
+use axum::{
+    extract::State,
+    routing::{delete, get, put},
+    Json, Router,
+};
+
+use crate::{
+    app::ShareAppState,
+    model::dto::share::*,
+    server::controller::{auth::KUser, extract::KNamespace, KResponse},
+};
+
+pub fn routes() -> Router<ShareAppState> {
+    Router::new()
+        .route("/api/v1/share-link", put(share_link_creation))
+        .route("/api/v1/share-link", delete(share_link_revocation))
+        .route("/api/v1/share-links", get(share_link_list))
+}
+
+async fn share_link_creation(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Json(req): Json<ShareLinkCreateReq>,
+) -> KResponse<ShareLinkCreateRsp> {
+    let req = namespace.req(req);
+    state.share_link_create(&user.user, req).await.into()
+}
+
+async fn share_link_list(
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+) -> KResponse<ShareLinkListRsp> {
+    state.share_link_list(&namespace.0).await.into()
+}
+
+async fn share_link_revocation(
+    user: KUser,
+    namespace: KNamespace,
+    state: State<ShareAppState>,
+    Json(req): Json<ShareLinkRevokeReq>,
+) -> KResponse<ShareLinkRevokeRsp> {
+    let req = namespace.req(req);
+    state.share_link_revoke(&user.user, req).await.into()
+}
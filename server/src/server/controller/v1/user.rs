@@ -0,0 +1,154 @@
+use axum::{
+    extract::State,
+    http::{header::SET_COOKIE, HeaderMap},
+    response::{AppendHeaders, IntoResponse},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+
+use crate::{
+    app::ShareAppState,
+    error::KError,
+    model::{
+        db::{acl::UserGroup, user::User},
+        dto::user::*,
+    },
+    server::controller::{
+        auth::{session_token, KUser, SESSION_COOKIE},
+        KResponse,
+    },
+};
+
+/// Routes open to anyone, the login itself.
+pub fn public_routes() -> Router<ShareAppState> {
+    Router::new().route("/api/v1/user-login", post(user_login))
+}
+
+pub fn routes() -> Router<ShareAppState> {
+    Router::new()
+        .route("/api/v1/user-logout", post(user_logout))
+        .route("/api/v1/user-me", get(user_me))
+        .route("/api/v1/api-token", put(api_token_creation))
+        .route("/api/v1/api-token", delete(api_token_revocation))
+        .route("/api/v1/api-tokens", get(api_token_list))
+        .route("/api/v1/user", put(user_creation))
+        .route("/api/v1/users", get(user_list))
+        .route("/api/v1/user-group", put(user_group_creation))
+        .route("/api/v1/user-groups", get(user_group_list))
+        .route("/api/v1/user-group-member", put(user_group_member_addition))
+        .route(
+            "/api/v1/user-group-member",
+            delete(user_group_member_removal),
+        )
+}
+
+async fn user_login(
+    state: State<ShareAppState>,
+    Json(req): Json<UserLoginReq>,
+) -> Result<impl IntoResponse, KError> {
+    let rsp = state.user_login(req).await?;
+    let cookie = format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}",
+        SESSION_COOKIE,
+        rsp.token,
+        u64::from(state.config.auth.session_ttl_hours) * 3600
+    );
+
+    Ok((AppendHeaders([(SET_COOKIE, cookie)]), Json(rsp)))
+}
+
+async fn user_logout(
+    state: State<ShareAppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, KError> {
+    if let Some(token) = session_token(&headers) {
+        state.user_logout(token).await?;
+    }
+    let cookie = format!(
+        "{}=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0",
+        SESSION_COOKIE
+    );
+
+    Ok((
+        AppendHeaders([(SET_COOKIE, cookie)]),
+        Json(UserLogoutRsp {}),
+    ))
+}
+
+async fn user_me(user: KUser) -> KResponse<User> {
+    KResponse(Ok(user.user))
+}
+
+async fn api_token_creation(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<ApiTokenCreateReq>,
+) -> Result<KResponse<ApiTokenCreateRsp>, KError> {
+    let user = user.session_user()?;
+    Ok(state.api_token_create(&user, req).await.into())
+}
+
+async fn api_token_list(
+    user: KUser,
+    state: State<ShareAppState>,
+) -> Result<KResponse<ApiTokenListRsp>, KError> {
+    let user = user.session_user()?;
+    Ok(state.api_token_list(&user).await.into())
+}
+
+async fn api_token_revocation(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<ApiTokenRevokeReq>,
+) -> Result<KResponse<ApiTokenRevokeRsp>, KError> {
+    let user = user.session_user()?;
+    Ok(state.api_token_revoke(&user, req).await.into())
+}
+
+async fn user_creation(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<UserCreateReq>,
+) -> Result<KResponse<User>, KError> {
+    let user = user.admin_user()?;
+    Ok(state
+        .user_create(Some(&user), &req.username, &req.password, req.admin)
+        .await
+        .into())
+}
+
+/// Any user may list the users to share namespaces with them.
+async fn user_list(state: State<ShareAppState>) -> KResponse<UserListRsp> {
+    state.user_list().await.into()
+}
+
+async fn user_group_creation(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<UserGroupCreateReq>,
+) -> Result<KResponse<UserGroup>, KError> {
+    let user = user.admin_user()?;
+    Ok(state.user_group_create(&user, req).await.into())
+}
+
+async fn user_group_list(state: State<ShareAppState>) -> KResponse<UserGroupListRsp> {
+    state.user_group_list().await.into()
+}
+
+async fn user_group_member_addition(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<UserGroupMemberReq>,
+) -> Result<KResponse<UserGroupMemberRsp>, KError> {
+    let user = user.admin_user()?;
+    Ok(state.user_group_member_add(&user, req).await.into())
+}
+
+async fn user_group_member_removal(
+    user: KUser,
+    state: State<ShareAppState>,
+    Json(req): Json<UserGroupMemberReq>,
+) -> Result<KResponse<UserGroupMemberRsp>, KError> {
+    let user = user.admin_user()?;
+    Ok(state.user_group_member_remove(&user, req).await.into())
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::{
+    model::db::acl::{NamespaceAcl, NamespaceRole},
+    util::namespace::NamespaceTree,
+};
+
+/// The roles of a user by the namespaces they are granted on, the highest
+/// one wins when the user has several.
+#[derive(Debug, Clone, Default)]
+pub struct NamespaceRoles {
+    roles: HashMap<String, NamespaceRole>,
+}
+
+impl NamespaceRoles {
+    pub fn new(acls: &[NamespaceAcl]) -> NamespaceRoles {
+        let mut roles: HashMap<String, NamespaceRole> = HashMap::new();
+        for acl in acls {
+            let role = roles.entry(acl.namespace.clone()).or_insert(acl.role);
+            *role = (*role).max(acl.role);
+        }
+
+        NamespaceRoles { roles }
+    }
+
+    /// The role on the namespace, a role granted on an ancestor applies to
+    /// its descendants too.
+    pub fn role_of(&self, tree: &NamespaceTree, namespace: &str) -> Option<NamespaceRole> {
+        let mut role = None;
+        let mut current = Some(namespace);
+        while let Some(id) = current {
+            role = role.max(self.roles.get(id).copied());
+            current = tree.parent_of(id);
+        }
+        role
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use crate::{
+        model::db::{
+            acl::{AclSubjectKind, NamespaceAcl, NamespaceRole},
+            namespace::{NamespaceRecord, NamespaceRelation},
+        },
+        util::namespace::NamespaceTree,
+    };
+
+    use super::NamespaceRoles;
+
+    fn acl(namespace: &str, role: NamespaceRole) -> NamespaceAcl {
+        NamespaceAcl {
+            id: format!("{}-{}", namespace, role),
+            namespace: namespace.to_owned(),
+            subject_kind: AclSubjectKind::User,
+            subject_id: "u".to_owned(),
+            role,
+            insert_time: Local::now().fixed_offset(),
+        }
+    }
+
+    #[test]
+    fn role_of() {
+        let now = Local::now().fixed_offset();
+        let records: Vec<NamespaceRecord> = ["work", "rust", "private"]
+            .iter()
+            .map(|id| NamespaceRecord {
+                id: id.to_string(),
+                name: id.to_string(),
+                delete_time: None,
+                update_time: None,
+                insert_time: now,
+            })
+            .collect();
+        let relations = vec![NamespaceRelation {
+            id: "r".to_owned(),
+            sub_id: "rust".to_owned(),
+            parent_id: "work".to_owned(),
+            delete_time: None,
+            update_time: None,
+            insert_time: now,
+        }];
+        let tree = NamespaceTree::new(&records, &relations);
+
+        let roles = NamespaceRoles::new(&[
+            acl("work", NamespaceRole::Viewer),
+            acl("rust", NamespaceRole::Editor),
+            acl("work", NamespaceRole::Owner),
+        ]);
+        assert_eq!(roles.role_of(&tree, "work"), Some(NamespaceRole::Owner));
+        // inherited from work, which is higher
+        assert_eq!(roles.role_of(&tree, "rust"), Some(NamespaceRole::Owner));
+        assert_eq!(roles.role_of(&tree, "private"), None);
+
+        let roles = NamespaceRoles::new(&[acl("rust", NamespaceRole::Viewer)]);
+        assert_eq!(roles.role_of(&tree, "work"), None);
+        assert_eq!(roles.role_of(&tree, "rust"), Some(NamespaceRole::Viewer));
+    }
+}
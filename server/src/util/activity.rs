@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Activity of one day, `created` counts the new chnots and `edited` counts
+/// the saved records, a new chnot saves its first record too.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayActivity {
+    pub date: NaiveDate,
+    pub created: u32,
+    pub edited: u32,
+}
+
+/// Count the times by their date at `offset`, ordered by date. The days
+/// without any activity are skipped.
+pub fn count_by_day(
+    created: &[DateTime<FixedOffset>],
+    edited: &[DateTime<FixedOffset>],
+    offset: FixedOffset,
+) -> Vec<DayActivity> {
+    let mut days: BTreeMap<NaiveDate, (u32, u32)> = BTreeMap::new();
+    for time in created {
+        days.entry(time.with_timezone(&offset).date_naive())
+            .or_default()
+            .0 += 1;
+    }
+    for time in edited {
+        days.entry(time.with_timezone(&offset).date_naive())
+            .or_default()
+            .1 += 1;
+    }
+
+    days.into_iter()
+        .map(|(date, (created, edited))| DayActivity {
+            date,
+            created,
+            edited,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset, NaiveDate};
+
+    use super::{count_by_day, DayActivity};
+
+    #[test]
+    fn by_local_day() {
+        let time = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        let created = [time("2025-03-01T23:30:00+00:00")];
+        let edited = [
+            time("2025-03-01T23:30:00+00:00"),
+            time("2025-03-02T01:00:00+08:00"),
+            time("2025-03-05T10:00:00+00:00"),
+        ];
+
+        let day = |d: u32, created, edited| DayActivity {
+            date: NaiveDate::from_ymd_opt(2025, 3, d).unwrap(),
+            created,
+            edited,
+        };
+        assert_eq!(
+            count_by_day(&created, &edited, FixedOffset::east_opt(0).unwrap()),
+            vec![day(1, 1, 2), day(5, 0, 1)]
+        );
+        assert_eq!(
+            count_by_day(&created, &edited, FixedOffset::east_opt(8 * 3600).unwrap()),
+            vec![day(2, 1, 2), day(5, 0, 1)]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, rand_core::RngCore, SaltString},
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use axum::http::Method;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chin_tools::wrapper::anyhow::AResult;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+
+/// Verified for unknown usernames, so a login takes as long whether the
+/// user exists or not.
+pub static DUMMY_PASSWORD_HASH: Lazy<String> =
+    Lazy::new(|| hash_password(&new_token()).expect("unable to hash the dummy password"));
+
+/// The argon2 phc string of the password, with a random salt.
+pub fn hash_password(password: &str) -> AResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("unable to hash password: {}", e))?;
+
+    Ok(hash.to_string())
+}
+
+/// False for a wrong password, an error only for a broken hash.
+pub fn verify_password(password: &str, hash: &str) -> AResult<bool> {
+    let hash =
+        PasswordHash::new(hash).map_err(|e| anyhow::anyhow!("invalid password hash: {}", e))?;
+
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok())
+}
+
+/// A random token given to the client, 32 bytes in url safe base64.
+pub fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Tokens are stored by their sha256, a leaked table does not leak them.
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Api tokens start with this, session tokens never do.
+pub const API_TOKEN_PREFIX: &str = "knt_";
+
+/// Reads sent by POST, their bodies are too large for a query string.
+const POST_READ_ROUTES: &[&str] = &[
+    "/api/v1/chnot-query",
+    "/api/v1/chnot-search",
+    "/api/v1/toent-guess",
+];
+
+/// Writes whose namespaces are in the body instead of the `K-namespace`
+/// header.
+const CROSS_NAMESPACE_ROUTES: &[&str] = &[
+    "/api/v1/namespace",
+    "/api/v1/namespace-rename",
+    "/api/v1/namespace-move",
+    "/api/v1/namespace-acl",
+    "/api/v1/chnot-move",
+];
+
+/// Whether a read-only api token may call the route, `path` is the matched
+/// route like `/api/v1/chnot/{meta_id}/history`.
+pub fn is_read_route(method: &Method, path: &str) -> bool {
+    method == Method::GET
+        || method == Method::HEAD
+        || (method == Method::POST && POST_READ_ROUTES.contains(&path))
+}
+
+/// Whether the route touches namespaces other than the one of the header,
+/// a namespace limited api token must not call it.
+pub fn is_cross_namespace_route(path: &str) -> bool {
+    CROSS_NAMESPACE_ROUTES.contains(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+
+    use super::{
+        hash_password, hash_token, is_cross_namespace_route, is_read_route, new_token,
+        verify_password,
+    };
+
+    #[test]
+    fn password() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_password("correct horse", &hash).unwrap());
+        assert!(!verify_password("battery staple", &hash).unwrap());
+        assert!(verify_password("correct horse", "plain").is_err());
+    }
+
+    #[test]
+    fn token() {
+        let token = new_token();
+        assert_eq!(token.len(), 43);
+        assert_ne!(token, new_token());
+
+        let hash = hash_token(&token);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash_token(&token));
+    }
+
+    #[test]
+    fn routes() {
+        assert!(is_read_route(&Method::GET, "/api/v1/chnot-trash"));
+        assert!(is_read_route(&Method::POST, "/api/v1/chnot-query"));
+        assert!(!is_read_route(&Method::POST, "/api/v1/chnot-update"));
+        assert!(!is_read_route(&Method::PUT, "/api/v1/chnot"));
+
+        assert!(is_cross_namespace_route("/api/v1/chnot-move"));
+        assert!(!is_cross_namespace_route("/api/v1/chnot"));
+    }
+}
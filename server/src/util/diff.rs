@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// One line of a line-level diff, line numbers start from 1.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub content: String,
+}
+
+/// The lcs table of the changed lines is limited to this many cells, about
+/// 32 MiB.
+pub const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Line-level diff between `old` and `new` based on the longest common
+/// subsequence, deletions are placed before insertions in a changed block.
+///
+/// None if the changed lines of both sides need more than `MAX_DIFF_CELLS`.
+pub fn diff_lines(old: &str, new: &str) -> Option<Vec<DiffLine>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // The common head and tail are cheap to match, only the middle part
+    // needs the lcs table.
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    let cells = (old_mid.len() + 1).saturating_mul(new_mid.len() + 1);
+    if cells > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    // lcs[i][j] is the lcs length of old_mid[i..] and new_mid[j..]
+    let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::with_capacity(old.len().max(new.len()));
+    let equal = |result: &mut Vec<DiffLine>, o: usize, n: usize| {
+        result.push(DiffLine {
+            tag: DiffTag::Equal,
+            old_line: Some(o + 1),
+            new_line: Some(n + 1),
+            content: old[o].to_owned(),
+        })
+    };
+
+    for i in 0..prefix {
+        equal(&mut result, i, i);
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            equal(&mut result, prefix + i, prefix + j);
+            i += 1;
+            j += 1;
+        } else if i < old_mid.len() && (j == new_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            result.push(DiffLine {
+                tag: DiffTag::Delete,
+                old_line: Some(prefix + i + 1),
+                new_line: None,
+                content: old_mid[i].to_owned(),
+            });
+            i += 1;
+        } else {
+            result.push(DiffLine {
+                tag: DiffTag::Insert,
+                old_line: None,
+                new_line: Some(prefix + j + 1),
+                content: new_mid[j].to_owned(),
+            });
+            j += 1;
+        }
+    }
+
+    for k in 0..suffix {
+        equal(
+            &mut result,
+            old.len() - suffix + k,
+            new.len() - suffix + k,
+        );
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiffLine, DiffTag};
+
+    fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+        super::diff_lines(old, new).unwrap()
+    }
+
+    fn tags(old: &str, new: &str) -> Vec<(DiffTag, String)> {
+        diff_lines(old, new)
+            .into_iter()
+            .map(|e| (e.tag, e.content))
+            .collect()
+    }
+
+    #[test]
+    fn same_content() {
+        let d = diff_lines("a\nb\nc", "a\nb\nc");
+        assert_eq!(d.len(), 3);
+        assert!(d.iter().all(|e| e.tag == DiffTag::Equal));
+        assert_eq!(d[2].old_line, Some(3));
+        assert_eq!(d[2].new_line, Some(3));
+    }
+
+    #[test]
+    fn changed_line() {
+        assert_eq!(
+            tags("a\nb\nc", "a\nx\nc"),
+            vec![
+                (DiffTag::Equal, "a".to_owned()),
+                (DiffTag::Delete, "b".to_owned()),
+                (DiffTag::Insert, "x".to_owned()),
+                (DiffTag::Equal, "c".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn insert_and_delete() {
+        let d = diff_lines("a\nb\nc\nd", "b\nc\ne\nd\nf");
+        let deleted: Vec<_> = d.iter().filter(|e| e.tag == DiffTag::Delete).collect();
+        let inserted: Vec<_> = d.iter().filter(|e| e.tag == DiffTag::Insert).collect();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].content, "a");
+        assert_eq!(deleted[0].old_line, Some(1));
+        assert_eq!(
+            inserted.iter().map(|e| e.content.as_str()).collect::<Vec<_>>(),
+            vec!["e", "f"]
+        );
+        assert_eq!(inserted[1].new_line, Some(5));
+    }
+
+    #[test]
+    fn empty_side() {
+        assert!(diff_lines("", "").is_empty());
+        assert!(diff_lines("", "a\nb").iter().all(|e| e.tag == DiffTag::Insert));
+        assert!(diff_lines("a\nb", "").iter().all(|e| e.tag == DiffTag::Delete));
+    }
+
+    #[test]
+    fn too_large() {
+        let old: String = (0..3000).map(|i| format!("old {}\n", i)).collect();
+        let new: String = (0..3000).map(|i| format!("new {}\n", i)).collect();
+        assert!(super::diff_lines(&old, &new).is_none());
+
+        // the common lines do not count
+        let head = "same\n".repeat(100_000);
+        let d = diff_lines(&format!("{}a", head), &format!("{}b", head));
+        assert_eq!(d.len(), 100_002);
+    }
+}
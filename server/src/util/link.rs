@@ -0,0 +1,131 @@
+use std::collections::BTreeSet;
+
+/// Longest link target to keep, a longer one is not a title anyway.
+const MAX_TARGET_LEN: usize = 300;
+
+/// Targets of `[[target]]` and `[[target|alias]]`, sorted and deduplicated.
+/// A target is either a chnot id or a chnot title, links in code blocks and
+/// code spans are skipped.
+pub fn extract_links(content: &str) -> Vec<String> {
+    let mut links = BTreeSet::new();
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        // the odd parts split by backticks are code spans
+        for text in line.split('`').step_by(2) {
+            let mut rest = text;
+            while let Some(start) = rest.find("[[") {
+                rest = &rest[start + 2..];
+                let Some(end) = rest.find("]]") else {
+                    break;
+                };
+                let target = rest[..end].split('|').next().unwrap_or_default().trim();
+                if !target.is_empty() && target.chars().count() <= MAX_TARGET_LEN {
+                    links.insert(target.to_owned());
+                }
+                rest = &rest[end + 2..];
+            }
+        }
+    }
+
+    links.into_iter().collect()
+}
+
+/// Ids of the resources embedded by `![alt](id)` or `[text](id)`, sorted and
+/// deduplicated. The target may be the download url of the resource as well.
+pub fn extract_resource_ids(content: &str) -> Vec<String> {
+    let mut ids = BTreeSet::new();
+
+    let mut rest = content;
+    while let Some(start) = rest.find("](") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find(')') else {
+            break;
+        };
+        // a link may have a title after the target
+        let target = rest[..end].split_whitespace().next().unwrap_or_default();
+        if let Some(id) = resource_id_of(target) {
+            ids.insert(id.to_owned());
+        }
+        rest = &rest[end + 1..];
+    }
+
+    ids.into_iter().collect()
+}
+
+/// The resource id of a link target, either the id itself or the download
+/// url of the resource.
+pub fn resource_id_of(target: &str) -> Option<&str> {
+    let id = target
+        .rsplit_once("api/v1/resource/")
+        .map(|(_, id)| id)
+        .unwrap_or(target);
+    is_resource_id(id).then_some(id)
+}
+
+/// A simple uuid with the extension of the uploaded file, if any.
+fn is_resource_id(id: &str) -> bool {
+    let (base, ext) = id.split_once('.').unwrap_or((id, ""));
+    base.len() == 32
+        && base
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        && ext.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// The first non-empty line without heading marks, which `[[title]]` links
+/// to.
+pub fn chnot_title(content: &str) -> &str {
+    content
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chnot_title, extract_links, extract_resource_ids};
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            extract_links("see [[Rust Notes]] and [[0193-abc|the old one]], [[ ]] [[Rust Notes]]"),
+            vec!["0193-abc", "Rust Notes"]
+        );
+        assert_eq!(
+            extract_links("`[[code]]` [[a]]\n```\n[[fenced]]\n```\n[[b]] [[unclosed"),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn resource_ids() {
+        assert_eq!(
+            extract_resource_ids(
+                "![2025](0f8e4c7a1b2d4e6f8a9b0c1d2e3f4a5b.png) [pdf](/api/v1/resource/11111111222233334444555555555555 \"doc\")\n\
+                 [site](https://example.com) ![again](0f8e4c7a1b2d4e6f8a9b0c1d2e3f4a5b.png) [bad](0F8E.png) [open](",
+            ),
+            vec![
+                "0f8e4c7a1b2d4e6f8a9b0c1d2e3f4a5b.png",
+                "11111111222233334444555555555555"
+            ]
+        );
+    }
+
+    #[test]
+    fn title() {
+        assert_eq!(chnot_title("\n## Rust Notes \nbody"), "Rust Notes");
+        assert_eq!(chnot_title("plain line\nbody"), "plain line");
+        assert_eq!(chnot_title("  \n"), "");
+    }
+}
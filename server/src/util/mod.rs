@@ -1 +1,10 @@
+pub mod acl;
+pub mod activity;
+pub mod auth;
+pub mod diff;
+pub mod link;
+pub mod namespace;
+pub mod ratelimit;
+pub mod share;
+pub mod tag;
 pub mod web_util;
@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::db::namespace::{NamespaceRecord, NamespaceRelation};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceNode {
+    pub id: String,
+    pub name: String,
+    pub children: Vec<NamespaceNode>,
+}
+
+/// The namespaces and their parents, a namespace without a parent is a root.
+///
+/// Relations to a missing namespace are ignored, so is a relation that would
+/// close a cycle, the first parent of a namespace wins.
+#[derive(Debug, Clone, Default)]
+pub struct NamespaceTree {
+    names: BTreeMap<String, String>,
+    parents: HashMap<String, String>,
+    children: HashMap<String, Vec<String>>,
+}
+
+impl NamespaceTree {
+    pub fn new(records: &[NamespaceRecord], relations: &[NamespaceRelation]) -> NamespaceTree {
+        let mut tree = NamespaceTree {
+            names: records
+                .iter()
+                .map(|e| (e.id.clone(), e.name.clone()))
+                .collect(),
+            ..Default::default()
+        };
+
+        for relation in relations {
+            let (sub, parent) = (&relation.sub_id, &relation.parent_id);
+            if !tree.contains(sub)
+                || !tree.contains(parent)
+                || tree.parents.contains_key(sub)
+                || tree.is_ancestor_or_self(sub, parent)
+            {
+                continue;
+            }
+            tree.parents.insert(sub.clone(), parent.clone());
+            tree.children
+                .entry(parent.clone())
+                .or_default()
+                .push(sub.clone());
+        }
+
+        tree
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.names.contains_key(id)
+    }
+
+    pub fn parent_of(&self, id: &str) -> Option<&str> {
+        self.parents.get(id).map(|e| e.as_str())
+    }
+
+    pub fn children_of(&self, id: &str) -> &[String] {
+        self.children
+            .get(id)
+            .map(|e| e.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Whether `ancestor` is `id` or one of its ancestors.
+    pub fn is_ancestor_or_self(&self, ancestor: &str, id: &str) -> bool {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.parent_of(id);
+        }
+        false
+    }
+
+    /// The children of `id`, their children and so on, `id` itself is not
+    /// included.
+    pub fn descendants(&self, id: &str) -> Vec<String> {
+        let mut descendants = vec![];
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            for child in self.children_of(id) {
+                descendants.push(child.clone());
+                pending.push(child);
+            }
+        }
+        descendants
+    }
+
+    /// Whether moving `id` under `parent` makes a cycle.
+    pub fn would_cycle(&self, id: &str, parent: &str) -> bool {
+        self.is_ancestor_or_self(id, parent)
+    }
+
+    /// The roots with their descendants, siblings are ordered by name.
+    pub fn nodes(&self) -> Vec<NamespaceNode> {
+        self.visible_nodes(|_| true)
+    }
+
+    /// Like `nodes` with only the namespaces passing `visible`, a visible
+    /// namespace under a hidden parent becomes a root.
+    pub fn visible_nodes(&self, visible: impl Fn(&str) -> bool) -> Vec<NamespaceNode> {
+        let mut roots: Vec<&String> = self
+            .names
+            .keys()
+            .filter(|id| visible(id) && self.parent_of(id).is_none_or(|e| !visible(e)))
+            .collect();
+        roots.sort_by_key(|id| &self.names[*id]);
+        roots
+            .into_iter()
+            .map(|id| self.node(id, &visible))
+            .collect()
+    }
+
+    fn node(&self, id: &str, visible: &impl Fn(&str) -> bool) -> NamespaceNode {
+        let mut children: Vec<&String> = self
+            .children_of(id)
+            .iter()
+            .filter(|id| visible(id))
+            .collect();
+        children.sort_by_key(|id| &self.names[*id]);
+
+        NamespaceNode {
+            id: id.to_owned(),
+            name: self.names[id].clone(),
+            children: children
+                .into_iter()
+                .map(|id| self.node(id, visible))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use crate::model::db::namespace::{NamespaceRecord, NamespaceRelation};
+
+    use super::NamespaceTree;
+
+    fn tree(ids: &[&str], relations: &[(&str, &str)]) -> NamespaceTree {
+        let now = Local::now().fixed_offset();
+        let records: Vec<NamespaceRecord> = ids
+            .iter()
+            .map(|id| NamespaceRecord {
+                id: id.to_string(),
+                name: id.to_uppercase(),
+                delete_time: None,
+                update_time: None,
+                insert_time: now,
+            })
+            .collect();
+        let relations: Vec<NamespaceRelation> = relations
+            .iter()
+            .map(|(sub, parent)| NamespaceRelation {
+                id: format!("{}-{}", sub, parent),
+                sub_id: sub.to_string(),
+                parent_id: parent.to_string(),
+                delete_time: None,
+                update_time: None,
+                insert_time: now,
+            })
+            .collect();
+        NamespaceTree::new(&records, &relations)
+    }
+
+    #[test]
+    fn nodes() {
+        let tree = tree(
+            &["work", "rust", "go", "private"],
+            &[("rust", "work"), ("go", "work"), ("missing", "work")],
+        );
+        let nodes = tree.nodes();
+
+        assert_eq!(
+            nodes.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["private", "work"]
+        );
+        assert_eq!(
+            nodes[1]
+                .children
+                .iter()
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["GO", "RUST"]
+        );
+    }
+
+    #[test]
+    fn visible_nodes() {
+        let tree = tree(
+            &["work", "rust", "async", "go", "private"],
+            &[("rust", "work"), ("async", "rust"), ("go", "work")],
+        );
+        let nodes = tree.visible_nodes(|id| id != "work" && id != "private");
+
+        // the children of the hidden work become roots
+        assert_eq!(
+            nodes.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["go", "rust"]
+        );
+        assert_eq!(nodes[1].children[0].id, "async");
+    }
+
+    #[test]
+    fn cycles() {
+        let tree = tree(
+            &["a", "b", "c", "d"],
+            &[("b", "a"), ("c", "b"), ("a", "c"), ("b", "d")],
+        );
+
+        // the relations closing a cycle or giving a second parent are ignored
+        assert_eq!(tree.parent_of("a"), None);
+        assert_eq!(tree.parent_of("b"), Some("a"));
+        assert!(tree.would_cycle("a", "c"));
+        assert!(tree.would_cycle("a", "a"));
+        assert!(!tree.would_cycle("c", "a"));
+        assert!(!tree.would_cycle("d", "c"));
+    }
+
+    #[test]
+    fn descendants() {
+        let tree = tree(
+            &["work", "rust", "async", "go", "private"],
+            &[("rust", "work"), ("async", "rust"), ("go", "work")],
+        );
+
+        let mut descendants = tree.descendants("work");
+        descendants.sort();
+        assert_eq!(descendants, vec!["async", "go", "rust"]);
+        assert_eq!(tree.descendants("rust"), vec!["async"]);
+        assert!(tree.descendants("private").is_empty());
+        assert!(tree.descendants("missing").is_empty());
+    }
+}
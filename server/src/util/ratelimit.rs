@@ -0,0 +1,92 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The buckets are swept once there are this many, the full ones are the
+/// same as missing ones.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A token bucket per key, every request takes one token and the buckets
+/// refill at `per_second` up to `burst`.
+pub struct RateLimiter {
+    burst: f64,
+    per_second: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, per_second: f64) -> Self {
+        Self {
+            burst: burst.max(1) as f64,
+            per_second: per_second.max(f64::MIN_POSITIVE),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token of the key, or how long to wait for the next one.
+    pub fn acquire(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= SWEEP_THRESHOLD {
+            buckets.retain(|_, bucket| self.refill(bucket, now) < self.burst);
+        }
+
+        let bucket = buckets.entry(key.to_owned()).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        bucket.tokens = self.refill(bucket, now);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.per_second,
+            ))
+        }
+    }
+
+    fn refill(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.per_second).min(self.burst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::RateLimiter;
+
+    #[test]
+    fn bucket() {
+        let limiter = RateLimiter::new(2, 0.5);
+        let now = Instant::now();
+
+        assert!(limiter.acquire("a", now).is_ok());
+        assert!(limiter.acquire("a", now).is_ok());
+        assert_eq!(limiter.acquire("a", now), Err(Duration::from_secs(2)));
+        // another key has its own bucket
+        assert!(limiter.acquire("b", now).is_ok());
+
+        assert_eq!(
+            limiter.acquire("a", now + Duration::from_secs(1)),
+            Err(Duration::from_secs(1))
+        );
+        assert!(limiter.acquire("a", now + Duration::from_secs(2)).is_ok());
+        // never more than the burst
+        let later = now + Duration::from_secs(3600);
+        assert!(limiter.acquire("a", later).is_ok());
+        assert!(limiter.acquire("a", later).is_ok());
+        assert!(limiter.acquire("a", later).is_err());
+    }
+}
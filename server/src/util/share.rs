@@ -0,0 +1,169 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use sha2::Sha256;
+
+use crate::util::link::resource_id_of;
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn share_mac(secret: &str, payload: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac takes keys of any size");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// `{id}.{expire}.{signature}`, the expire time is in unix seconds.
+pub fn sign_share_token(secret: &str, id: &str, expire: i64) -> String {
+    let payload = format!("{}.{}", id, expire);
+    let signature = URL_SAFE_NO_PAD.encode(share_mac(secret, &payload).finalize().into_bytes());
+    format!("{}.{}", payload, signature)
+}
+
+/// The link id of a token signed by the secret which has not expired at
+/// `now`, the link itself may be revoked still.
+pub fn verify_share_token<'a>(secret: &str, token: &'a str, now: i64) -> Option<&'a str> {
+    let (payload, signature) = token.rsplit_once('.')?;
+    let (id, expire) = payload.split_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    share_mac(secret, payload).verify_slice(&signature).ok()?;
+
+    (expire.parse::<i64>().ok()? > now).then_some(id)
+}
+
+/// Relative urls and the common schemes, no `javascript:` and the like.
+fn is_safe_url(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains('/') => {
+            ["http", "https", "mailto"].contains(&scheme.to_ascii_lowercase().as_str())
+        }
+        _ => true,
+    }
+}
+
+fn share_url<'a>(url: CowStr<'a>, resource_prefix: &str) -> CowStr<'a> {
+    if let Some(id) = resource_id_of(&url) {
+        return format!("{}/{}", resource_prefix, id).into();
+    }
+    if is_safe_url(&url) {
+        url
+    } else {
+        "#".into()
+    }
+}
+
+/// The markdown as html for the public share page. Raw html is shown as
+/// text, resources are linked under `resource_prefix`.
+pub fn render_markdown(content: &str, resource_prefix: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(content, options).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: share_url(dest_url, resource_prefix),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: share_url(dest_url, resource_prefix),
+            title,
+            id,
+        }),
+        event => event,
+    });
+
+    let mut out = String::with_capacity(content.len() * 3 / 2);
+    html::push_html(&mut out, events);
+    out
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A standalone page around the rendered `body`.
+pub fn share_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>{title}</title>
+<style>
+body {{ max-width: 48rem; margin: 2rem auto; padding: 0 1rem; font-family: sans-serif; line-height: 1.6; }}
+img {{ max-width: 100%; }}
+pre {{ overflow-x: auto; padding: 0.5rem; background: #f5f5f5; }}
+.record {{ border-top: 1px solid #ddd; padding-top: 0.5rem; }}
+.role {{ color: #888; font-size: 0.85rem; }}
+</style>
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        title = escape_html(title),
+        body = body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_markdown, share_page, sign_share_token, verify_share_token};
+
+    #[test]
+    fn token() {
+        let token = sign_share_token("secret", "0193-abc", 1000);
+        assert!(token.starts_with("0193-abc.1000."));
+        assert_eq!(verify_share_token("secret", &token, 999), Some("0193-abc"));
+        // expired
+        assert_eq!(verify_share_token("secret", &token, 1000), None);
+        // another secret
+        assert_eq!(verify_share_token("other", &token, 999), None);
+        // a later expire time with the old signature
+        let forged = token.replacen(".1000.", ".9000.", 1);
+        assert_eq!(verify_share_token("secret", &forged, 999), None);
+        assert_eq!(verify_share_token("secret", "garbage", 999), None);
+    }
+
+    #[test]
+    fn markdown() {
+        let html = render_markdown(
+            "# Title\n\n![cat](0f8e4c7a1b2d4e6f8a9b0c1d2e3f4a5b.png) <script>alert(1)</script> [x](javascript:alert(1)) [site](https://example.com)",
+            "/s/token/resource",
+        );
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains(r#"src="/s/token/resource/0f8e4c7a1b2d4e6f8a9b0c1d2e3f4a5b.png""#));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains(r#"href="https://example.com""#));
+
+        assert!(share_page("<b>", "<p>body</p>").contains("<title>&lt;b&gt;</title>"));
+    }
+}
@@ -0,0 +1,152 @@
+use std::{collections::BTreeSet, ops::Range};
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// A tag must not stick to a word or a path, like `abc#1` or `/#anchor`.
+fn can_start_tag(prev: Option<char>) -> bool {
+    prev.is_none_or(|c| {
+        !(c.is_ascii_alphanumeric() || matches!(c, '#' | '&' | '/' | '_' | '-' | '\\'))
+    })
+}
+
+/// Byte ranges of `#tag` in the content, including the leading `#`.
+///
+/// Tags in code blocks and code spans are skipped, a heading like `# Title`
+/// is not a tag, and a tag with only digits like `#123` is not either.
+fn tag_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut in_fence = false;
+    let mut line_start = 0;
+
+    for line in content.split_inclusive('\n') {
+        let offset = line_start;
+        line_start += line.len();
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut prev = None;
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && can_start_tag(prev) {
+                let mut end = i + 1;
+                while let Some(&(j, n)) = chars.peek() {
+                    if !is_tag_char(n) {
+                        break;
+                    }
+                    end = j + n.len_utf8();
+                    prev = Some(n);
+                    chars.next();
+                }
+
+                let tag = line[i + 1..end].trim_end_matches('/');
+                if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) {
+                    ranges.push(offset + i..offset + i + 1 + tag.len());
+                }
+                continue;
+            }
+            prev = Some(c);
+        }
+    }
+
+    ranges
+}
+
+/// Tags of the content without `#`, sorted and deduplicated. A nested tag
+/// like `#proj/chnots` is kept as it is.
+pub fn extract_tags(content: &str) -> Vec<String> {
+    tag_ranges(content)
+        .into_iter()
+        .map(|r| content[r.start + 1..r.end].to_owned())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+/// Whether `tag` is `parent` or nested in it.
+pub fn is_tag_of(tag: &str, parent: &str) -> bool {
+    tag.strip_prefix(parent)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Whether `tag` can be written as `#tag` and read back as the same tag.
+pub fn is_valid_tag(tag: &str) -> bool {
+    extract_tags(&format!("#{}", tag)) == [tag]
+}
+
+/// Replace the tag `from` and its nested tags with `to`, returns `None` if
+/// the content has no such tag.
+pub fn replace_tag(content: &str, from: &str, to: &str) -> Option<String> {
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+
+    for range in tag_ranges(content) {
+        let tag = &content[range.start + 1..range.end];
+        if is_tag_of(tag, from) {
+            result.push_str(&content[last..range.start]);
+            result.push('#');
+            result.push_str(to);
+            result.push_str(&tag[from.len()..]);
+            last = range.end;
+        }
+    }
+
+    if last == 0 {
+        return None;
+    }
+    result.push_str(&content[last..]);
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_tags, is_tag_of, is_valid_tag, replace_tag};
+
+    #[test]
+    fn extract() {
+        assert_eq!(
+            extract_tags("#rust is fun, #proj/chnots/ and #笔记。\n(#rust)"),
+            vec!["proj/chnots", "rust", "笔记"]
+        );
+        assert_eq!(
+            extract_tags("# Title\n## Sub\nissue #123, a#b, http://x/#anchor &#39;"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn skip_code() {
+        let content = "`#inline` #real\n```rust\n#[derive(Debug)]\n```\n~~~\n#fenced\n~~~\n#after";
+        assert_eq!(extract_tags(content), vec!["after", "real"]);
+    }
+
+    #[test]
+    fn nested() {
+        assert!(is_tag_of("proj", "proj"));
+        assert!(is_tag_of("proj/chnots", "proj"));
+        assert!(!is_tag_of("project", "proj"));
+        assert!(is_valid_tag("proj/chnots"));
+        assert!(!is_valid_tag("a b"));
+        assert!(!is_valid_tag("123"));
+    }
+
+    #[test]
+    fn replace() {
+        assert_eq!(
+            replace_tag("#proj and #proj/chnots, #project `#proj`", "proj", "work"),
+            Some("#work and #work/chnots, #project `#proj`".to_owned())
+        );
+        assert_eq!(replace_tag("#rust", "go", "zig"), None);
+    }
+}
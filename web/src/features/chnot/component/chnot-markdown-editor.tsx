@@ -31,7 +31,7 @@ export const ChnotMarkdownEditor = ({ className }: { className?: string }) => {
     setCurrentChnot,
     overwriteChnot,
     queryChnot,
-    updateChnot,
+    moveChnot,
     validateChnotCache,
   } = useChnotStore();
 
@@ -125,10 +125,9 @@ export const ChnotMarkdownEditor = ({ className }: { className?: string }) => {
           <NamespaceSelect
             onSelect={(ns) => {
               if (currentChnot) {
-                updateChnot({
-                  meta_id: currentChnot.meta.id,
-                  update_time: false,
-                  namespace: ns,
+                moveChnot({
+                  target_namespace: ns,
+                  meta_ids: [currentChnot.meta.id],
                 }).then((_) => {
                   if (ns !== currentNamespace.name) {
                     validateChnotCache([currentChnot.meta.id]);
@@ -73,14 +73,17 @@ export interface ChnotDeletionReq {
 export interface ChnotUpdateReq {
   meta_id: string;
 
-  namespace?: string;
-
   pinned?: boolean;
   archive?: boolean;
 
   update_time: boolean;
 }
 
+export interface ChnotMoveReq {
+  target_namespace: string;
+  meta_ids: string[];
+}
+
 export interface ChnotCommentAddReq {
   id: string;
 
@@ -203,6 +206,9 @@ export const useChnotStore = create(
     updateChnot: async (req: ChnotUpdateReq) => {
       return request.post(`api/v1/chnot-update`, req);
     },
+    moveChnot: async (req: ChnotMoveReq) => {
+      return request.post(`api/v1/chnot-move`, req);
+    },
     setCurrentChnot: (chnot?: Chnot) => {
       set((state) => {
         return { ...state, currentChnotIndex: chnot?.meta.id };